@@ -1,12 +1,13 @@
-use crate::agent_pure::parse_analysis;
+use crate::agent_pure::parse_analysis_for_language;
+use crate::error::{AgentError, AgentResult};
 use crate::vision_model::{
     CONFIG_VISION_MODEL_ID, LLAMA_CHAT_MODEL_ID, VISION_GGUF_FILENAME, VISION_MMPROJ_FILENAME,
     VISION_STATUS_LABEL,
 };
 use serde::{Deserialize, Serialize};
-use std::sync::Mutex;
+use std::sync::{Mutex, Once};
 use std::path::{Path, PathBuf};
-use tauri::State;
+use tauri::{Emitter, State};
 use base64::{Engine as _, engine::general_purpose::STANDARD as BASE64};
 use chrono::{Datelike, Local};
 use rusqlite::{Connection, params};
@@ -22,12 +23,37 @@ pub struct ActivityReport {
     pub description: String,
     pub activity_type: String,
     pub synced: bool,
+    /// Input intensity over the capture window (`none`/`low`/`medium`/`high`); `None` when
+    /// input tracking is off. Derived from event counts only — see `input_activity`.
+    #[serde(default)]
+    pub activity_level: Option<String>,
+    /// Free-form integration data (`reports.metadata`, a JSON object); see `normalize_report_metadata`.
+    #[serde(default)]
+    pub metadata: Option<serde_json::Value>,
+}
+
+/// `reports.metadata` column → JSON; unparseable legacy text is dropped rather than failing the row.
+fn metadata_from_column(raw: Option<String>) -> Option<serde_json::Value> {
+    raw.and_then(|t| serde_json::from_str(&t).ok())
+}
+
+/// One `get_activity_log` page. `limit` is the page size actually used; `capped` is set when
+/// the requested `limit` was above `activityLogMaxLimit`.
+#[derive(Serialize, Debug)]
+pub struct ActivityLogPage {
+    pub reports: Vec<ActivityReport>,
+    pub limit: u32,
+    pub capped: bool,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, Default)]
 pub struct AgentConfig {
     #[serde(rename = "devName")]
     pub dev_name: Option<String>,
+    /// Friendly machine name ("MacBook-Pro"), so two "Alex"es — or one Alex on two machines —
+    /// can be told apart. Defaults to the OS device name.
+    #[serde(rename = "deviceName")]
+    pub device_name: Option<String>,
     #[serde(rename = "captureInterval")]
     pub capture_interval: Option<u64>,
     #[serde(rename = "visionModel")]
@@ -38,6 +64,227 @@ pub struct AgentConfig {
     pub gpu_layers: Option<i32>,
     #[serde(rename = "dailyGoalHours")]
     pub daily_goal_hours: Option<f64>,
+    /// `None` => keep local history forever. `Some(n)` => daily sweep deletes reports older than `n` days.
+    #[serde(rename = "retentionDays")]
+    pub retention_days: Option<u32>,
+    /// Local hour (0-23) at which the retention sweep runs.
+    #[serde(rename = "retentionSweepHour")]
+    pub retention_sweep_hour: Option<u32>,
+    /// `None` => unbounded offline queue. `Some(n)` => keep at most `n` unsynced reports,
+    /// dropping the oldest ones first.
+    #[serde(rename = "maxUnsyncedReports")]
+    pub max_unsynced_reports: Option<u32>,
+    /// Proxy for cloud calls (`http://host:port`). `None` => `HTTP_PROXY` env / direct.
+    #[serde(rename = "httpProxy")]
+    pub http_proxy: Option<String>,
+    #[serde(rename = "httpsProxy")]
+    pub https_proxy: Option<String>,
+    /// Per-request timeout for the local vision call. Slow CPU-only boxes need minutes; on a fast
+    /// GPU a lower value makes a wedged server fail fast instead of stalling the capture loop.
+    #[serde(rename = "visionTimeoutSecs")]
+    pub vision_timeout_secs: Option<u64>,
+    /// Sampling temperature for the vision call (0-1). Lower = terser, more deterministic.
+    #[serde(rename = "visionTemperature")]
+    pub vision_temperature: Option<f32>,
+    /// Max generated tokens per vision call (1-1024). Lower = faster, shorter descriptions.
+    #[serde(rename = "visionMaxTokens")]
+    pub vision_max_tokens: Option<u32>,
+    /// Vision answers shorter than this many characters ("Desktop.") are retried once, then
+    /// recorded from the window title instead (0-500, `0` = accept anything).
+    #[serde(rename = "minDescriptionLength")]
+    pub min_description_length: Option<u32>,
+    /// Opt-in keyboard/mouse activity level. Counts key presses and clicks only;
+    /// no keystroke content, key codes or positions are ever recorded.
+    #[serde(rename = "inputActivityTracking")]
+    pub input_activity_tracking: Option<bool>,
+    /// Master switch: `false` => no screenshot is ever taken, whatever triggers the capture.
+    #[serde(rename = "enableScreenCapture")]
+    pub enable_screen_capture: Option<bool>,
+    /// Upper bound on `get_activity_log`'s `limit` (1-5000, default 500).
+    #[serde(rename = "activityLogMaxLimit")]
+    pub activity_log_max_limit: Option<u32>,
+    /// Convert frames to grayscale before the vision call (smaller PNG; sometimes crisper text).
+    #[serde(rename = "imageGrayscale")]
+    pub image_grayscale: Option<bool>,
+    /// Contrast adjustment in percent (-100..100, `0` = off) before the vision call.
+    #[serde(rename = "imageContrast")]
+    pub image_contrast: Option<f32>,
+    /// Unsharp-mask frames before the vision call.
+    #[serde(rename = "imageSharpen")]
+    pub image_sharpen: Option<bool>,
+    /// Personal time tracking only: never contacts FlowSight cloud, Jira / Linear or an external
+    /// analysis API. The embedded local vision server keeps working.
+    #[serde(rename = "localOnly")]
+    pub local_only: Option<bool>,
+    /// Random ± spread (percent, 0-50, default 10) on each capture wait so a team on the same
+    /// interval does not capture and sync at the same instants.
+    #[serde(rename = "captureJitterPercent")]
+    pub capture_jitter_percent: Option<u32>,
+    /// Base64 payload limit for the vision call; larger frames are sent at half size as JPEG.
+    #[serde(rename = "maxImageBytes")]
+    pub max_image_bytes: Option<u64>,
+    /// Capture interval (ms) while running on battery; `None` => same as `captureInterval`.
+    #[serde(rename = "captureIntervalOnBattery")]
+    pub capture_interval_on_battery: Option<u64>,
+    /// On battery, record app / window context only and skip the screenshot + vision call.
+    #[serde(rename = "skipVisionOnBattery")]
+    pub skip_vision_on_battery: Option<bool>,
+    /// `"vision"` (screenshot + model), `"window_only"` (app / window title only, no screenshot)
+    /// or `"hybrid"` (window info while the screen is unchanged, vision when it changed).
+    #[serde(rename = "analysisMode")]
+    pub analysis_mode: Option<String>,
+    /// `"interval"` (fixed timer) or `"on_change"` (analyze only when the screen changed).
+    #[serde(rename = "captureMode")]
+    pub capture_mode: Option<String>,
+    /// `"screen"` (primary display) or `"active_window"` (foreground window only).
+    #[serde(rename = "captureTarget")]
+    pub capture_target: Option<String>,
+    /// Index into `list_screens` of the display to capture. `None` => first display.
+    #[serde(rename = "captureMonitor")]
+    pub capture_monitor: Option<u32>,
+    /// Language of the vision descriptions (`"en"`, `"es"`, `"de"`). Also selects the extra
+    /// keyword set used when the model omits a CATEGORY line.
+    #[serde(rename = "reportLanguage")]
+    pub report_language: Option<String>,
+    /// `"local"` (embedded llama-server) or `"openai_compatible"` (`apiBase` + `apiKey` + `apiModel`).
+    #[serde(rename = "analysisBackend")]
+    pub analysis_backend: Option<String>,
+    /// Base URL of the OpenAI-compatible endpoint, e.g. `http://localhost:1234` (`/v1` optional).
+    #[serde(rename = "apiBase")]
+    pub api_base: Option<String>,
+    /// Sent as `Authorization: Bearer ...`; `None` for servers without auth (LM Studio, vLLM).
+    /// Write-only from the webview: never serialized back (see `ConfigView::api_key_set`).
+    #[serde(rename = "apiKey", skip_serializing)]
+    pub api_key: Option<String>,
+    /// `model` sent to the external endpoint.
+    #[serde(rename = "apiModel")]
+    pub api_model: Option<String>,
+}
+
+impl AgentConfig {
+    /// Values used on first run and by `reset_config_value`.
+    pub(crate) fn initial() -> Self {
+        Self {
+            dev_name: Some(whoami::realname()),
+            device_name: Some(whoami::devicename()),
+            capture_interval: Some(60000),
+            vision_model: Some(CONFIG_VISION_MODEL_ID.to_string()),
+            // -1 = automatic tier probing (maximum compatibility + strongest profile that survives).
+            gpu_layers: Some(-1),
+            daily_goal_hours: Some(6.0),
+            retention_days: None,
+            retention_sweep_hour: Some(crate::retention::RETENTION_SWEEP_HOUR_DEFAULT),
+            max_unsynced_reports: None,
+            http_proxy: None,
+            https_proxy: None,
+            vision_timeout_secs: Some(VISION_TIMEOUT_SECS_DEFAULT),
+            vision_temperature: Some(VISION_TEMPERATURE_DEFAULT),
+            vision_max_tokens: Some(VISION_MAX_TOKENS_DEFAULT),
+            min_description_length: Some(MIN_DESCRIPTION_LENGTH_DEFAULT),
+            input_activity_tracking: Some(false),
+            enable_screen_capture: Some(true),
+            activity_log_max_limit: Some(crate::agent_pure::ACTIVITY_LOG_MAX_LIMIT_DEFAULT),
+            image_grayscale: Some(false),
+            image_contrast: Some(0.0),
+            image_sharpen: Some(false),
+            local_only: Some(false),
+            capture_jitter_percent: Some(crate::agent_pure::CAPTURE_JITTER_PERCENT_DEFAULT),
+            max_image_bytes: Some(crate::capture_target::MAX_IMAGE_BYTES_DEFAULT),
+            capture_interval_on_battery: None,
+            skip_vision_on_battery: Some(false),
+            analysis_mode: Some(crate::agent_pure::ANALYSIS_MODE_VISION.to_string()),
+            capture_mode: Some(crate::screen_change::CAPTURE_MODE_INTERVAL.to_string()),
+            capture_target: Some(crate::capture_target::CAPTURE_TARGET_SCREEN.to_string()),
+            capture_monitor: None,
+            report_language: Some(crate::agent_pure::REPORT_LANGUAGE_DEFAULT.to_string()),
+            analysis_backend: Some(crate::analysis_backend::ANALYSIS_BACKEND_LOCAL.to_string()),
+            api_base: None,
+            api_key: None,
+            api_model: None,
+        }
+    }
+}
+
+/// Matches the previous hardcoded value (CPU-only first inference can take several minutes).
+pub(crate) const VISION_TIMEOUT_SECS_DEFAULT: u64 = 300;
+const VISION_TIMEOUT_SECS_RANGE: std::ops::RangeInclusive<u64> = 10..=900;
+pub(crate) const VISION_TEMPERATURE_DEFAULT: f32 = 0.1;
+pub(crate) const VISION_MAX_TOKENS_DEFAULT: u32 = 800;
+const VISION_MAX_TOKENS_RANGE: std::ops::RangeInclusive<u32> = 1..=1024;
+pub(crate) const MIN_DESCRIPTION_LENGTH_DEFAULT: u32 = 20;
+const MIN_DESCRIPTION_LENGTH_MAX: u32 = 500;
+
+/// Per-call knobs for the local vision request, resolved from `AgentConfig` with defaults.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct VisionParams {
+    pub timeout_secs: u64,
+    pub temperature: f32,
+    pub max_tokens: u32,
+    /// `minDescriptionLength` in characters of described content (`agent_pure::description_chars`).
+    pub min_description_chars: usize,
+    /// Normalized `reportLanguage` code.
+    pub language: &'static str,
+}
+
+impl VisionParams {
+    pub(crate) fn from_config(c: Option<&AgentConfig>) -> Self {
+        Self {
+            timeout_secs: c
+                .and_then(|c| c.vision_timeout_secs)
+                .unwrap_or(VISION_TIMEOUT_SECS_DEFAULT),
+            temperature: c
+                .and_then(|c| c.vision_temperature)
+                .unwrap_or(VISION_TEMPERATURE_DEFAULT),
+            max_tokens: c
+                .and_then(|c| c.vision_max_tokens)
+                .unwrap_or(VISION_MAX_TOKENS_DEFAULT),
+            min_description_chars: c
+                .and_then(|c| c.min_description_length)
+                .unwrap_or(MIN_DESCRIPTION_LENGTH_DEFAULT) as usize,
+            language: c
+                .and_then(|c| c.report_language.as_deref())
+                .and_then(|l| crate::agent_pure::normalize_report_language(l).ok())
+                .unwrap_or(crate::agent_pure::REPORT_LANGUAGE_DEFAULT),
+        }
+    }
+}
+
+fn validate_vision_temperature(t: f32) -> Result<f32, String> {
+    if (0.0..=1.0).contains(&t) {
+        Ok(t)
+    } else {
+        Err(format!("visionTemperature must be between 0 and 1 (got {})", t))
+    }
+}
+
+fn validate_vision_max_tokens(n: u32) -> Result<u32, String> {
+    if VISION_MAX_TOKENS_RANGE.contains(&n) {
+        Ok(n)
+    } else {
+        Err(format!("visionMaxTokens must be between 1 and 1024 (got {})", n))
+    }
+}
+
+fn validate_min_description_length(n: u32) -> Result<u32, String> {
+    if n <= MIN_DESCRIPTION_LENGTH_MAX {
+        Ok(n)
+    } else {
+        Err(format!(
+            "minDescriptionLength must be between 0 and {} (got {})",
+            MIN_DESCRIPTION_LENGTH_MAX, n
+        ))
+    }
+}
+
+/// Bounds for `captureIntervalOnBattery` (15 s .. 1 h).
+const CAPTURE_INTERVAL_MS_RANGE: std::ops::RangeInclusive<u64> = 15_000..=3_600_000;
+
+fn validate_capture_interval_ms(ms: u64) -> Result<u64, String> {
+    if CAPTURE_INTERVAL_MS_RANGE.contains(&ms) {
+        Ok(ms)
+    } else {
+        Err(format!("captureIntervalOnBattery must be between 15000 and 3600000 ms (got {})", ms))
+    }
 }
 
 pub struct FlowSightAgent {
@@ -45,6 +292,62 @@ pub struct FlowSightAgent {
     pub is_running: bool,
     pub reports_sent: u32,
     pub db_path: PathBuf,
+    pub(crate) summary_cache: SummaryCache,
+}
+
+/// The renderer polls the Today view every minute (and again on every tab switch); summaries
+/// are rebuilt at most this often, or right after a local write.
+const SUMMARY_CACHE_TTL: std::time::Duration = std::time::Duration::from_secs(10);
+
+/// One cached summary, valid for `day` (local `YYYY-MM-DD`) until `SUMMARY_CACHE_TTL` elapses.
+pub(crate) struct CachedSummary<T> {
+    slot: Option<(std::time::Instant, String, T)>,
+}
+
+impl<T> Default for CachedSummary<T> {
+    fn default() -> Self {
+        Self { slot: None }
+    }
+}
+
+impl<T: Clone> CachedSummary<T> {
+    fn fresh(&self, day: &str) -> Option<T> {
+        self.fresh_at(day, std::time::Instant::now())
+    }
+
+    fn fresh_at(&self, day: &str, now: std::time::Instant) -> Option<T> {
+        let (at, cached_day, value) = self.slot.as_ref()?;
+        (cached_day == day && now.saturating_duration_since(*at) < SUMMARY_CACHE_TTL).then(|| value.clone())
+    }
+
+    fn store(&mut self, day: String, value: T) {
+        self.slot = Some((std::time::Instant::now(), day, value));
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct SummaryCache {
+    today: CachedSummary<TodayHistory>,
+    week: CachedSummary<WeekSummary>,
+    /// `retention::reports_deleted_generation()` when the summaries were last checked.
+    deletions_seen: u64,
+}
+
+impl SummaryCache {
+    /// Call after any write to `reports`.
+    pub(crate) fn invalidate(&mut self) {
+        self.today = CachedSummary::default();
+        self.week = CachedSummary::default();
+    }
+
+    /// Drops both summaries if retention deleted reports since the last check.
+    fn drop_if_reports_deleted(&mut self) {
+        let generation = crate::retention::reports_deleted_generation();
+        if generation != self.deletions_seen {
+            self.invalidate();
+            self.deletions_seen = generation;
+        }
+    }
 }
 
 impl Default for FlowSightAgent {
@@ -55,10 +358,7 @@ impl FlowSightAgent {
     pub fn new() -> Self {
         let db_path = crate::paths::db_path().unwrap_or_else(|e| {
             log::error!("[Agent] paths::db_path unavailable ({}); using cwd fallback.", e);
-            dirs::data_local_dir()
-                .unwrap_or_else(|| PathBuf::from("."))
-                .join("FlowSight")
-                .join("dev-agent.db")
+            crate::paths::db_path_or_fallback()
         });
 
         if let Some(parent) = db_path.parent() {
@@ -66,29 +366,33 @@ impl FlowSightAgent {
         }
         
         let mut agent = Self {
-            config: AgentConfig {
-                dev_name: Some(whoami::realname()),
-                capture_interval: Some(60000),
-                vision_model: Some(CONFIG_VISION_MODEL_ID.to_string()),
-                // -1 = automatic tier probing (maximum compatibility + strongest profile that survives).
-                gpu_layers: Some(-1),
-                daily_goal_hours: Some(6.0),
-            },
+            config: AgentConfig::initial(),
             is_running: false,
             reports_sent: 0,
             db_path,
+            summary_cache: SummaryCache::default(),
         };
         
         agent.init_db();
         agent.load_config();
-        
-        // Start Background Sync (10m interval)
-        crate::sync::start_sync_thread(agent.db_path.clone());
-        // Proactive Supabase JWT refresh (~every 2m when near expiry)
-        crate::sync::start_token_refresh_thread(agent.db_path.clone());
+        agent.apply_runtime_settings();
         
         agent
     }
+
+    /// Background loops; they reopen the DB by path on every pass, so they survive a
+    /// `factory_reset` and are started only once per process (from `initialize_agent`).
+    fn start_background_threads(&self) {
+        static BACKGROUND_THREADS: Once = Once::new();
+        BACKGROUND_THREADS.call_once(|| {
+            // Start Background Sync (10m interval)
+            crate::sync::start_sync_thread(self.db_path.clone());
+            // Proactive Supabase JWT refresh (~every 2m when near expiry)
+            crate::sync::start_token_refresh_thread(self.db_path.clone());
+            // Daily local-history retention sweep (no-op until retention_days is set)
+            crate::retention::start_retention_thread(self.db_path.clone());
+        });
+    }
     
     fn init_db(&self) {
         match Connection::open(&self.db_path) {
@@ -101,6 +405,22 @@ impl FlowSightAgent {
                         activity_type TEXT,
                         synced INTEGER DEFAULT 0,
                         created_at TEXT DEFAULT CURRENT_TIMESTAMP
+                     );
+                     CREATE TABLE IF NOT EXISTS blockers (
+                        id INTEGER PRIMARY KEY,
+                        first_report_id INTEGER UNIQUE,
+                        last_report_id INTEGER,
+                        occurrences INTEGER,
+                        duration_seconds INTEGER,
+                        summary TEXT,
+                        started_at TEXT DEFAULT CURRENT_TIMESTAMP,
+                        last_seen_at TEXT DEFAULT CURRENT_TIMESTAMP,
+                        resolved_at TEXT
+                     );
+                     CREATE TABLE IF NOT EXISTS category_display (
+                        category TEXT PRIMARY KEY,
+                        label TEXT,
+                        color TEXT
                      );",
                 ) {
                     log::error!(
@@ -114,6 +434,8 @@ impl FlowSightAgent {
                     "ALTER TABLE reports ADD COLUMN duration_seconds INTEGER DEFAULT 30",
                     [],
                 );
+                let _ = conn.execute("ALTER TABLE reports ADD COLUMN activity_level TEXT", []);
+                let _ = conn.execute("ALTER TABLE reports ADD COLUMN metadata TEXT", []);
             }
             Err(e) => log::error!(
                 "[Agent] SQLite open failed {:?} (init_db): {}",
@@ -134,7 +456,18 @@ impl FlowSightAgent {
 
         for (key, field) in [
             ("dev_name", &mut self.config.dev_name),
+            ("device_name", &mut self.config.device_name),
             ("vision_model", &mut self.config.vision_model),
+            ("http_proxy", &mut self.config.http_proxy),
+            ("https_proxy", &mut self.config.https_proxy),
+            ("capture_mode", &mut self.config.capture_mode),
+            ("analysis_mode", &mut self.config.analysis_mode),
+            ("report_language", &mut self.config.report_language),
+            ("capture_target", &mut self.config.capture_target),
+            ("analysis_backend", &mut self.config.analysis_backend),
+            ("api_base", &mut self.config.api_base),
+            ("api_key", &mut self.config.api_key),
+            ("api_model", &mut self.config.api_model),
         ] {
             if let Ok(val) = conn.query_row::<String, _, _>(
                 "SELECT value FROM config WHERE key = ?",
@@ -155,6 +488,77 @@ impl FlowSightAgent {
             }
         }
 
+        if let Ok(val) = conn.query_row::<String, _, _>(
+            "SELECT value FROM config WHERE key = 'vision_timeout_secs'",
+            [],
+            |r| r.get(0),
+        ) {
+            if let Ok(parsed) = val.parse::<u64>() {
+                self.config.vision_timeout_secs = Some(
+                    parsed.clamp(*VISION_TIMEOUT_SECS_RANGE.start(), *VISION_TIMEOUT_SECS_RANGE.end()),
+                );
+            }
+        }
+
+        if let Ok(val) = conn.query_row::<String, _, _>(
+            "SELECT value FROM config WHERE key = 'vision_temperature'",
+            [],
+            |r| r.get(0),
+        ) {
+            if let Ok(parsed) = val.parse::<f32>() {
+                self.config.vision_temperature = Some(parsed.clamp(0.0, 1.0));
+            }
+        }
+
+        if let Ok(val) = conn.query_row::<String, _, _>(
+            "SELECT value FROM config WHERE key = 'vision_max_tokens'",
+            [],
+            |r| r.get(0),
+        ) {
+            if let Ok(parsed) = val.parse::<u32>() {
+                self.config.vision_max_tokens = Some(
+                    parsed.clamp(*VISION_MAX_TOKENS_RANGE.start(), *VISION_MAX_TOKENS_RANGE.end()),
+                );
+            }
+        }
+
+        if let Ok(val) = conn.query_row::<String, _, _>(
+            "SELECT value FROM config WHERE key = 'min_description_length'",
+            [],
+            |r| r.get(0),
+        ) {
+            if let Ok(parsed) = val.parse::<u32>() {
+                self.config.min_description_length = Some(parsed.min(MIN_DESCRIPTION_LENGTH_MAX));
+            }
+        }
+
+        if let Ok(val) = conn.query_row::<String, _, _>(
+            "SELECT value FROM config WHERE key = 'input_activity_tracking'",
+            [],
+            |r| r.get(0),
+        ) {
+            self.config.input_activity_tracking = Some(val == "true");
+        }
+
+        if let Ok(val) = conn.query_row::<String, _, _>(
+            "SELECT value FROM config WHERE key = 'enable_screen_capture'",
+            [],
+            |r| r.get(0),
+        ) {
+            self.config.enable_screen_capture = Some(val != "false");
+        }
+
+        if let Ok(val) = conn.query_row::<String, _, _>(
+            "SELECT value FROM config WHERE key = 'activity_log_max_limit'",
+            [],
+            |r| r.get(0),
+        ) {
+            if let Ok(parsed) = val.parse::<u32>() {
+                let range = crate::agent_pure::ACTIVITY_LOG_MAX_LIMIT_RANGE;
+                self.config.activity_log_max_limit = Some(parsed.clamp(*range.start(), *range.end()));
+            }
+        }
+
         if let Ok(val) = conn.query_row::<String, _, _>(
             "SELECT value FROM config WHERE key = 'daily_goal_hours'",
             [],
@@ -164,6 +568,128 @@ impl FlowSightAgent {
                 self.config.daily_goal_hours = Some(parsed.clamp(0.0, 24.0));
             }
         }
+
+        let (retention_days, sweep_hour) = crate::retention::load_retention_settings(&conn);
+        self.config.retention_days = retention_days;
+        self.config.retention_sweep_hour = Some(sweep_hour);
+        self.config.max_unsynced_reports = crate::retention::load_max_unsynced(&conn);
+
+        if let Ok(val) = conn.query_row::<String, _, _>(
+            "SELECT value FROM config WHERE key = 'capture_monitor'",
+            [],
+            |r| r.get(0),
+        ) {
+            self.config.capture_monitor = val.parse::<u32>().ok();
+        }
+
+        for (key, field) in [
+            ("image_grayscale", &mut self.config.image_grayscale),
+            ("image_sharpen", &mut self.config.image_sharpen),
+            ("skip_vision_on_battery", &mut self.config.skip_vision_on_battery),
+            ("local_only", &mut self.config.local_only),
+        ] {
+            if let Ok(val) = conn.query_row::<String, _, _>(
+                "SELECT value FROM config WHERE key = ?",
+                [key],
+                |r| r.get(0),
+            ) {
+                *field = Some(val == "true");
+            }
+        }
+        if let Ok(val) = conn.query_row::<String, _, _>(
+            "SELECT value FROM config WHERE key = 'image_contrast'",
+            [],
+            |r| r.get(0),
+        ) {
+            if let Ok(parsed) = val.parse::<f32>() {
+                let range = crate::capture_target::IMAGE_CONTRAST_RANGE;
+                self.config.image_contrast = Some(parsed.clamp(*range.start(), *range.end()));
+            }
+        }
+        if let Ok(val) = conn.query_row::<String, _, _>(
+            "SELECT value FROM config WHERE key = 'capture_jitter_percent'",
+            [],
+            |r| r.get(0),
+        ) {
+            if let Ok(parsed) = val.parse::<u32>() {
+                self.config.capture_jitter_percent = Some(parsed.min(crate::agent_pure::CAPTURE_JITTER_PERCENT_MAX));
+            }
+        }
+        if let Ok(val) = conn.query_row::<String, _, _>(
+            "SELECT value FROM config WHERE key = 'max_image_bytes'",
+            [],
+            |r| r.get(0),
+        ) {
+            if let Ok(parsed) = val.parse::<u64>() {
+                self.config.max_image_bytes = Some(parsed.max(crate::capture_target::MAX_IMAGE_BYTES_MIN));
+            }
+        }
+        if let Ok(val) = conn.query_row::<String, _, _>(
+            "SELECT value FROM config WHERE key = 'capture_interval_on_battery'",
+            [],
+            |r| r.get(0),
+        ) {
+            self.config.capture_interval_on_battery = val
+                .parse::<u64>()
+                .ok()
+                .map(|ms| ms.clamp(*CAPTURE_INTERVAL_MS_RANGE.start(), *CAPTURE_INTERVAL_MS_RANGE.end()));
+        }
+    }
+
+    /// Pushes the config into the process-wide settings (capture, input hook, proxy, backend).
+    /// Only ever called with a config that has been fully validated. Kept out of `load_config`
+    /// so unit tests can load a config without flipping flags other tests rely on.
+    fn apply_runtime_settings(&self) {
+        let c = &self.config;
+        self.apply_proxy_settings();
+        crate::input_activity::set_enabled(c.input_activity_tracking.unwrap_or(false));
+        crate::capture_target::set_screen_capture_enabled(c.enable_screen_capture.unwrap_or(true));
+        crate::capture_target::set_capture_target(c.capture_target.as_deref().unwrap_or_default());
+        crate::capture_target::set_capture_monitor(c.capture_monitor);
+        self.apply_preprocessing();
+        self.apply_analysis_backend();
+    }
+
+    fn apply_preprocessing(&self) {
+        let c = &self.config;
+        crate::capture_target::set_preprocessing(crate::capture_target::Preprocessing {
+            grayscale: c.image_grayscale.unwrap_or(false),
+            contrast: c.image_contrast.unwrap_or(0.0),
+            sharpen: c.image_sharpen.unwrap_or(false),
+        });
+        crate::capture_target::set_max_image_bytes(
+            c.max_image_bytes.unwrap_or(crate::capture_target::MAX_IMAGE_BYTES_DEFAULT),
+        );
+    }
+
+    /// An incomplete external setup (edited DB, cleared key) falls back to the local server, and so
+    /// does any external setup while `localOnly` is on.
+    fn apply_analysis_backend(&self) {
+        let c = &self.config;
+        let local_only = c.local_only.unwrap_or(false);
+        crate::http_client::set_local_only(local_only);
+        if local_only {
+            crate::analysis_backend::set_backend(crate::analysis_backend::VisionBackend::LocalLlama);
+            return;
+        }
+        let backend = crate::analysis_backend::resolve(
+            c.analysis_backend.as_deref(),
+            c.api_base.as_deref(),
+            c.api_key.as_deref(),
+            c.api_model.as_deref(),
+        )
+        .unwrap_or_else(|e| {
+            log::warn!("[Agent] {}; using the local vision server", e);
+            crate::analysis_backend::VisionBackend::LocalLlama
+        });
+        crate::analysis_backend::set_backend(backend);
+    }
+
+    fn apply_proxy_settings(&self) {
+        crate::http_client::set_proxy_settings(crate::http_client::ProxySettings {
+            http: self.config.http_proxy.clone(),
+            https: self.config.https_proxy.clone(),
+        });
     }
 
     fn save_config(&self) {
@@ -172,42 +698,71 @@ impl FlowSightAgent {
             return;
         };
 
+        let c = &self.config;
+        // `None` deletes the row so a cleared value does not come back on the next load_config.
         for (key, val) in [
-            ("dev_name", &self.config.dev_name),
-            ("vision_model", &self.config.vision_model),
+            ("dev_name", c.dev_name.clone()),
+            ("device_name", c.device_name.clone()),
+            ("vision_model", c.vision_model.clone()),
+            ("gpu_layers", c.gpu_layers.map(|v| v.to_string())),
+            ("daily_goal_hours", c.daily_goal_hours.map(|v| v.to_string())),
+            ("retention_days", c.retention_days.map(|v| v.to_string())),
+            ("retention_sweep_hour", c.retention_sweep_hour.map(|v| v.to_string())),
+            ("max_unsynced_reports", c.max_unsynced_reports.map(|v| v.to_string())),
+            ("http_proxy", c.http_proxy.clone()),
+            ("https_proxy", c.https_proxy.clone()),
+            ("capture_mode", c.capture_mode.clone()),
+            ("analysis_mode", c.analysis_mode.clone()),
+            ("report_language", c.report_language.clone()),
+            ("capture_target", c.capture_target.clone()),
+            ("analysis_backend", c.analysis_backend.clone()),
+            ("api_base", c.api_base.clone()),
+            ("api_key", c.api_key.clone()),
+            ("api_model", c.api_model.clone()),
+            ("capture_monitor", c.capture_monitor.map(|v| v.to_string())),
+            ("vision_timeout_secs", c.vision_timeout_secs.map(|v| v.to_string())),
+            ("vision_temperature", c.vision_temperature.map(|v| v.to_string())),
+            ("vision_max_tokens", c.vision_max_tokens.map(|v| v.to_string())),
+            ("min_description_length", c.min_description_length.map(|v| v.to_string())),
+            ("input_activity_tracking", c.input_activity_tracking.map(|v| v.to_string())),
+            ("enable_screen_capture", c.enable_screen_capture.map(|v| v.to_string())),
+            ("activity_log_max_limit", c.activity_log_max_limit.map(|v| v.to_string())),
+            ("image_grayscale", c.image_grayscale.map(|v| v.to_string())),
+            ("image_contrast", c.image_contrast.map(|v| v.to_string())),
+            ("image_sharpen", c.image_sharpen.map(|v| v.to_string())),
+            ("capture_interval_on_battery", c.capture_interval_on_battery.map(|v| v.to_string())),
+            ("skip_vision_on_battery", c.skip_vision_on_battery.map(|v| v.to_string())),
+            ("capture_jitter_percent", c.capture_jitter_percent.map(|v| v.to_string())),
+            ("max_image_bytes", c.max_image_bytes.map(|v| v.to_string())),
+            ("local_only", c.local_only.map(|v| v.to_string())),
         ] {
-            if let Some(v) = val {
-                let _ = conn.execute(
+            let _ = match val {
+                Some(v) => conn.execute(
                     "INSERT OR REPLACE INTO config (key, value) VALUES (?, ?)",
                     params![key, v],
-                );
-            }
-        }
-
-        if let Some(layers) = self.config.gpu_layers {
-            let _ = conn.execute(
-                "INSERT OR REPLACE INTO config (key, value) VALUES (?, ?)",
-                params!["gpu_layers", layers.to_string()],
-            );
-        }
-
-        if let Some(hours) = self.config.daily_goal_hours {
-            let _ = conn.execute(
-                "INSERT OR REPLACE INTO config (key, value) VALUES (?, ?)",
-                params!["daily_goal_hours", hours.to_string()],
-            );
+                ),
+                None => conn.execute("DELETE FROM config WHERE key = ?", [key]),
+            };
         }
     }
 
-    fn save_report(&self, desc: &str, activity_type: &str, ticket: Option<String>, duration: u64) -> Option<i64> {
+    fn save_report(
+        &self,
+        desc: &str,
+        activity_type: &str,
+        ticket: Option<String>,
+        duration: u64,
+        activity_level: Option<&str>,
+        metadata: Option<&str>,
+    ) -> Option<i64> {
         let Ok(conn) = Connection::open(&self.db_path) else {
             log::warn!("[Agent] save_report: cannot open {:?}", self.db_path);
             return None;
         };
         if conn
             .execute(
-                "INSERT INTO reports (description, activity_type, jira_ticket_id, duration_seconds) VALUES (?, ?, ?, ?)",
-                params![desc, activity_type, ticket, duration],
+                "INSERT INTO reports (description, activity_type, jira_ticket_id, duration_seconds, activity_level, metadata) VALUES (?, ?, ?, ?, ?, ?)",
+                params![desc, activity_type, ticket, duration, activity_level, metadata],
             )
             .is_err()
         {
@@ -223,19 +778,27 @@ impl FlowSightAgent {
         }
     }
     
-    fn get_recent(&self, limit: u32) -> Vec<ActivityReport> {
+    /// Newest first. `activity_type` must already be a canonical label; `None` = all types.
+    /// With `since_id`, only reports with a larger id, oldest first (ready to append).
+    fn get_recent(&self, limit: u32, activity_type: Option<&str>, since_id: Option<i64>) -> Vec<ActivityReport> {
         let mut reports = Vec::new();
+        let order = if since_id.is_some() { "ASC" } else { "DESC" };
         if let Ok(conn) = Connection::open(&self.db_path) {
-            if let Ok(mut stmt) = conn.prepare(
-                "SELECT id, description, activity_type, synced, created_at FROM reports ORDER BY id DESC LIMIT ?"
-            ) {
-                if let Ok(rows) = stmt.query_map([limit], |row| {
+            if let Ok(mut stmt) = conn.prepare(&format!(
+                "SELECT id, description, activity_type, synced, created_at, activity_level, metadata FROM reports
+                 WHERE (?2 IS NULL OR activity_type = ?2) AND (?3 IS NULL OR id > ?3)
+                 ORDER BY id {} LIMIT ?1",
+                order
+            )) {
+                if let Ok(rows) = stmt.query_map(params![limit, activity_type, since_id], |row| {
                     Ok(ActivityReport {
                         id: row.get(0).ok(),
                         description: row.get(1)?,
                         activity_type: row.get(2)?,
                         synced: row.get::<_, i32>(3).unwrap_or(0) == 1,
                         timestamp: row.get(4)?,
+                        activity_level: row.get(5).unwrap_or(None),
+                        metadata: metadata_from_column(row.get(6).unwrap_or(None)),
                     })
                 }) {
                     for row_result in rows {
@@ -253,21 +816,49 @@ impl FlowSightAgent {
 // Capture and analyze screen
 // (Logic moved to Frontend for cross-platform support)
 
-fn capture_screen() -> Result<(String, std::path::PathBuf), String> {
+/// Error returned by `capture_screen` when there is nothing to capture (locked workstation,
+/// disconnected RDP session, headless box). Callers treat it as idle time, not as a failure.
+const NO_SCREEN_ERR: &str = "No screen";
+
+/// Raw frame of the `captureMonitor` display (first display when unset or unplugged), full
+/// resolution. `Err(NO_SCREEN_ERR)` when nothing is attached.
+pub(crate) fn grab_primary_screen() -> Result<image::DynamicImage, String> {
     use screenshots::Screen;
     
-    let screens = Screen::all().map_err(|e| e.to_string())?;
-    let screen = screens.first().ok_or("No screen")?;
+    let screens = Screen::all().unwrap_or_else(|e| {
+        log::warn!("[Agent] Screen enumeration failed: {}", e);
+        Vec::new()
+    });
+    let idx = crate::capture_target::monitor_index(screens.len()).ok_or(NO_SCREEN_ERR)?;
+    let screen = &screens[idx];
     let captured = screen.capture().map_err(|e| e.to_string())?;
     
     // Convert to DynamicImage
     let (width, height) = captured.dimensions();
-    let img = image::DynamicImage::ImageRgba8(
+    Ok(image::DynamicImage::ImageRgba8(
         image::RgbaImage::from_raw(width, height, captured.into_raw())
             .ok_or("Failed to create image")?
-    );
-    
-    let img = img.resize(960, 540, image::imageops::FilterType::Lanczos3);
+    ))
+}
+
+/// `(base64 png, debug path, dhash)` of the capture target (primary screen or active window),
+/// downscaled for the vision model.
+fn capture_screen() -> Result<(String, std::path::PathBuf, u64), String> {
+    let img = crate::capture_target::grab_frame()?;
+    let hash = crate::screen_change::dhash(&img);
+    let (base64, debug_path) = encode_for_vision(img)?;
+    Ok((base64, debug_path, hash))
+}
+
+/// Downscale + preprocessing + PNG / base64 of a grabbed frame (and the debug copy on disk).
+fn encode_for_vision(img: image::DynamicImage) -> Result<(String, std::path::PathBuf), String> {
+    // A small active window is sent as-is; only downscale.
+    let img = if img.width() > 960 || img.height() > 540 {
+        img.resize(960, 540, image::imageops::FilterType::Lanczos3)
+    } else {
+        img
+    };
+    let img = crate::capture_target::preprocess(img);
 
     let mut png = Vec::new();
     img.write_to(&mut std::io::Cursor::new(&mut png), image::ImageFormat::Png)
@@ -294,7 +885,7 @@ pub struct CaptureResult {
 
 #[tauri::command]
 pub fn capture_screen_command() -> Result<CaptureResult, String> {
-    let (base64, path) = capture_screen()?;
+    let (base64, path, _) = capture_screen()?;
     Ok(CaptureResult {
         path: path.to_string_lossy().to_string(),
         base64
@@ -308,6 +899,14 @@ pub struct ContextSnapshot {
     pub category: String, // NEW
     pub analysis_failed: bool,
     pub metadata: SnapshotMetadata,
+    /// `captureMode: "on_change"` and the screen matched the last analyzed frame:
+    /// description/category are reused and no vision call was made.
+    #[serde(default)]
+    pub reused_previous: bool,
+    /// Non-fatal problems (screenshot or vision failed). The snapshot still carries a usable
+    /// description (from the active window) so the renderer can record the time anyway.
+    #[serde(default)]
+    pub warnings: Vec<String>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -325,32 +924,108 @@ pub async fn capture_context_snapshot(
     user_task: Option<String>, 
     jira_ticket: Option<String>
 ) -> Result<ContextSnapshot, String> {
-    
     // Extract config (default to 16 if not set to ensure balanced load)
-    let gpu_layers = {
+    let (gpu_layers, vision_params, on_change, window_only, hybrid) = {
         let guard = state.lock().unwrap();
-        guard.as_ref()
-            .and_then(|a| a.config.gpu_layers)
-            .or(Some(16)) 
+        let cfg = guard.as_ref().map(|a| &a.config);
+        let mode = cfg.and_then(|c| c.analysis_mode.as_deref());
+        (
+            cfg.and_then(|c| c.gpu_layers).or(Some(16)),
+            VisionParams::from_config(cfg),
+            cfg.and_then(|c| c.capture_mode.as_deref())
+                == Some(crate::screen_change::CAPTURE_MODE_ON_CHANGE),
+            mode == Some(crate::agent_pure::ANALYSIS_MODE_WINDOW_ONLY)
+                || cfg.map(|c| capture_schedule(c).2).unwrap_or(false),
+            mode == Some(crate::agent_pure::ANALYSIS_MODE_HYBRID),
+        )
     };
 
+    // inputActivityTracking saw no key or click since the last report: Idle, no screenshot
+    let idle_secs = crate::input_activity::idle_secs();
+
+    // privacy switch: refuse before anything touches the screen (window_only never does)
+    if idle_secs.is_none() && !window_only && !crate::capture_target::screen_capture_enabled() {
+        return Err(crate::capture_target::CAPTURE_DISABLED_ERR.to_string());
+    }
+
     // Run ALL heavy work on a background thread to avoid blocking the main/UI thread
     tauri::async_runtime::spawn_blocking(move || {
         use crate::context::get_system_context;
         use std::path::PathBuf;
 
-        // 1. Capture Screen
-        let (base64, path_str) = capture_screen()?;
+        if let Some(secs) = idle_secs {
+            log::info!("[Agent] No input for {}s; recording Idle without a vision call", secs);
+            return Ok(idle_snapshot(
+                format!("No keyboard or mouse input for {} min.", secs.div_ceil(60)),
+                jira_ticket.or(user_task),
+            ));
+        }
+
+        // analysisMode window_only (or skipVisionOnBattery while unplugged): no screenshot / model call
+        if window_only {
+            return Ok(window_only_snapshot(jira_ticket.or(user_task), vision_params.language));
+        }
+
+        // 1. Capture Screen (no display => record Idle without calling the vision model)
+        let (base64, path_str, hash) = match capture_screen() {
+            Ok(v) => v,
+            Err(e) if e == NO_SCREEN_ERR => {
+                log::info!("[Agent] No screen available (locked / disconnected); recording Idle");
+                return Ok(idle_snapshot(
+                    "No display available (session locked or disconnected).".to_string(),
+                    jira_ticket.or(user_task),
+                ));
+            }
+            Err(e) => {
+                log::warn!("[Agent] Screenshot failed ({}); recording window context only", e);
+                let mut snapshot = window_only_snapshot(jira_ticket.or(user_task), vision_params.language);
+                snapshot.analysis_failed = true;
+                snapshot.warnings.push(format!("Screenshot failed: {}", e));
+                return Ok(snapshot);
+            }
+        };
         let path = PathBuf::from(&path_str);
 
+        // hybrid: unchanged screen => cheap window-title report instead of reusing / re-running vision
+        if hybrid && crate::screen_change::reusable_analysis(hash).is_some() {
+            let _ = std::fs::remove_file(&path);
+            return Ok(window_only_snapshot(jira_ticket.or(user_task), vision_params.language));
+        }
+
+        // on_change: same screen as the last analyzed frame => reuse it, skip the model call
+        if on_change {
+            if let Some((description, category)) = crate::screen_change::reusable_analysis(hash) {
+                let _ = std::fs::remove_file(&path);
+                let sys = get_system_context();
+                return Ok(ContextSnapshot {
+                    vector: vec![],
+                    dimension: 0,
+                    description,
+                    category,
+                    analysis_failed: false,
+                    metadata: SnapshotMetadata {
+                        task: jira_ticket.or(user_task),
+                        file: sys.file_name,
+                        app: sys.app_name,
+                        branch: None,
+                        language: None,
+                    },
+                    reused_previous: true,
+                    warnings: vec![],
+                });
+            }
+        }
+
         // 2. Local vision analysis (visual description + category)
         let task_context = jira_ticket.clone().or(user_task.clone()).unwrap_or_else(|| "General".to_string());
         
-        let raw_analysis = match analyze_image_with_vision(&base64, &task_context, gpu_layers) {
+        let mut warnings = Vec::new();
+        let raw_analysis = match analyze_image_with_vision(&base64, &task_context, gpu_layers, vision_params) {
             Ok(res) => (res, false),
             Err(e) => {
+                warnings.push(format!("Vision analysis failed: {}", e));
                 let err_msg = format!("[Agent] AI Analysis Failed: {}", e);
-                println!("{}", err_msg);
+                log::error!("{}", err_msg);
                 
                 // Log a archivo en el app data dir (antes era "agent_error.log"
                 // con path relativo: en release cwd puede ser Program Files y
@@ -366,12 +1041,23 @@ pub async fn capture_context_snapshot(
         };
         
         // Parse category from response
-        let (description, category) = parse_analysis(&raw_analysis.0);
+        let (mut description, mut category) = parse_analysis_for_language(&raw_analysis.0, vision_params.language);
         let analysis_failed = raw_analysis.1
             || description.eq_ignore_ascii_case("No analysis available");
+        if !analysis_failed {
+            crate::screen_change::remember_analyzed(hash, &description, &category);
+        }
 
         // 3. System Context (Window/App)
         let sys = get_system_context();
+        // vision failed: the window title is still a better record than a placeholder
+        if analysis_failed && (sys.app_name.is_some() || sys.window_title.is_some()) {
+            (description, category) = crate::agent_pure::window_only_analysis(
+                sys.app_name.as_deref(),
+                sys.window_title.as_deref(),
+                vision_params.language,
+            );
+        }
         
         // 4. Git Context (Project)
         // Antes: hardcodeaba ~/Desktop/FlowSight.AI (solo exist\u00eda en la m\u00e1quina
@@ -396,23 +1082,288 @@ pub async fn capture_context_snapshot(
                 app: sys.app_name,
                 branch: git.and_then(|g| g.branch),
                 language: None,
-            }
+            },
+            reused_previous: false,
+            warnings,
         })
     }).await.map_err(|e| format!("Task join error: {}", e))?
 }
 
+/// `Idle` snapshot recorded without a vision call (no display, or no input since the last report).
+fn idle_snapshot(description: String, task: Option<String>) -> ContextSnapshot {
+    let sys = crate::context::get_system_context();
+    ContextSnapshot {
+        vector: vec![],
+        dimension: 0,
+        description,
+        category: "Idle".to_string(),
+        analysis_failed: false,
+        metadata: SnapshotMetadata {
+            task,
+            file: sys.file_name,
+            app: sys.app_name,
+            branch: None,
+            language: None,
+        },
+        reused_previous: false,
+        warnings: vec![],
+    }
+}
+
+/// Snapshot from the active app / window title alone (`analysisMode` `window_only` / `hybrid`).
+fn window_only_snapshot(task: Option<String>, language: &str) -> ContextSnapshot {
+    let sys = crate::context::get_system_context();
+    let (description, category) = crate::agent_pure::window_only_analysis(
+        sys.app_name.as_deref(),
+        sys.window_title.as_deref(),
+        language,
+    );
+    ContextSnapshot {
+        vector: vec![],
+        dimension: 0,
+        description,
+        category,
+        analysis_failed: false,
+        metadata: SnapshotMetadata {
+            task,
+            file: sys.file_name,
+            app: sys.app_name,
+            branch: None,
+            language: None,
+        },
+        reused_previous: false,
+        warnings: vec![],
+    }
+}
+
+/// What `capture_context_snapshot` + `save_activity` would record right now, without writing
+/// SQLite, syncing, touching stats / the on_change cache or draining the input counters.
+#[derive(Serialize, Debug)]
+pub struct CapturePreview {
+    /// `id: None`, never persisted.
+    pub report: ActivityReport,
+    pub analysis_failed: bool,
+    pub app: Option<String>,
+    pub file: Option<String>,
+    /// Small PNG (base64) of the frame that was analyzed.
+    pub thumbnail: Option<String>,
+}
+
+/// One timed capture → encode → vision run, split by stage, against the interval monitoring
+/// would use right now.
+#[derive(Serialize, Debug)]
+pub struct CaptureCostEstimate {
+    pub capture_ms: u64,
+    pub encode_ms: u64,
+    pub vision_ms: u64,
+    pub total_ms: u64,
+    /// Base64 payload sent to the model.
+    pub image_bytes: usize,
+    /// Effective capture interval (power source applied).
+    pub interval_ms: u64,
+    pub sustainable: bool,
+    pub suggested_interval_ms: Option<u64>,
+    pub recommendation: String,
+    /// Vision error, if the model call failed (timings are still filled in).
+    pub error: Option<String>,
+}
+
+/// Measures one analysis on this machine with the current model and settings, so the capture
+/// interval can be chosen before monitoring starts. Nothing is saved or synced.
+#[tauri::command]
+pub async fn estimate_capture_cost(state: State<'_, AgentState>) -> AgentResult<CaptureCostEstimate> {
+    if !crate::capture_target::screen_capture_enabled() {
+        return Err(AgentError::Config(crate::capture_target::CAPTURE_DISABLED_ERR.to_string()));
+    }
+    let (gpu_layers, vision_params, interval_ms) = {
+        let guard = state.lock().unwrap();
+        let cfg = guard.as_ref().map(|a| &a.config);
+        (
+            cfg.and_then(|c| c.gpu_layers).or(Some(16)),
+            VisionParams::from_config(cfg),
+            cfg.map(|c| capture_schedule(c).1)
+                .unwrap_or(60_000),
+        )
+    };
+
+    let estimate = tauri::async_runtime::spawn_blocking(move || -> Result<CaptureCostEstimate, String> {
+        let ms = |t: std::time::Instant| t.elapsed().as_millis() as u64;
+        let started = std::time::Instant::now();
+        let img = crate::capture_target::grab_frame()?;
+        let capture_ms = ms(started);
+
+        let started = std::time::Instant::now();
+        let (base64, path) = encode_for_vision(img)?;
+        let _ = std::fs::remove_file(&path);
+        let encode_ms = ms(started);
+
+        let started = std::time::Instant::now();
+        let error = analyze_image_with_vision(&base64, "General", gpu_layers, vision_params).err();
+        let vision_ms = ms(started);
+
+        let total_ms = capture_ms + encode_ms + vision_ms;
+        let (sustainable, suggested_interval_ms, recommendation) =
+            crate::agent_pure::capture_cost_recommendation(total_ms, interval_ms, error.as_deref());
+        log::info!(
+            "[Agent] Capture cost: capture {}ms, encode {}ms, vision {}ms (interval {}ms)",
+            capture_ms,
+            encode_ms,
+            vision_ms,
+            interval_ms
+        );
+        Ok(CaptureCostEstimate {
+            capture_ms,
+            encode_ms,
+            vision_ms,
+            total_ms,
+            image_bytes: base64.len(),
+            interval_ms,
+            sustainable,
+            suggested_interval_ms,
+            recommendation,
+            error,
+        })
+    })
+    .await
+    .map_err(|e| format!("Task join error: {}", e))??;
+    Ok(estimate)
+}
+
+/// Dry run for tuning prompt / model / capture target before enabling monitoring.
 #[tauri::command]
-pub fn save_activity(state: State<'_, AgentState>, description: String, activity_type: String, jira_ticket: Option<String>) -> Result<ActivityReport, String> {
+pub async fn capture_preview(
+    state: State<'_, AgentState>,
+    user_task: Option<String>,
+) -> Result<CapturePreview, String> {
+    if !crate::capture_target::screen_capture_enabled() {
+        return Err(crate::capture_target::CAPTURE_DISABLED_ERR.to_string());
+    }
+    let (gpu_layers, vision_params) = {
+        let guard = state.lock().unwrap();
+        let cfg = guard.as_ref().map(|a| &a.config);
+        (cfg.and_then(|c| c.gpu_layers).or(Some(16)), VisionParams::from_config(cfg))
+    };
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let (base64, path, _hash) = capture_screen()?;
+        let _ = std::fs::remove_file(&path);
+        let thumbnail = BASE64
+            .decode(&base64)
+            .ok()
+            .and_then(|png| image::load_from_memory(&png).ok())
+            .and_then(|img| crate::capture_target::thumbnail_base64(&img));
+
+        let task_context = user_task.unwrap_or_else(|| "General".to_string());
+        let (raw, analysis_failed) =
+            match analyze_image_with_vision(&base64, &task_context, gpu_layers, vision_params) {
+                Ok(res) => (res, false),
+                Err(e) => {
+                    log::warn!("[Preview] analysis failed: {}", e);
+                    ("Screen analysis failed. Category: General".to_string(), true)
+                }
+            };
+        let (mut description, mut category) = parse_analysis_for_language(&raw, vision_params.language);
+        let sys = crate::context::get_system_context();
+        // same window-title fallback as a real capture
+        if analysis_failed && (sys.app_name.is_some() || sys.window_title.is_some()) {
+            (description, category) = crate::agent_pure::window_only_analysis(
+                sys.app_name.as_deref(),
+                sys.window_title.as_deref(),
+                vision_params.language,
+            );
+        }
+        let (description, activity_type) =
+            crate::agent_pure::sanitize_report(&description, &category)?;
+
+        Ok(CapturePreview {
+            report: ActivityReport {
+                id: None,
+                timestamp: Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+                description,
+                activity_type,
+                synced: false,
+                activity_level: None,
+                metadata: None,
+            },
+            analysis_failed,
+            app: sys.app_name,
+            file: sys.file_name,
+            thumbnail,
+        })
+    })
+    .await
+    .map_err(|e| format!("Task join error: {}", e))?
+}
+
+/// Explicit timeline marker when the renderer's capture loop notices it stalled for
+/// `SLEEP_GAP_MIN_SECS`+ (laptop slept / hibernated). Stored as a zero-duration Idle report
+/// with `metadata.sleepGap`, so it shows in the log without adding tracked time.
+/// `from_ms` / `to_ms` are epoch milliseconds (JS `Date.now()`). `Ok(None)` for a short stall.
+#[tauri::command]
+pub fn record_sleep_gap(state: State<'_, AgentState>, from_ms: i64, to_ms: i64) -> AgentResult<Option<i64>> {
+    let secs = (to_ms - from_ms) / 1000;
+    if secs < crate::agent_pure::SLEEP_GAP_MIN_SECS {
+        return Ok(None);
+    }
+    let local = |ms: i64| {
+        chrono::DateTime::from_timestamp_millis(ms)
+            .map(|t| t.with_timezone(&chrono::Local).naive_local())
+            .ok_or_else(|| AgentError::Config(format!("Invalid timestamp: {}", ms)))
+    };
+    let description = crate::agent_pure::sleep_gap_description(local(from_ms)?, local(to_ms)?);
+    let metadata = serde_json::json!({ "sleepGap": { "fromMs": from_ms, "toMs": to_ms, "seconds": secs } });
+
+    let mut agent = state.lock().unwrap();
+    let a = agent.as_mut().ok_or(AgentError::NotInitialized)?;
+    a.summary_cache.invalidate();
+    let id = a
+        .save_report(&description, "Idle", None, 0, None, Some(&metadata.to_string()))
+        .ok_or_else(|| AgentError::Database("Failed to write sleep gap to local database.".to_string()))?;
+    log::info!("[Agent] {}", description);
+    Ok(Some(id))
+}
+
+#[tauri::command]
+pub fn save_activity(
+    state: State<'_, AgentState>,
+    description: String,
+    activity_type: String,
+    jira_ticket: Option<String>,
+    metadata: Option<serde_json::Value>,
+) -> AgentResult<ActivityReport> {
+    let (description, activity_type) =
+        crate::agent_pure::sanitize_report(&description, &activity_type)
+            .map_err(AgentError::Config)?;
+    let metadata_text =
+        crate::agent_pure::normalize_report_metadata(metadata.as_ref()).map_err(AgentError::Config)?;
     let mut agent = state.lock().unwrap();
     let Some(a) = agent.as_mut() else {
-        return Err(
-            "Agent not initialized — wait for startup to finish before capturing.".to_string(),
-        );
+        return Err(AgentError::NotInitialized);
     };
     a.reports_sent += 1;
+    a.summary_cache.invalidate();
+    let activity_level = crate::input_activity::take_window().map(|(keys, clicks, secs)| {
+        crate::input_activity::activity_level(keys + clicks, secs).to_string()
+    });
     let report_id = a
-        .save_report(&description, &activity_type, jira_ticket, 30)
-        .ok_or_else(|| "Failed to write activity to local database.".to_string())?;
+        .save_report(
+            &description,
+            &activity_type,
+            jira_ticket,
+            30,
+            activity_level.as_deref(),
+            metadata_text.as_deref(),
+        )
+        .ok_or_else(|| AgentError::Database("Failed to write activity to local database.".to_string()))?;
+
+    if let Ok(conn) = Connection::open(&a.db_path) {
+        if let Err(e) = crate::blockers::detect_blockers(&conn) {
+            log::warn!("[Blockers] detection failed: {}", e);
+        }
+        if let Err(e) = crate::retention::enforce_unsynced_cap(&conn) {
+            log::warn!("[Retention] unsynced cap failed: {}", e);
+        }
+    }
 
     Ok(ActivityReport {
         id: Some(report_id),
@@ -420,9 +1371,58 @@ pub fn save_activity(state: State<'_, AgentState>, description: String, activity
         description,
         activity_type,
         synced: false,
+        activity_level,
+        metadata: metadata_text.and_then(|t| serde_json::from_str(&t).ok()),
     })
 }
 
+/// Manual correction of a misclassified capture. Only the local row changes: the cloud gets
+/// AI window summaries (work_sessions / activity_reports), never per-report categories.
+#[tauri::command]
+pub fn set_activity_type(
+    state: State<'_, AgentState>,
+    id: i64,
+    activity_type: String,
+) -> AgentResult<ActivityReport> {
+    let label = crate::agent_pure::canonical_category(&activity_type)
+        .ok_or_else(|| AgentError::Config(format!("Unknown activity type: {}", activity_type)))?;
+    let db_path = {
+        let guard = state.lock().unwrap();
+        guard
+            .as_ref()
+            .map(|a| a.db_path.clone())
+            .ok_or(AgentError::NotInitialized)?
+    };
+    let conn = Connection::open(&db_path)?;
+    let updated = conn
+        .execute(
+            "UPDATE reports SET activity_type = ?1 WHERE id = ?2",
+            params![label, id],
+        )?;
+    if let Some(a) = state.lock().unwrap().as_mut() {
+        a.summary_cache.invalidate();
+    }
+    if updated == 0 {
+        return Err(AgentError::Config(format!("Report {} not found", id)));
+    }
+    conn.query_row(
+        "SELECT id, created_at, description, activity_type, synced, activity_level, metadata FROM reports WHERE id = ?1",
+        [id],
+        |r| {
+            Ok(ActivityReport {
+                id: Some(r.get(0)?),
+                timestamp: r.get(1)?,
+                description: r.get(2)?,
+                activity_type: r.get(3)?,
+                synced: r.get::<_, i64>(4)? != 0,
+                activity_level: r.get(5)?,
+                metadata: metadata_from_column(r.get(6)?),
+            })
+        },
+    )
+    .map_err(AgentError::from)
+}
+
 // ============== TAURI COMMANDS ==============
 
 /// Comprueba que SQLite puede **escribir** en `dev-agent.db` (CFA / solo lectura / disco lleno).
@@ -469,21 +1469,88 @@ pub fn initialize_agent(state: State<'_, AgentState>) -> Result<bool, String> {
         _ => {}
     }
 
-    *g = Some(FlowSightAgent::new());
+    let agent = FlowSightAgent::new();
+    agent.start_background_threads();
+    *g = Some(agent);
     Ok(true)
 }
 
+/// Borra todo lo que FlowSight guardó en esta máquina (BD, logs, capturas) y arranca con un
+/// agente nuevo y vacío. Las sesiones de FlowSight / Jira / Linear del backend viven en la BD
+/// (no hay entradas en el llavero del SO) y el estado en memoria (OAuth en curso, throttle 429)
+/// también se descarta; la sesión de Supabase del renderer y su localStorage los limpia la UI.
+/// Requiere `confirm: true`.
 #[tauri::command]
-pub fn get_config(state: State<'_, AgentState>) -> Result<AgentConfig, String> {
-    Ok(state.lock().unwrap().as_ref().map(|a| a.config.clone()).unwrap_or_default())
+pub fn factory_reset(state: State<'_, AgentState>, confirm: bool) -> AgentResult<crate::paths::WipeSummary> {
+    if !confirm {
+        return Err(AgentError::Config("factory_reset requires confirm: true".to_string()));
+    }
+    let mut g = state.lock().unwrap();
+    // Drop the agent first: capture loop stops and no command holds the DB while it is deleted.
+    *g = None;
+    let dir = crate::paths::app_data_dir()?;
+    let summary = crate::paths::wipe_user_data(&dir);
+    crate::auth::clear_oauth_state();
+    crate::sync::clear_throttle();
+    log::info!(
+        "[Agent] Factory reset: removed {:?} ({} screenshot(s)), failed {:?}",
+        summary.removed,
+        summary.screenshots_removed,
+        summary.failed
+    );
+    *g = Some(FlowSightAgent::new());
+    Ok(summary)
+}
+
+/// Saves `devName` without touching the rest of the config (`sync::update_display_name`).
+pub(crate) fn store_dev_name(state: &AgentState, name: &str) -> AgentResult<()> {
+    let mut g = state.lock().unwrap();
+    let agent = g.as_mut().ok_or(AgentError::NotInitialized)?;
+    agent.config.dev_name = Some(name.to_string());
+    agent.save_config();
+    Ok(())
+}
+
+/// `AgentConfig` as returned to the webview: the external API key stays on the Rust side.
+#[derive(Serialize, Debug)]
+pub struct ConfigView {
+    #[serde(flatten)]
+    pub config: AgentConfig,
+    #[serde(rename = "apiKeySet")]
+    pub api_key_set: bool,
+}
+
+impl From<AgentConfig> for ConfigView {
+    fn from(config: AgentConfig) -> Self {
+        let api_key_set = config.api_key.is_some();
+        Self { config, api_key_set }
+    }
+}
+
+#[tauri::command]
+pub fn get_config(state: State<'_, AgentState>) -> Result<ConfigView, String> {
+    Ok(state.lock().unwrap().as_ref().map(|a| a.config.clone()).unwrap_or_default().into())
 }
 
 #[tauri::command]
-pub fn update_config(state: State<'_, AgentState>, patch: AgentConfig) -> Result<bool, String> {
+pub fn update_config(state: State<'_, AgentState>, patch: AgentConfig) -> AgentResult<bool> {
     if let Some(agent) = state.lock().unwrap().as_mut() {
-        let c = &mut agent.config;
-        if patch.dev_name.is_some() {
+        // Validate into a copy: an Err below must leave memory, the DB and runtime state untouched.
+        let mut next = agent.config.clone();
+        let c = &mut next;
+        let mut renamed = None;
+        if patch.dev_name.is_some() && patch.dev_name != c.dev_name {
             c.dev_name = patch.dev_name;
+            renamed = c.dev_name.clone().filter(|n| !n.trim().is_empty());
+        }
+        // "" => back to the OS device name
+        if let Some(device) = patch.device_name.as_deref() {
+            let device = device.trim();
+            c.device_name = Some(if device.is_empty() {
+                whoami::devicename()
+            } else {
+                device.to_string()
+            });
         }
         if patch.capture_interval.is_some() {
             c.capture_interval = patch.capture_interval;
@@ -500,18 +1567,271 @@ pub fn update_config(state: State<'_, AgentState>, patch: AgentConfig) -> Result
                 .daily_goal_hours
                 .map(|h| h.clamp(0.0, 24.0));
         }
+        // 0 = keep everything
+        if let Some(days) = patch.retention_days {
+            c.retention_days = crate::retention::validate_retention_days(days).map_err(AgentError::Config)?;
+        }
+        // 0 = unbounded
+        if patch.max_unsynced_reports.is_some() {
+            c.max_unsynced_reports = patch.max_unsynced_reports.filter(|&n| n > 0);
+        }
+        if patch.retention_sweep_hour.is_some() {
+            c.retention_sweep_hour = patch.retention_sweep_hour.map(|h| h.min(23));
+        }
+        if patch.vision_timeout_secs.is_some() {
+            c.vision_timeout_secs = patch.vision_timeout_secs.map(|t| {
+                t.clamp(*VISION_TIMEOUT_SECS_RANGE.start(), *VISION_TIMEOUT_SECS_RANGE.end())
+            });
+        }
+        if let Some(lang) = patch.report_language.as_deref() {
+            c.report_language = Some(
+                crate::agent_pure::normalize_report_language(lang)
+                    .map_err(AgentError::Config)?
+                    .to_string(),
+            );
+        }
+        if let Some(target) = patch.capture_target.as_deref() {
+            c.capture_target = Some(
+                crate::capture_target::normalize_capture_target(target).map_err(AgentError::Config)?,
+            );
+        }
+        if let Some(monitor) = patch.capture_monitor {
+            c.capture_monitor = Some(monitor);
+        }
+        if let Some(mode) = patch.capture_mode.as_deref() {
+            c.capture_mode = Some(crate::screen_change::normalize_capture_mode(mode).map_err(AgentError::Config)?);
+        }
+        if let Some(mode) = patch.analysis_mode.as_deref() {
+            c.analysis_mode = Some(crate::agent_pure::normalize_analysis_mode(mode).map_err(AgentError::Config)?);
+        }
+        if let Some(on) = patch.input_activity_tracking {
+            c.input_activity_tracking = Some(on);
+        }
+        if patch.activity_log_max_limit.is_some() {
+            let range = crate::agent_pure::ACTIVITY_LOG_MAX_LIMIT_RANGE;
+            c.activity_log_max_limit = patch
+                .activity_log_max_limit
+                .map(|n| n.clamp(*range.start(), *range.end()));
+        }
+        if let Some(on) = patch.image_grayscale {
+            c.image_grayscale = Some(on);
+        }
+        if let Some(contrast) = patch.image_contrast {
+            c.image_contrast = Some(
+                crate::capture_target::validate_image_contrast(contrast).map_err(AgentError::Config)?,
+            );
+        }
+        if let Some(on) = patch.image_sharpen {
+            c.image_sharpen = Some(on);
+        }
+        // 0 clears (same interval as on AC)
+        if let Some(ms) = patch.capture_interval_on_battery {
+            c.capture_interval_on_battery = match ms {
+                0 => None,
+                ms => Some(validate_capture_interval_ms(ms).map_err(AgentError::Config)?),
+            };
+        }
+        if let Some(on) = patch.skip_vision_on_battery {
+            c.skip_vision_on_battery = Some(on);
+        }
+        if let Some(pct) = patch.capture_jitter_percent {
+            if pct > crate::agent_pure::CAPTURE_JITTER_PERCENT_MAX {
+                return Err(AgentError::Config(format!(
+                    "captureJitterPercent must be between 0 and {} (got {})",
+                    crate::agent_pure::CAPTURE_JITTER_PERCENT_MAX,
+                    pct
+                )));
+            }
+            c.capture_jitter_percent = Some(pct);
+        }
+        if let Some(bytes) = patch.max_image_bytes {
+            c.max_image_bytes = Some(
+                crate::capture_target::validate_max_image_bytes(bytes).map_err(AgentError::Config)?,
+            );
+        }
+        if let Some(on) = patch.local_only {
+            c.local_only = Some(on);
+        }
+        if let Some(on) = patch.enable_screen_capture {
+            c.enable_screen_capture = Some(on);
+        }
+        if let Some(t) = patch.vision_temperature {
+            c.vision_temperature = Some(validate_vision_temperature(t).map_err(AgentError::Config)?);
+        }
+        if let Some(n) = patch.vision_max_tokens {
+            c.vision_max_tokens = Some(validate_vision_max_tokens(n).map_err(AgentError::Config)?);
+        }
+        if let Some(n) = patch.min_description_length {
+            c.min_description_length = Some(validate_min_description_length(n).map_err(AgentError::Config)?);
+        }
+        // "" clears the proxy (back to env / direct)
+        if patch.http_proxy.is_some() {
+            c.http_proxy = crate::http_client::normalize_proxy_url(patch.http_proxy.as_deref())
+                .map_err(AgentError::Config)?;
+        }
+        if patch.https_proxy.is_some() {
+            c.https_proxy = crate::http_client::normalize_proxy_url(patch.https_proxy.as_deref())
+                .map_err(AgentError::Config)?;
+        }
+        // "" clears; the combination is validated before anything is saved
+        if patch.analysis_backend.is_some() || patch.api_base.is_some() || patch.api_key.is_some() || patch.api_model.is_some() {
+            let non_blank = |v: Option<String>| v.map(|v| v.trim().to_string()).filter(|v| !v.is_empty());
+            if let Some(b) = patch.analysis_backend.as_deref() {
+                c.analysis_backend = Some(
+                    crate::analysis_backend::normalize_analysis_backend(b).map_err(AgentError::Config)?,
+                );
+            }
+            if patch.api_base.is_some() {
+                c.api_base = crate::analysis_backend::normalize_api_base(patch.api_base.as_deref())
+                    .map_err(AgentError::Config)?;
+            }
+            if patch.api_key.is_some() {
+                c.api_key = non_blank(patch.api_key);
+            }
+            if patch.api_model.is_some() {
+                c.api_model = non_blank(patch.api_model);
+            }
+            crate::analysis_backend::resolve(
+                c.analysis_backend.as_deref(),
+                c.api_base.as_deref(),
+                c.api_key.as_deref(),
+                c.api_model.as_deref(),
+            )
+            .map_err(AgentError::Config)?;
+        }
+        agent.config = next;
         agent.save_config();
+        agent.apply_runtime_settings();
+        // rename shows on the dashboard now, not at the next profile upsert; only once saved
+        if let Some(name) = renamed.filter(|_| !crate::http_client::local_only()) {
+            let db_path = agent.db_path.clone();
+            std::thread::spawn(move || match crate::sync::push_display_name(&db_path, &name) {
+                Ok(()) | Err(AgentError::NotRegistered) => {}
+                Err(e) => log::warn!("[Agent] Could not push new display name: {}", e),
+            });
+        }
     }
     Ok(true)
 }
 
+/// Clears one setting (camelCase key, as in `AgentConfig`) back to its first-run value.
+/// `update_config` treats missing fields as "unchanged", so it cannot express this.
+#[tauri::command]
+pub fn reset_config_value(state: State<'_, AgentState>, key: String) -> AgentResult<ConfigView> {
+    let mut guard = state.lock().unwrap();
+    let agent = guard.as_mut().ok_or(AgentError::NotInitialized)?;
+    let d = AgentConfig::initial();
+    let c = &mut agent.config;
+    match key.as_str() {
+        "devName" => c.dev_name = d.dev_name,
+        "deviceName" => c.device_name = d.device_name,
+        "captureInterval" => c.capture_interval = d.capture_interval,
+        "visionModel" => c.vision_model = d.vision_model,
+        "gpuLayers" => c.gpu_layers = d.gpu_layers,
+        "dailyGoalHours" => c.daily_goal_hours = d.daily_goal_hours,
+        "retentionDays" => c.retention_days = d.retention_days,
+        "retentionSweepHour" => c.retention_sweep_hour = d.retention_sweep_hour,
+        "maxUnsyncedReports" => c.max_unsynced_reports = d.max_unsynced_reports,
+        "httpProxy" => c.http_proxy = d.http_proxy,
+        "httpsProxy" => c.https_proxy = d.https_proxy,
+        "visionTimeoutSecs" => c.vision_timeout_secs = d.vision_timeout_secs,
+        "visionTemperature" => c.vision_temperature = d.vision_temperature,
+        "visionMaxTokens" => c.vision_max_tokens = d.vision_max_tokens,
+        "minDescriptionLength" => c.min_description_length = d.min_description_length,
+        "captureMode" => c.capture_mode = d.capture_mode,
+        "analysisMode" => c.analysis_mode = d.analysis_mode,
+        "reportLanguage" => c.report_language = d.report_language,
+        "analysisBackend" => c.analysis_backend = d.analysis_backend,
+        "apiBase" => c.api_base = d.api_base,
+        "apiKey" => c.api_key = d.api_key,
+        "apiModel" => c.api_model = d.api_model,
+        "captureMonitor" => c.capture_monitor = d.capture_monitor,
+        "captureTarget" => c.capture_target = d.capture_target,
+        "inputActivityTracking" => c.input_activity_tracking = d.input_activity_tracking,
+        "activityLogMaxLimit" => c.activity_log_max_limit = d.activity_log_max_limit,
+        "imageGrayscale" => c.image_grayscale = d.image_grayscale,
+        "imageContrast" => c.image_contrast = d.image_contrast,
+        "imageSharpen" => c.image_sharpen = d.image_sharpen,
+        "captureIntervalOnBattery" => c.capture_interval_on_battery = d.capture_interval_on_battery,
+        "skipVisionOnBattery" => c.skip_vision_on_battery = d.skip_vision_on_battery,
+        "localOnly" => c.local_only = d.local_only,
+        "captureJitterPercent" => c.capture_jitter_percent = d.capture_jitter_percent,
+        "maxImageBytes" => c.max_image_bytes = d.max_image_bytes,
+        "enableScreenCapture" => c.enable_screen_capture = d.enable_screen_capture,
+        other => return Err(AgentError::Config(format!("Unknown config key: {}", other))),
+    }
+    agent.save_config();
+    agent.apply_runtime_settings();
+    Ok(agent.config.clone().into())
+}
+
+/// Power source plus the interval / vision switch the capture loop should use right now.
+fn capture_schedule(config: &AgentConfig) -> (crate::power::PowerSource, u64, bool) {
+    let source = crate::power::power_source();
+    let interval = crate::power::effective_capture_interval(
+        config.capture_interval.unwrap_or(60_000),
+        config.capture_interval_on_battery,
+        source,
+    );
+    let skip_vision =
+        source == crate::power::PowerSource::Battery && config.skip_vision_on_battery.unwrap_or(false);
+    (source, interval, skip_vision)
+}
+
+/// Seed for `jittered_interval_ms` outside tests: clock nanos mixed with the process id.
+pub(crate) fn jitter_seed() -> u64 {
+    let nanos = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_nanos() as u64)
+        .unwrap_or(0);
+    nanos ^ ((std::process::id() as u64) << 32)
+}
+
+/// Polled by the frontend capture loop so unplugging / plugging in takes effect without a restart.
+/// `nextIntervalMs` is `intervalMs` with `captureJitterPercent` applied (fresh draw per call).
+#[tauri::command]
+pub fn get_capture_schedule(state: State<'_, AgentState>) -> AgentResult<serde_json::Value> {
+    let guard = state.lock().unwrap();
+    let agent = guard.as_ref().ok_or(AgentError::NotInitialized)?;
+    let (source, interval, skip_vision) = capture_schedule(&agent.config);
+    let jitter = agent
+        .config
+        .capture_jitter_percent
+        .unwrap_or(crate::agent_pure::CAPTURE_JITTER_PERCENT_DEFAULT);
+    Ok(serde_json::json!({
+        "powerSource": source,
+        "intervalMs": interval,
+        "nextIntervalMs": crate::agent_pure::jittered_interval_ms(interval, jitter, jitter_seed()),
+        "jitterPercent": jitter,
+        "skipVision": skip_vision,
+    }))
+}
+
 #[tauri::command]
 pub fn get_status(state: State<'_, AgentState>) -> Result<serde_json::Value, String> {
     let agent = state.lock().unwrap();
     Ok(if let Some(a) = agent.as_ref() {
+        let (power_source, effective_interval, _) = capture_schedule(&a.config);
+        let queue = Connection::open(&a.db_path)
+            .map(|conn| crate::retention::unsynced_queue_status(&conn))
+            .unwrap_or(serde_json::Value::Null);
         serde_json::json!({
             "isRunning": a.is_running,
-            "reportsSent": a.reports_sent
+            "reportsSent": a.reports_sent,
+            "deviceName": a.config.device_name,
+            "developerLabel": crate::agent_pure::developer_label(
+                a.config.dev_name.as_deref().unwrap_or_default(),
+                a.config.device_name.as_deref(),
+            ),
+            "pendingReports": queue["pendingReports"],
+            "maxUnsyncedReports": queue["maxUnsyncedReports"],
+            "evictionOccurred": queue["evictionOccurred"],
+            "evictedReports": queue["evictedReports"],
+            "lastEvictionAt": queue["lastEvictionAt"],
+            // uploads paused by a 429 until this time; reports keep queuing locally
+            "throttledUntil": crate::sync::throttled_until().map(|t| t.to_rfc3339()),
+            "powerSource": power_source,
+            "effectiveCaptureInterval": effective_interval,
         })
     } else {
         serde_json::json!({"isRunning": false, "reportsSent": 0})
@@ -531,11 +1851,39 @@ pub fn stop_monitoring(state: State<'_, AgentState>) -> Result<bool, String> {
 }
 
 #[tauri::command]
-pub fn get_activity_log(state: State<'_, AgentState>, limit: Option<u32>) -> Result<Vec<ActivityReport>, String> {
-    Ok(state.lock().unwrap().as_ref().map(|a| a.get_recent(limit.unwrap_or(20))).unwrap_or_default())
+pub fn get_activity_log(
+    state: State<'_, AgentState>,
+    limit: Option<u32>,
+    activity_type: Option<String>,
+    since_id: Option<i64>,
+) -> Result<ActivityLogPage, String> {
+    // "meeting" / "code review" → stored label; an unknown type is a caller bug, not "no rows"
+    let activity_type = activity_type
+        .as_deref()
+        .map(|t| {
+            crate::agent_pure::canonical_category(t)
+                .ok_or_else(|| format!("Unknown activity type: {}", t))
+        })
+        .transpose()?;
+    // `limit` defaults to 20 and is clamped to `activityLogMaxLimit` (500) so a renderer bug
+    // cannot load the whole table
+    let guard = state.lock().unwrap();
+    let max = guard
+        .as_ref()
+        .and_then(|a| a.config.activity_log_max_limit)
+        .unwrap_or(crate::agent_pure::ACTIVITY_LOG_MAX_LIMIT_DEFAULT);
+    let (limit, capped) = crate::agent_pure::effective_log_limit(limit, max);
+    Ok(ActivityLogPage {
+        reports: guard
+            .as_ref()
+            .map(|a| a.get_recent(limit, activity_type, since_id))
+            .unwrap_or_default(),
+        limit,
+        capped,
+    })
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct DayHistoryEntry {
     pub time: String,
     pub description: String,
@@ -544,50 +1892,105 @@ pub struct DayHistoryEntry {
     pub duration_seconds: i32,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct CategoryBreakdown {
     pub category: String,
     pub total_seconds: i32,
     pub count: i32,
+    /// Display name / `#rrggbb` color (`set_category_display` overrides, else defaults).
+    #[serde(default)]
+    pub label: String,
+    #[serde(default)]
+    pub color: String,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct CategoryDisplay {
+    pub category: String,
+    pub label: String,
+    pub color: String,
+    /// `true` when the label or color comes from `set_category_display`.
+    pub customized: bool,
+}
+
+/// Stored category → `(label, color)` overrides from `category_display`.
+fn load_category_overrides(
+    conn: &Connection,
+) -> std::collections::HashMap<String, (Option<String>, Option<String>)> {
+    let Ok(mut stmt) = conn.prepare("SELECT category, label, color FROM category_display") else {
+        return Default::default();
+    };
+    stmt.query_map([], |row| {
+        Ok((
+            row.get::<_, String>(0)?,
+            (row.get::<_, Option<String>>(1)?, row.get::<_, Option<String>>(2)?),
+        ))
+    })
+    .map(|rows| rows.filter_map(|r| r.ok()).collect())
+    .unwrap_or_default()
+}
+
+fn category_display_with(
+    overrides: &std::collections::HashMap<String, (Option<String>, Option<String>)>,
+    category: &str,
+) -> CategoryDisplay {
+    let (label, color) = crate::agent_pure::category_display_default(category);
+    let (custom_label, custom_color) = overrides.get(category).cloned().unwrap_or_default();
+    CategoryDisplay {
+        category: category.to_string(),
+        customized: custom_label.is_some() || custom_color.is_some(),
+        label: custom_label.unwrap_or(label),
+        color: custom_color.unwrap_or(color),
+    }
+}
+
+/// Applies stored label / color overrides to a breakdown built by `summarize_entries`.
+pub(crate) fn apply_category_display(conn: &Connection, breakdown: &mut [CategoryBreakdown]) {
+    let overrides = load_category_overrides(conn);
+    if overrides.is_empty() {
+        return;
+    }
+    for c in breakdown.iter_mut() {
+        let d = category_display_with(&overrides, &c.category);
+        c.label = d.label;
+        c.color = d.color;
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct TicketBreakdown {
     pub ticket: String,
     pub total_seconds: i32,
     pub count: i32,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct TodayHistory {
     pub entries: Vec<DayHistoryEntry>,
     pub total_seconds: i32,
     pub category_breakdown: Vec<CategoryBreakdown>,
     pub ticket_breakdown: Vec<TicketBreakdown>,
     pub date: String,
+    /// When this summary was computed (RFC 3339); may be up to `SUMMARY_CACHE_TTL` old.
+    #[serde(default)]
+    pub cached_at: String,
 }
 
-#[tauri::command]
-pub fn get_today_history(state: State<'_, AgentState>) -> Result<TodayHistory, String> {
-    let agent = state.lock().unwrap();
-    let agent = agent.as_ref().ok_or("Agent not initialized")?;
-    
-    let conn = Connection::open(&agent.db_path).map_err(|e| e.to_string())?;
-    // Calendar 'today' in local TZ must use UTC→local conversion: `created_at`
+/// Reports whose local calendar date is in `from..=to` (`YYYY-MM-DD`), newest first.
+pub(crate) fn history_entries_between(conn: &Connection, from: &str, to: &str) -> Result<Vec<DayHistoryEntry>, String> {
+    // Calendar dates in local TZ must use UTC→local conversion: `created_at`
     // defaults to CURRENT_TIMESTAMP (UTC). Comparing plain `date(created_at)`
     // to `date('now','localtime')` used mismatched halves and often returned zero rows.
-    let today = Local::now().format("%Y-%m-%d").to_string();
-
     let mut stmt = conn
         .prepare(
             "SELECT created_at, description, activity_type, jira_ticket_id, duration_seconds
          FROM reports
-         WHERE date(created_at, 'localtime') = ?1
+         WHERE date(created_at, 'localtime') >= ?1 AND date(created_at, 'localtime') <= ?2
          ORDER BY datetime(created_at) DESC",
         )
         .map_err(|e| e.to_string())?;
 
-    let entries: Vec<DayHistoryEntry> = stmt.query_map(params![today], |row| {
+    let entries: Vec<DayHistoryEntry> = stmt.query_map(params![from, to], |row| {
         Ok(DayHistoryEntry {
             time: row.get::<_, String>(0).unwrap_or_default(),
             description: row.get::<_, String>(1).unwrap_or_default(),
@@ -598,24 +2001,31 @@ pub fn get_today_history(state: State<'_, AgentState>) -> Result<TodayHistory, S
     }).map_err(|e| e.to_string())?
     .filter_map(|r| r.ok())
     .collect();
-    
+    Ok(entries)
+}
+
+/// `(total_seconds, category_breakdown, ticket_breakdown)` for a set of entries.
+pub(crate) fn summarize_entries(entries: &[DayHistoryEntry]) -> (i32, Vec<CategoryBreakdown>, Vec<TicketBreakdown>) {
     // Calculate total
     let total_seconds: i32 = entries.iter().map(|e| e.duration_seconds).sum();
     
     // Category breakdown
     let mut cat_map: std::collections::HashMap<String, (i32, i32)> = std::collections::HashMap::new();
-    for e in &entries {
+    for e in entries {
         let entry = cat_map.entry(e.category.clone()).or_insert((0, 0));
         entry.0 += e.duration_seconds;
         entry.1 += 1;
     }
     let category_breakdown: Vec<CategoryBreakdown> = cat_map.into_iter()
-        .map(|(category, (total_seconds, count))| CategoryBreakdown { category, total_seconds, count })
+        .map(|(category, (total_seconds, count))| {
+            let (label, color) = crate::agent_pure::category_display_default(&category);
+            CategoryBreakdown { category, total_seconds, count, label, color }
+        })
         .collect();
     
     // Ticket breakdown
     let mut ticket_map: std::collections::HashMap<String, (i32, i32)> = std::collections::HashMap::new();
-    for e in &entries {
+    for e in entries {
         if let Some(ref ticket) = e.ticket {
             let entry = ticket_map.entry(ticket.clone()).or_insert((0, 0));
             entry.0 += e.duration_seconds;
@@ -625,6 +2035,30 @@ pub fn get_today_history(state: State<'_, AgentState>) -> Result<TodayHistory, S
     let ticket_breakdown: Vec<TicketBreakdown> = ticket_map.into_iter()
         .map(|(ticket, (total_seconds, count))| TicketBreakdown { ticket, total_seconds, count })
         .collect();
+
+    (total_seconds, category_breakdown, ticket_breakdown)
+}
+
+#[tauri::command]
+pub fn get_today_history(state: State<'_, AgentState>) -> AgentResult<TodayHistory> {
+    let mut agent = state.lock().unwrap();
+    let agent = agent.as_mut().ok_or(AgentError::NotInitialized)?;
+    let today = Local::now().format("%Y-%m-%d").to_string();
+    agent.summary_cache.drop_if_reports_deleted();
+    if let Some(hit) = agent.summary_cache.today.fresh(&today) {
+        return Ok(hit);
+    }
+    let history = compute_today_history(&agent.db_path, today.clone())?;
+    agent.summary_cache.today.store(today, history.clone());
+    Ok(history)
+}
+
+fn compute_today_history(db_path: &Path, today: String) -> AgentResult<TodayHistory> {
+    let conn = Connection::open(db_path)?;
+
+    let entries = history_entries_between(&conn, &today, &today)?;
+    let (total_seconds, mut category_breakdown, ticket_breakdown) = summarize_entries(&entries);
+    apply_category_display(&conn, &mut category_breakdown);
     
     Ok(TodayHistory {
         entries,
@@ -632,10 +2066,233 @@ pub fn get_today_history(state: State<'_, AgentState>) -> Result<TodayHistory, S
         category_breakdown,
         ticket_breakdown,
         date: today,
+        cached_at: chrono::Utc::now().to_rfc3339(),
     })
 }
 
 #[derive(Serialize, Deserialize, Debug)]
+pub struct RangeHistory {
+    pub entries: Vec<DayHistoryEntry>,
+    pub total_seconds: i32,
+    pub category_breakdown: Vec<CategoryBreakdown>,
+    pub ticket_breakdown: Vec<TicketBreakdown>,
+    pub from: String,
+    pub to: String,
+    pub days: i64,
+}
+
+/// Parses an inclusive `YYYY-MM-DD` range and rejects `from > to`.
+fn parse_date_range(from: &str, to: &str) -> Result<(chrono::NaiveDate, chrono::NaiveDate), String> {
+    let parse = |s: &str| {
+        chrono::NaiveDate::parse_from_str(s.trim(), "%Y-%m-%d")
+            .map_err(|_| format!("Invalid date '{}' (expected YYYY-MM-DD)", s))
+    };
+    let (from, to) = (parse(from)?, parse(to)?);
+    if from > to {
+        return Err(format!("'from' ({}) is after 'to' ({})", from, to));
+    }
+    Ok((from, to))
+}
+
+/// Same shape as `get_today_history`, for an inclusive local-date range ("last 7 days", a sprint…).
+#[tauri::command]
+pub fn get_history_range(
+    state: State<'_, AgentState>,
+    from: String,
+    to: String,
+) -> AgentResult<RangeHistory> {
+    let (from_date, to_date) = parse_date_range(&from, &to).map_err(AgentError::Config)?;
+    let agent = state.lock().unwrap();
+    let agent = agent.as_ref().ok_or(AgentError::NotInitialized)?;
+
+    let conn = Connection::open(&agent.db_path)?;
+    let from = from_date.format("%Y-%m-%d").to_string();
+    let to = to_date.format("%Y-%m-%d").to_string();
+
+    let entries = history_entries_between(&conn, &from, &to)?;
+    let (total_seconds, mut category_breakdown, ticket_breakdown) = summarize_entries(&entries);
+    apply_category_display(&conn, &mut category_breakdown);
+
+    Ok(RangeHistory {
+        entries,
+        total_seconds,
+        category_breakdown,
+        ticket_breakdown,
+        from,
+        to,
+        days: (to_date - from_date).num_days() + 1,
+    })
+}
+
+#[derive(Serialize, Debug)]
+pub struct TimelinePoint {
+    /// Bucket start, local time: `YYYY-MM-DD` for days, `YYYY-MM-DD HH:MM` otherwise.
+    pub start: String,
+    pub total_seconds: i64,
+    pub count: i64,
+}
+
+#[derive(Serialize, Debug)]
+pub struct ActivityTimeline {
+    /// Bucket size actually used (`"15min"`, `"hour"` or `"day"`), for axis labels.
+    pub bucket: &'static str,
+    pub from: String,
+    pub to: String,
+    /// Every bucket in the range, oldest first, zero-filled.
+    pub points: Vec<TimelinePoint>,
+    /// Consecutive Idle reports (including sleep markers) merged into start/end blocks.
+    pub idle_blocks: Vec<crate::agent_pure::IdleBlock>,
+}
+
+/// Tracked time per bucket over an inclusive local-date range: daily for the weekly overview,
+/// `hour` / `15min` for an intraday drill-down. Ranges over `TIMELINE_MAX_BUCKETS` are rejected.
+/// Runs of Idle reports also come back as `idle_blocks` with a start and end.
+#[tauri::command]
+pub fn get_activity_timeline(
+    state: State<'_, AgentState>,
+    from: String,
+    to: String,
+    bucket: Option<String>,
+) -> AgentResult<ActivityTimeline> {
+    let (from_date, to_date) = parse_date_range(&from, &to).map_err(AgentError::Config)?;
+    let bucket = crate::agent_pure::TimelineBucket::parse(bucket.as_deref()).map_err(AgentError::Config)?;
+    let keys = crate::agent_pure::timeline_keys(from_date, to_date, bucket).map_err(AgentError::Config)?;
+    let agent = state.lock().unwrap();
+    let agent = agent.as_ref().ok_or(AgentError::NotInitialized)?;
+
+    let conn = Connection::open(&agent.db_path)?;
+    let from = from_date.format("%Y-%m-%d").to_string();
+    let to = to_date.format("%Y-%m-%d").to_string();
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} AS b, SUM(duration_seconds), COUNT(*)
+         FROM reports
+         WHERE date(created_at, 'localtime') >= ?1 AND date(created_at, 'localtime') <= ?2
+         GROUP BY b",
+        bucket.sql_key()
+    ))?;
+    let mut totals = stmt
+        .query_map([&from, &to], |row| {
+            Ok((row.get::<_, String>(0)?, (row.get::<_, i64>(1)?, row.get::<_, i64>(2)?)))
+        })?
+        .collect::<Result<std::collections::HashMap<_, _>, _>>()?;
+
+    let points = keys
+        .into_iter()
+        .map(|start| {
+            let (total_seconds, count) = totals.remove(&start).unwrap_or((0, 0));
+            TimelinePoint { start, total_seconds, count }
+        })
+        .collect();
+
+    let local_ms = |ms: Option<i64>| {
+        chrono::DateTime::from_timestamp_millis(ms?).map(|t| t.with_timezone(&chrono::Local).naive_local())
+    };
+    let mut stmt = conn.prepare(
+        "SELECT datetime(created_at, 'localtime'), activity_type, COALESCE(duration_seconds, 0), metadata
+         FROM reports
+         WHERE date(created_at, 'localtime') >= ?1 AND date(created_at, 'localtime') <= ?2
+         ORDER BY id",
+    )?;
+    let rows = stmt
+        .query_map([&from, &to], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, Option<String>>(1)?.unwrap_or_default(),
+                row.get::<_, i64>(2)?,
+                row.get::<_, Option<String>>(3)?,
+            ))
+        })?
+        .filter_map(|r| r.ok())
+        .filter_map(|(at, activity_type, duration_seconds, metadata)| {
+            let at = chrono::NaiveDateTime::parse_from_str(&at, "%Y-%m-%d %H:%M:%S").ok()?;
+            let gap = metadata
+                .and_then(|m| serde_json::from_str::<serde_json::Value>(&m).ok())
+                .map(|m| m["sleepGap"].clone());
+            let sleep_gap = gap.and_then(|g| {
+                Some((local_ms(g["fromMs"].as_i64())?, local_ms(g["toMs"].as_i64())?))
+            });
+            Some(crate::agent_pure::TimelineRow { at, activity_type, duration_seconds, sleep_gap })
+        })
+        .collect::<Vec<_>>();
+
+    Ok(ActivityTimeline {
+        bucket: bucket.as_str(),
+        from,
+        to,
+        points,
+        idle_blocks: crate::agent_pure::merge_idle_blocks(&rows),
+    })
+}
+
+/// Runs the capture pipeline's category logic on `description` (built-in keywords plus the
+/// `reportLanguage` set) and returns the pick with its per-category scores, for rule tuning.
+#[tauri::command]
+pub fn classify_description(
+    state: State<'_, AgentState>,
+    description: String,
+) -> AgentResult<crate::agent_pure::Classification> {
+    let language = VisionParams::from_config(state.lock().unwrap().as_ref().map(|a| &a.config)).language;
+    Ok(crate::agent_pure::classify_description(&description, language))
+}
+
+/// Label and color for every known category plus any other type found in reports or overrides.
+#[tauri::command]
+pub fn get_category_display(state: State<'_, AgentState>) -> AgentResult<Vec<CategoryDisplay>> {
+    let agent = state.lock().unwrap();
+    let agent = agent.as_ref().ok_or(AgentError::NotInitialized)?;
+    let conn = Connection::open(&agent.db_path)?;
+    let overrides = load_category_overrides(&conn);
+
+    let mut names: Vec<String> = crate::agent_pure::default_category_names().map(str::to_string).collect();
+    let mut stmt = conn.prepare("SELECT DISTINCT activity_type FROM reports WHERE activity_type IS NOT NULL")?;
+    let stored = stmt.query_map([], |row| row.get::<_, String>(0))?.filter_map(|r| r.ok());
+    for name in stored.chain(overrides.keys().cloned()) {
+        if !name.trim().is_empty() && !names.contains(&name) {
+            names.push(name);
+        }
+    }
+    Ok(names.iter().map(|n| category_display_with(&overrides, n)).collect())
+}
+
+/// Overrides a category's label and/or color (`#rrggbb`); `None` or `""` restores that default.
+#[tauri::command]
+pub fn set_category_display(
+    state: State<'_, AgentState>,
+    category: String,
+    label: Option<String>,
+    color: Option<String>,
+) -> AgentResult<CategoryDisplay> {
+    let category = crate::agent_pure::canonical_category(&category)
+        .map(str::to_string)
+        .unwrap_or_else(|| category.trim().to_string());
+    if category.is_empty() {
+        return Err(AgentError::Config("category is required".to_string()));
+    }
+    let label = label.map(|l| l.trim().to_string()).filter(|l| !l.is_empty());
+    let color = color
+        .as_deref()
+        .map(str::trim)
+        .filter(|c| !c.is_empty())
+        .map(crate::agent_pure::normalize_hex_color)
+        .transpose()
+        .map_err(AgentError::Config)?;
+
+    let mut guard = state.lock().unwrap();
+    let agent = guard.as_mut().ok_or(AgentError::NotInitialized)?;
+    let conn = Connection::open(&agent.db_path)?;
+    if label.is_none() && color.is_none() {
+        conn.execute("DELETE FROM category_display WHERE category = ?", [&category])?;
+    } else {
+        conn.execute(
+            "INSERT OR REPLACE INTO category_display (category, label, color) VALUES (?, ?, ?)",
+            params![category, label, color],
+        )?;
+    }
+    agent.summary_cache.invalidate();
+    Ok(category_display_with(&load_category_overrides(&conn), &category))
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct DayActivity {
     pub date: String,
     pub weekday: String,
@@ -644,19 +2301,32 @@ pub struct DayActivity {
     pub is_today: bool,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct WeekSummary {
     pub days: Vec<DayActivity>,
     pub yesterday_seconds: i32,
+    /// When this summary was computed (RFC 3339); may be up to `SUMMARY_CACHE_TTL` old.
+    #[serde(default)]
+    pub cached_at: String,
 }
 
 #[tauri::command]
-pub fn get_week_summary(state: State<'_, AgentState>) -> Result<WeekSummary, String> {
-    let agent = state.lock().unwrap();
-    let agent = agent.as_ref().ok_or("Agent not initialized")?;
-
-    let conn = Connection::open(&agent.db_path).map_err(|e| e.to_string())?;
+pub fn get_week_summary(state: State<'_, AgentState>) -> AgentResult<WeekSummary> {
+    let mut agent = state.lock().unwrap();
+    let agent = agent.as_mut().ok_or(AgentError::NotInitialized)?;
     let today = Local::now().date_naive();
+    let key = today.format("%Y-%m-%d").to_string();
+    agent.summary_cache.drop_if_reports_deleted();
+    if let Some(hit) = agent.summary_cache.week.fresh(&key) {
+        return Ok(hit);
+    }
+    let summary = compute_week_summary(&agent.db_path, today)?;
+    agent.summary_cache.week.store(key, summary.clone());
+    Ok(summary)
+}
+
+fn compute_week_summary(db_path: &Path, today: chrono::NaiveDate) -> AgentResult<WeekSummary> {
+    let conn = Connection::open(db_path)?;
     let weekday = today.weekday().num_days_from_monday();
     let week_start = today - chrono::Duration::days(weekday as i64);
     let week_end = week_start + chrono::Duration::days(6);
@@ -710,9 +2380,63 @@ pub fn get_week_summary(state: State<'_, AgentState>) -> Result<WeekSummary, Str
     Ok(WeekSummary {
         days,
         yesterday_seconds,
+        cached_at: chrono::Utc::now().to_rfc3339(),
     })
 }
 
+/// Report counts per hour for the last `hours` hours (oldest first, max 168), for an inline
+/// sparkline. One grouped query; empty hours are filled in as 0.
+#[tauri::command]
+pub fn get_activity_sparkline(state: State<'_, AgentState>, hours: u32) -> AgentResult<Vec<i64>> {
+    let agent = state.lock().unwrap();
+    let agent = agent.as_ref().ok_or(AgentError::NotInitialized)?;
+
+    let conn = Connection::open(&agent.db_path)?;
+    let hours = hours.clamp(1, crate::agent_pure::SPARKLINE_MAX_HOURS);
+    let mut stmt = conn.prepare(
+        "SELECT strftime('%Y-%m-%d %H', created_at) AS h, COUNT(*)
+         FROM reports
+         WHERE created_at >= strftime('%Y-%m-%d %H:00:00', 'now', ?1)
+         GROUP BY h",
+    )?;
+    let counts = stmt
+        .query_map([format!("-{} hours", hours - 1)], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?))
+        })?
+        .collect::<Result<std::collections::HashMap<_, _>, _>>()?;
+
+    Ok(crate::agent_pure::hourly_sparkline(&counts, chrono::Utc::now(), hours))
+}
+
+/// Reports per local day for the last `days` days (1-366), every day present (zero when
+/// empty), keyed `YYYY-MM-DD` in date order, for a contribution-graph style calendar.
+#[tauri::command]
+pub fn get_activity_heatmap(
+    state: State<'_, AgentState>,
+    days: u32,
+) -> AgentResult<std::collections::BTreeMap<String, i64>> {
+    let agent = state.lock().unwrap();
+    let agent = agent.as_ref().ok_or(AgentError::NotInitialized)?;
+
+    let conn = Connection::open(&agent.db_path)?;
+    let days = days.clamp(1, crate::agent_pure::HEATMAP_MAX_DAYS);
+    let today = chrono::Local::now().date_naive();
+    let from = today - chrono::Duration::days(i64::from(days) - 1);
+    let mut stmt = conn.prepare(
+        "SELECT date(created_at, 'localtime') AS d, COUNT(*)
+         FROM reports
+         WHERE date(created_at, 'localtime') >= ?1
+         GROUP BY d",
+    )?;
+    let counts = stmt
+        .query_map([from.format("%Y-%m-%d").to_string()], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?))
+        })?
+        .collect::<Result<std::collections::HashMap<_, _>, _>>()?;
+
+    Ok(crate::agent_pure::daily_heatmap(&counts, today, days))
+}
+
 // Health check against nuestro llama-server local (NO es ollama; el nombre se
 // mantuvo en el tauri command hist\u00f3ricamente pero el endpoint es de llama.cpp).
 //
@@ -725,26 +2449,34 @@ fn local_server_health_ok() -> bool {
     let Some(health_url) = crate::llama_port::managed_health_url() else {
         return false;
     };
-    let Ok(client) = reqwest::blocking::Client::builder()
-        .timeout(std::time::Duration::from_secs(LOCAL_HEALTH_HTTP_TIMEOUT_SECS))
-        .build()
-    else {
-        return false;
-    };
-    client
+    crate::http_client::local_client()
         .get(&health_url)
+        .timeout(std::time::Duration::from_secs(LOCAL_HEALTH_HTTP_TIMEOUT_SECS))
         .send()
         .ok()
         .map(|r| r.status().is_success())
         .unwrap_or(false)
 }
 
+/// How much of server.log `check_local_server` scans for the GPU offload line.
+const SERVER_LOG_OFFLOAD_SCAN_CHARS: usize = 256 * 1024;
+
+/// Health of the analysis backend. For the managed server, also the GPU / CPU split llama.cpp
+/// logged at load (`placement`: `gpu` / `partial` / `cpu`) so the UI can explain slow captures.
 #[tauri::command]
 pub fn check_local_server() -> Result<serde_json::Value, String> {
-    let client = reqwest::blocking::Client::builder()
-        .timeout(std::time::Duration::from_secs(LOCAL_HEALTH_HTTP_TIMEOUT_SECS))
-        .build()
-        .map_err(|e| e.to_string())?;
+    let backend = crate::analysis_backend::current();
+    if backend.is_external() {
+        return Ok(crate::analysis_backend::external_health(&backend));
+    }
+    if let Some(ended) = reap_exited_server() {
+        return Ok(serde_json::json!({
+            "online": false,
+            "installed": true,
+            "error": format!("Local model server exited ({}). Start Local AI again.", ended)
+        }));
+    }
+    let client = crate::http_client::local_client();
 
     let Some(health_url) = crate::llama_port::managed_health_url() else {
         return Ok(serde_json::json!({
@@ -754,14 +2486,32 @@ pub fn check_local_server() -> Result<serde_json::Value, String> {
         }));
     };
 
-    match client.get(&health_url).send() {
-        Ok(r) if r.status().is_success() => Ok(serde_json::json!({
-            "online": true,
-            "installed": true,
-            "models": [VISION_STATUS_LABEL],
-            "hasVisionModel": true,
-            "localServerPort": crate::llama_port::current_managed_listen_port(),
-        })),
+    match client
+        .get(&health_url)
+        .timeout(std::time::Duration::from_secs(LOCAL_HEALTH_HTTP_TIMEOUT_SECS))
+        .send()
+    {
+        Ok(r) if r.status().is_success() => {
+            let offload = crate::agent_pure::parse_gpu_offload(&read_server_log_tail_chars(SERVER_LOG_OFFLOAD_SCAN_CHARS));
+            let (placement, placement_warning) = match offload {
+                Some((done, total)) => {
+                    let (p, w) = crate::agent_pure::gpu_placement(done, total);
+                    (Some(p), w)
+                }
+                None => (None, None),
+            };
+            Ok(serde_json::json!({
+                "online": true,
+                "installed": true,
+                "models": [VISION_STATUS_LABEL],
+                "hasVisionModel": true,
+                "localServerPort": crate::llama_port::current_managed_listen_port(),
+                "gpuLayersOffloaded": offload.map(|(done, _)| done),
+                "gpuLayersTotal": offload.map(|(_, total)| total),
+                "placement": placement,
+                "placementWarning": placement_warning,
+            }))
+        }
         Ok(r) => Ok(serde_json::json!({
             "online": false,
             "installed": true,
@@ -786,6 +2536,26 @@ pub fn check_ollama() -> Result<serde_json::Value, String> {
 
 static SERVER_PROCESS: Mutex<Option<std::process::Child>> = Mutex::new(None);
 
+/// If the managed child has exited (crash, OOM, killed from Task Manager), frees the slot and
+/// the managed port so callers stop reporting a phantom "already running". Returns how it ended.
+fn reap_exited_server() -> Option<String> {
+    let mut guard = SERVER_PROCESS.lock().unwrap();
+    let ended = match guard.as_mut()?.try_wait() {
+        Ok(None) => return None,
+        Ok(Some(status)) => match status.code() {
+            Some(code) => format!("exit code {}", code),
+            None => "terminated by signal".to_string(),
+        },
+        Err(e) => format!("status unavailable: {}", e),
+    };
+    guard.take();
+    crate::llama_port::clear_managed_llama_port();
+    #[cfg(windows)]
+    crate::llama_windows_job::reset_llama_job();
+    log::warn!("[LlamaServer] managed llama-server exited unexpectedly ({})", ended);
+    Some(ended)
+}
+
 /// Puertos nuevos ante `EADDRINUSE`/fallo rápido de escucha tras TOCTOU o TIME_WAIT.
 const LLAMA_LISTEN_PORT_SPAWN_ATTEMPTS: u8 = 8;
 
@@ -829,25 +2599,7 @@ fn wait_for_managed_health_secs(max_secs: u64) -> bool {
             return true;
         }
 
-        let still_running = {
-            let mut g = SERVER_PROCESS.lock().unwrap();
-            match g.as_mut() {
-                Some(ch) => match ch.try_wait() {
-                    Ok(Some(_)) => {
-                        g.take();
-                        false
-                    }
-                    Ok(None) => true,
-                    Err(_) => {
-                        let _ = g.take();
-                        false
-                    }
-                },
-                None => false,
-            }
-        };
-
-        if !still_running {
+        if reap_exited_server().is_some() || SERVER_PROCESS.lock().unwrap().is_none() {
             return false;
         }
 
@@ -863,7 +2615,7 @@ pub fn ensure_local_llm_ready(app: tauri::AppHandle, state: State<'_, AgentState
     }
 
     log::info!("[LocalReport] Local AI offline — starting server for insight generation…");
-    let result = start_server(app, state)?;
+    let result = start_server(app, state).map_err(|e| e.to_string())?;
     let status = result["status"].as_str().unwrap_or("");
     if status != "started" && status != "already_running" {
         return Err(format!("Could not start local AI: {}", result));
@@ -891,7 +2643,11 @@ fn read_server_log_tail_chars(max_chars: usize) -> String {
     if s.len() <= max_chars {
         s
     } else {
-        s[s.len() - max_chars..].to_string()
+        let mut start = s.len() - max_chars;
+        while !s.is_char_boundary(start) {
+            start += 1;
+        }
+        s[start..].to_string()
     }
 }
 
@@ -908,6 +2664,7 @@ pub fn llama_managed_process_status() -> Result<serde_json::Value, String> {
             Ok(Some(status)) => {
                 let code = status.code();
                 guard.take();
+                crate::llama_port::clear_managed_llama_port();
                 Ok(serde_json::json!({
                     "managed": true,
                     "alive": false,
@@ -1207,8 +2964,43 @@ fn spawn_llama_managed_child(
 /// Arranca llama-server: modo automático sube desde capas GPU altas hasta que `/health`
 /// responda; modo manual fuerza `--n-gpu-layers` fijo.
 #[tauri::command]
-pub fn start_server(app: tauri::AppHandle, state: State<'_, AgentState>) -> Result<serde_json::Value, String> {
-    let mode = gpu_serve_mode(&state);
+pub fn start_server(app: tauri::AppHandle, state: State<'_, AgentState>) -> AgentResult<serde_json::Value> {
+    let result = start_llama_server(&app, &state);
+    emit_server_event(&app, &result);
+    result
+}
+
+/// `server-started` (payload = the start result) / `server-error` (`{ message }`), so the
+/// renderer does not have to poll `check_local_server` to learn the outcome. `already_running`
+/// is not a state change and emits nothing.
+fn emit_server_event(app: &tauri::AppHandle, result: &AgentResult<serde_json::Value>) {
+    let emitted = match result {
+        Ok(v) if v["status"] == "started" => app.emit("server-started", v.clone()),
+        Ok(_) => return,
+        Err(e) => app.emit("server-error", serde_json::json!({ "message": e.to_string() })),
+    };
+    if let Err(e) = emitted {
+        log::warn!("[LlamaServer] event emit failed: {}", e);
+    }
+}
+
+fn start_llama_server(app: &tauri::AppHandle, state: &State<'_, AgentState>) -> AgentResult<serde_json::Value> {
+    if crate::analysis_backend::current().is_external() {
+        // nothing to spawn; the renderer's health poll goes to the external endpoint
+        return Ok(serde_json::json!({
+            "status": "already_running",
+            "message": "Using external analysis backend",
+            "external": true,
+            "gpuAuto": false,
+        }));
+    }
+    let mode = gpu_serve_mode(state);
+    if let Some(ended) = reap_exited_server() {
+        let _ = app.emit(
+            "server-error",
+            serde_json::json!({ "message": format!("Local model server exited ({}); restarting", ended) }),
+        );
+    }
     {
         let guard = SERVER_PROCESS.lock().unwrap();
         if guard.is_some() {
@@ -1224,7 +3016,8 @@ pub fn start_server(app: tauri::AppHandle, state: State<'_, AgentState>) -> Resu
     match mode {
         GpuServeMode::Manual(gpu_layers) => {
             let mut guard = SERVER_PROCESS.lock().unwrap();
-            let child = spawn_llama_managed_child(&app, gpu_layers, None)?;
+            let child = spawn_llama_managed_child(app, gpu_layers, None)
+                .map_err(AgentError::LlamaServer)?;
             *guard = Some(child);
             Ok(serde_json::json!({
                 "status": "started",
@@ -1245,10 +3038,10 @@ pub fn start_server(app: tauri::AppHandle, state: State<'_, AgentState>) -> Resu
             for vk_vis in vk_rounds {
                 let vk_label = vk_vis.unwrap_or("default");
                 for &layers in AUTO_GPU_LAYER_TIERS {
-                    let _ = stop_server();
+                    let _ = stop_llama_server();
                     std::thread::sleep(std::time::Duration::from_millis(450));
 
-                    let child = match spawn_llama_managed_child(&app, layers, vk_vis) {
+                    let child = match spawn_llama_managed_child(app, layers, vk_vis) {
                         Ok(c) => c,
                         Err(e) => {
                             log::warn!(
@@ -1301,31 +3094,39 @@ pub fn start_server(app: tauri::AppHandle, state: State<'_, AgentState>) -> Resu
                         }
                     );
                     log::warn!("[FlowSight llama-server] {}", last_err);
-                    let _ = stop_server();
+                    let _ = stop_llama_server();
                     std::thread::sleep(std::time::Duration::from_millis(350));
                 }
             }
 
-            Err(format!(
+            Err(AgentError::LlamaServer(format!(
                 "Automatic GPU tier startup failed on all steps. {}",
                 last_err
-            ))
+            )))
         }
     }
 }
 
 /// Tras fallos interminables con GPU (drivers/hardware), reinicia sólo CPU — más lento pero mucho más compatible.
 #[tauri::command]
-pub fn restart_llama_server_cpu_only(app: tauri::AppHandle) -> Result<serde_json::Value, String> {
-    let _ = stop_server();
+pub fn restart_llama_server_cpu_only(app: tauri::AppHandle) -> AgentResult<serde_json::Value> {
+    let result = restart_cpu_only(&app);
+    emit_server_event(&app, &result);
+    result
+}
+
+fn restart_cpu_only(app: &tauri::AppHandle) -> AgentResult<serde_json::Value> {
+    let _ = stop_llama_server();
     std::thread::sleep(std::time::Duration::from_millis(500));
 
     let mut guard = SERVER_PROCESS.lock().unwrap();
     if guard.is_some() {
-        return Err("Could not clear managed server slot; try restarting FlowSight.".to_string());
+        return Err(AgentError::LlamaServer(
+            "Could not clear managed server slot; try restarting FlowSight.".to_string(),
+        ));
     }
 
-    let child = spawn_llama_managed_child(&app, 0, None)?;
+    let child = spawn_llama_managed_child(app, 0, None).map_err(AgentError::LlamaServer)?;
     *guard = Some(child);
     Ok(serde_json::json!({
         "status": "started",
@@ -1339,7 +3140,15 @@ pub fn restart_llama_server_cpu_only(app: tauri::AppHandle) -> Result<serde_json
 }
 
 #[tauri::command]
-pub fn stop_server() -> Result<bool, String> {
+pub fn stop_server(app: tauri::AppHandle) -> Result<bool, String> {
+    let stopped = stop_llama_server()?;
+    if let Err(e) = app.emit("server-stopped", ()) {
+        log::warn!("[LlamaServer] event emit failed: {}", e);
+    }
+    Ok(stopped)
+}
+
+fn stop_llama_server() -> Result<bool, String> {
     let mut guard = SERVER_PROCESS.lock().unwrap();
     if let Some(mut child) = guard.take() {
         let _ = child.kill();
@@ -1381,21 +3190,98 @@ fn truncate_repetition(text: &str) -> String {
     }
 
     if result.len() < words.len() {
-        println!("[Vision] Truncated {} repeated tokens from output", words.len() - result.len());
+        log::debug!("[Vision] Truncated {} repeated tokens from output", words.len() - result.len());
     }
     result.join(" ")
 }
 
+/// Half-size JPEG of `base64_img`, logged so users on very large displays know why the model
+/// sees a lower resolution.
+fn shrink_for_vision(base64_img: &str, reason: &str) -> Result<String, String> {
+    let (small, w, h) = crate::capture_target::shrink_base64_image(base64_img)?;
+    log::warn!(
+        "[Vision] {}: resending frame at {}x{} JPEG ({} -> {} base64 bytes)",
+        reason,
+        w,
+        h,
+        base64_img.len(),
+        small.len()
+    );
+    Ok(small)
+}
+
+/// A failed vision call. `retry_smaller` marks failures that point at the frame size rather
+/// than at the model, so they are worth one retry with a smaller frame.
+struct VisionError {
+    message: String,
+    retry_smaller: bool,
+}
+
+impl From<String> for VisionError {
+    fn from(message: String) -> Self {
+        VisionError { message, retry_smaller: false }
+    }
+}
+
+/// The server or a proxy refused the body size (HTTP 413), the connection could not be set
+/// up in time, or the request body could not be written. A plain response timeout is a slow
+/// model, and retrying it would just cost a second `visionTimeoutSecs` wait.
+fn is_payload_rejection(status: Option<u16>, connect_timeout: bool, upload_failed: bool) -> bool {
+    status == Some(413) || connect_timeout || upload_failed
+}
+
+/// Whether reqwest failed while sending the request body (hyper's "error writing a body").
+fn body_write_failed(err: &reqwest::Error) -> bool {
+    let mut source = std::error::Error::source(err);
+    while let Some(e) = source {
+        if e.to_string().contains("writing a body") {
+            return true;
+        }
+        source = e.source();
+    }
+    false
+}
+
 // RESTORED AI ANALYSIS (Backend)
+/// Frames over `maxImageBytes` are shrunk before sending; a 413 or connect/upload failure on a
+/// full-size frame is retried once at half size instead of failing the capture.
 #[tauri::command]
-fn analyze_image_with_vision(base64_img: &str, current_task: &str, _gpu_layers: Option<i32>) -> Result<String, String> {
-    let chat_url = crate::llama_port::managed_chat_completions_url().ok_or_else(|| {
-        "Local vision server URL unknown — start the embedded Local AI server first.".to_string()
-    })?;
-    let client = reqwest::blocking::Client::builder()
-        .timeout(std::time::Duration::from_secs(300))
-        .build()
-        .map_err(|e| e.to_string())?;
+fn analyze_image_with_vision(
+    base64_img: &str,
+    current_task: &str,
+    gpu_layers: Option<i32>,
+    params: VisionParams,
+) -> Result<String, String> {
+    let limit = crate::capture_target::max_image_bytes();
+    let (image, shrunk) = if base64_img.len() as u64 > limit {
+        match shrink_for_vision(base64_img, &format!("Frame exceeds maxImageBytes ({})", limit)) {
+            Ok(small) => (std::borrow::Cow::Owned(small), true),
+            Err(e) => {
+                log::warn!("[Vision] Could not shrink oversized frame, sending as-is: {}", e);
+                (std::borrow::Cow::Borrowed(base64_img), false)
+            }
+        }
+    } else {
+        (std::borrow::Cow::Borrowed(base64_img), false)
+    };
+    match vision_request(&image, current_task, gpu_layers, params) {
+        Err(e) if !shrunk && e.retry_smaller => {
+            let small = shrink_for_vision(&image, &e.message).map_err(|_| e.message)?;
+            vision_request(&small, current_task, gpu_layers, params).map_err(|e| e.message)
+        }
+        other => other.map_err(|e| e.message),
+    }
+}
+
+fn vision_request(
+    base64_img: &str,
+    current_task: &str,
+    _gpu_layers: Option<i32>,
+    params: VisionParams,
+) -> Result<String, VisionError> {
+    let backend = crate::analysis_backend::current();
+    let chat_url = backend.chat_completions_url()?;
+    let client = backend.client()?;
 
     let system_msg = "You are a screenshot analysis assistant. You ALWAYS respond with a filled-in template. You NEVER refuse. You NEVER say you cannot see the image. Be accurate and concise: capture the user's primary task, not a full inventory of the UI.";
 
@@ -1418,12 +3304,16 @@ CATEGORY: [pick exactly ONE from: Coding, Debugging, CodeReview, Testing, Docume
 CATEGORY rules: use Coding ONLY for software development (editing code, debugging in an IDE, repo/PR review in a dev tool, programming-focused terminal). Spreadsheets (Excel/Sheets), email, chat, slides, PDFs, CRM, and generic browsing are NOT Coding unless the visible work is clearly programming.]"#,
         current_task
     );
+    let prompt = match crate::agent_pure::report_language_instruction(params.language) {
+        Some(instruction) => format!("{}\n\n{}", prompt, instruction),
+        None => prompt,
+    };
 
-    // Retry up to 2 times on empty/refusal responses
+    // Retry up to 2 times on empty/refusal/too-short responses
     let max_attempts = 2;
     for attempt in 1..=max_attempts {
-        let body = serde_json::json!({
-            "model": LLAMA_CHAT_MODEL_ID,
+        let mut body = serde_json::json!({
+            "model": backend.model(LLAMA_CHAT_MODEL_ID),
             "messages": [
                 {
                     "role": "system",
@@ -1436,31 +3326,49 @@ CATEGORY rules: use Coding ONLY for software development (editing code, debuggin
                         {
                             "type": "image_url",
                             "image_url": {
-                                "url": format!("data:image/png;base64,{}", base64_img)
+                                "url": format!(
+                                    "data:{};base64,{}",
+                                    crate::capture_target::image_mime(base64_img),
+                                    base64_img
+                                )
                             }
                         }
                     ]
                 }
             ],
-            "temperature": 0.1,
+            "temperature": params.temperature,
             "top_p": 0.9,
-            "max_tokens": 800,
-            "repeat_penalty": 1.3,
+            "max_tokens": params.max_tokens,
             "frequency_penalty": 0.5,
             "presence_penalty": 0.5,
             "stream": false
         });
+        // llama.cpp-only sampling knob; hosted OpenAI-compatible APIs reject unknown fields
+        if matches!(backend, crate::analysis_backend::VisionBackend::LocalLlama) {
+            body["repeat_penalty"] = serde_json::json!(1.3);
+        }
 
         let resp = client.post(&chat_url)
+            .timeout(std::time::Duration::from_secs(params.timeout_secs))
             .json(&body)
             .send()
-            .map_err(|e| format!("Request failed: {}", e))?;
-
-        if !resp.status().is_success() {
-            return Err(format!("Server Error: {}", resp.status()));
+            .map_err(|e| VisionError {
+                message: if e.is_timeout() {
+                    format!("Vision request timed out after {}s (visionTimeoutSecs)", params.timeout_secs)
+                } else {
+                    format!("Request failed: {}", e)
+                },
+                retry_smaller: is_payload_rejection(None, e.is_connect() && e.is_timeout(), body_write_failed(&e)),
+            })?;
+
+        let status = resp.status().as_u16();
+        if is_payload_rejection(Some(status), false, false) {
+            return Err(VisionError {
+                message: format!("Vision request failed ({})", resp.status()),
+                retry_smaller: true,
+            });
         }
-
-        let json: serde_json::Value = resp.json().map_err(|e| e.to_string())?;
+        let json = crate::http_client::read_json(resp, "Vision request")?;
         let content = json["choices"][0]["message"]["content"].as_str().unwrap_or("").trim();
 
         // Detect empty or refusal responses
@@ -1478,30 +3386,53 @@ CATEGORY rules: use Coding ONLY for software development (editing code, debuggin
             || c.contains("no puedo ver")
             || c.contains("no puedo analizar");
 
-        if is_empty || is_refusal {
-            println!("[Vision] Attempt {}/{}: empty or refusal response, retrying...", attempt, max_attempts);
+        let chars = crate::agent_pure::description_chars(content);
+        let is_too_short = !is_empty && chars < params.min_description_chars;
+
+        if is_empty || is_refusal || is_too_short {
+            log::warn!(
+                "[Vision] Attempt {}/{}: empty, refusal or too-short ({} chars) response, retrying...",
+                attempt,
+                max_attempts,
+                chars
+            );
             if attempt < max_attempts {
                 std::thread::sleep(std::time::Duration::from_secs(1));
                 continue;
             }
-            return Err(if is_empty {
+            return Err(VisionError::from(if is_empty {
                 "Model returned empty response after retries".to_string()
-            } else {
+            } else if is_refusal {
                 "Model refused or could not analyze the screenshot after retries".to_string()
-            });
+            } else {
+                format!(
+                    "Model description too short after retries ({} chars, minDescriptionLength {})",
+                    chars, params.min_description_chars
+                )
+            }));
         }
 
         let content = truncate_repetition(content);
         return Ok(content);
     }
 
-    Err("Model analysis failed after retries".to_string())
+    Err(VisionError::from("Model analysis failed after retries".to_string()))
 }
 
 #[cfg(test)]
 mod agent_struct_tests {
     use super::*;
 
+    #[test]
+    fn only_size_related_vision_errors_retry_smaller() {
+        assert!(is_payload_rejection(Some(413), false, false));
+        assert!(is_payload_rejection(None, true, false));
+        assert!(is_payload_rejection(None, false, true));
+        // A slow model (plain response timeout) or a server error is not retried smaller.
+        assert!(!is_payload_rejection(None, false, false));
+        assert!(!is_payload_rejection(Some(500), false, false));
+    }
+
     #[test]
     fn agent_config_json_roundtrip_negative_one_auto_marker() {
         let c = AgentConfig {
@@ -1510,12 +3441,27 @@ mod agent_struct_tests {
             vision_model: Some("model-id".into()),
             gpu_layers: Some(-1),
             daily_goal_hours: Some(6.0),
+            ..Default::default()
         };
         let json = serde_json::to_string(&c).unwrap();
         let back: AgentConfig = serde_json::from_str(&json).unwrap();
         assert_eq!(back.gpu_layers, Some(-1));
     }
 
+    #[test]
+    fn config_view_never_serializes_api_key() {
+        let c = AgentConfig {
+            api_key: Some("sk-secret".into()),
+            ..Default::default()
+        };
+        let v = serde_json::to_value(ConfigView::from(c)).unwrap();
+        assert!(v.get("apiKey").is_none(), "key leaked to the webview");
+        assert_eq!(v["apiKeySet"], true);
+        // still accepted on the way in (update_config)
+        let patch: AgentConfig = serde_json::from_str(r#"{"apiKey":"sk-new"}"#).unwrap();
+        assert_eq!(patch.api_key.as_deref(), Some("sk-new"));
+    }
+
     #[test]
     fn agent_config_json_roundtrip() {
         let c = AgentConfig {
@@ -1524,6 +3470,7 @@ mod agent_struct_tests {
             vision_model: Some("model-id".into()),
             gpu_layers: Some(4),
             daily_goal_hours: Some(8.0),
+            ..Default::default()
         };
         let json = serde_json::to_string(&c).unwrap();
         let back: AgentConfig = serde_json::from_str(&json).unwrap();
@@ -1531,6 +3478,58 @@ mod agent_struct_tests {
         assert_eq!(back.gpu_layers, c.gpu_layers);
     }
 
+    #[test]
+    fn category_overrides_apply_to_breakdown() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE category_display (category TEXT PRIMARY KEY, label TEXT, color TEXT);
+             INSERT INTO category_display VALUES ('Coding', 'Deep work', NULL);",
+        )
+        .unwrap();
+        let entry = |category: &str| DayHistoryEntry {
+            time: "t".into(),
+            description: "d".into(),
+            category: category.into(),
+            ticket: None,
+            duration_seconds: 30,
+        };
+        let (_, mut breakdown, _) = summarize_entries(&[entry("Coding"), entry("Pairing")]);
+        apply_category_display(&conn, &mut breakdown);
+        breakdown.sort_by(|a, b| a.category.cmp(&b.category));
+        assert_eq!(breakdown[0].label, "Deep work");
+        assert_eq!(breakdown[0].color, "#7c3aed", "color override not set");
+        assert_eq!(breakdown[1].label, "Pairing");
+        assert_eq!(breakdown[1].color, crate::agent_pure::fallback_category_color("Pairing"));
+    }
+
+    #[test]
+    fn summary_cache_expires_and_is_per_day() {
+        let mut c: CachedSummary<u32> = CachedSummary::default();
+        assert_eq!(c.fresh("2026-03-02"), None);
+        c.store("2026-03-02".into(), 7);
+        let now = std::time::Instant::now();
+        assert_eq!(c.fresh_at("2026-03-02", now), Some(7));
+        assert_eq!(c.fresh_at("2026-03-03", now), None, "day rolled over");
+        assert_eq!(c.fresh_at("2026-03-02", now + SUMMARY_CACHE_TTL), None);
+    }
+
+    #[test]
+    fn summary_cache_drops_after_retention_delete() {
+        let mut cache = SummaryCache::default();
+        cache.drop_if_reports_deleted();
+        let week = WeekSummary { days: vec![], yesterday_seconds: 0, cached_at: String::new() };
+        cache.week.store("2026-03-02".into(), week);
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE reports (id INTEGER PRIMARY KEY, synced INTEGER DEFAULT 0, created_at TEXT);
+             INSERT INTO reports (synced, created_at) VALUES (1, datetime('now', '-40 days'));",
+        )
+        .unwrap();
+        assert_eq!(crate::retention::delete_reports_older_than(&conn, 30, false).unwrap(), 1);
+        cache.drop_if_reports_deleted();
+        assert!(cache.week.fresh("2026-03-02").is_none(), "deleted reports still summarized");
+    }
+
     #[test]
     fn activity_report_serializes() {
         let r = ActivityReport {
@@ -1539,10 +3538,139 @@ mod agent_struct_tests {
             description: "d".into(),
             activity_type: "coding".into(),
             synced: false,
+            activity_level: None,
+            metadata: Some(serde_json::json!({"pr": 7})),
         };
         let v = serde_json::to_value(&r).unwrap();
+        assert_eq!(v["metadata"]["pr"], 7);
         assert_eq!(v["activity_type"], "coding");
     }
+
+    #[test]
+    fn vision_params_defaults_and_validation() {
+        let p = VisionParams::from_config(None);
+        assert_eq!(p.temperature, VISION_TEMPERATURE_DEFAULT);
+        assert_eq!(p.max_tokens, VISION_MAX_TOKENS_DEFAULT);
+        assert!(validate_vision_temperature(1.5).is_err());
+        assert!(validate_vision_max_tokens(0).is_err());
+        assert_eq!(validate_vision_max_tokens(1024), Ok(1024));
+        assert_eq!(p.min_description_chars, MIN_DESCRIPTION_LENGTH_DEFAULT as usize);
+        assert_eq!(validate_min_description_length(0), Ok(0));
+        assert!(validate_min_description_length(MIN_DESCRIPTION_LENGTH_MAX + 1).is_err());
+    }
+
+    #[test]
+    fn battery_capture_interval_is_bounded() {
+        assert!(validate_capture_interval_ms(1_000).is_err());
+        assert!(validate_capture_interval_ms(7_200_000).is_err());
+        assert_eq!(validate_capture_interval_ms(300_000), Ok(300_000));
+    }
+
+    #[test]
+    fn save_config_none_clears_persisted_value() {
+        let db_path = std::env::temp_dir().join(format!(
+            "flowsight-cfg-test-{}.db",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_file(&db_path);
+        let mut agent = FlowSightAgent {
+            config: AgentConfig {
+                retention_days: Some(30),
+                ..Default::default()
+            },
+            is_running: false,
+            reports_sent: 0,
+            db_path: db_path.clone(),
+            summary_cache: SummaryCache::default(),
+        };
+        agent.init_db();
+        agent.save_config();
+        agent.config.retention_days = None;
+        agent.save_config();
+
+        agent.config.retention_days = Some(99);
+        agent.load_config();
+        assert_eq!(agent.config.retention_days, None);
+        let _ = std::fs::remove_file(&db_path);
+    }
+
+    #[test]
+    fn get_recent_filters_by_activity_type() {
+        let db_path = std::env::temp_dir().join(format!(
+            "flowsight-recent-test-{}.db",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_file(&db_path);
+        let agent = FlowSightAgent {
+            config: AgentConfig::default(),
+            is_running: false,
+            reports_sent: 0,
+            db_path: db_path.clone(),
+            summary_cache: SummaryCache::default(),
+        };
+        agent.init_db();
+        for (desc, ty) in [("standup", "Meeting"), ("edit", "Coding"), ("1:1", "Meeting")] {
+            agent.save_report(desc, ty, None, 30, None, None);
+        }
+        assert_eq!(agent.get_recent(20, None, None).len(), 3);
+        let meetings = agent.get_recent(20, Some("Meeting"), None);
+        assert_eq!(
+            meetings.iter().map(|r| r.description.as_str()).collect::<Vec<_>>(),
+            vec!["1:1", "standup"]
+        );
+        assert_eq!(agent.get_recent(1, Some("Meeting"), None).len(), 1);
+        let first_id = agent.get_recent(20, None, None).last().and_then(|r| r.id).unwrap();
+        let newer = agent.get_recent(20, None, Some(first_id));
+        assert_eq!(
+            newer.iter().map(|r| r.description.as_str()).collect::<Vec<_>>(),
+            vec!["edit", "1:1"],
+            "since_id returns newer reports oldest-first"
+        );
+        let _ = std::fs::remove_file(&db_path);
+    }
+}
+
+#[cfg(test)]
+mod history_range_tests {
+    use super::{history_entries_between, parse_date_range};
+    use chrono::{Local, TimeZone, Utc};
+    use rusqlite::Connection;
+
+    #[test]
+    fn range_accepts_single_day_and_rejects_inverted() {
+        let (f, t) = parse_date_range("2026-03-02", "2026-03-02").unwrap();
+        assert_eq!(f, t);
+        assert!(parse_date_range("2026-03-09", "2026-03-02").is_err());
+        assert!(parse_date_range("03/02/2026", "2026-03-09").is_err());
+    }
+
+    #[test]
+    fn late_evening_local_report_lands_in_local_day() {
+        // created_at is stored as UTC (CURRENT_TIMESTAMP); 23:00 local may already be "tomorrow" in UTC.
+        let day = Local::now().date_naive();
+        let late_local = Local
+            .from_local_datetime(&day.and_hms_opt(23, 0, 0).unwrap())
+            .earliest()
+            .unwrap();
+        let stored = late_local.with_timezone(&Utc).format("%Y-%m-%d %H:%M:%S").to_string();
+
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE reports (id INTEGER PRIMARY KEY, description TEXT, activity_type TEXT,
+             jira_ticket_id TEXT, duration_seconds INTEGER, created_at TEXT);",
+        )
+        .unwrap();
+        conn.execute(
+            "INSERT INTO reports (description, activity_type, duration_seconds, created_at)
+             VALUES ('late', 'Coding', 60, ?1)",
+            [&stored],
+        )
+        .unwrap();
+
+        let today = day.format("%Y-%m-%d").to_string();
+        let entries = history_entries_between(&conn, &today, &today).unwrap();
+        assert_eq!(entries.len(), 1);
+    }
 }
 
 #[cfg(test)]
@@ -1,11 +1,17 @@
 //! Vision output parsing — pure logic, heavily unit-tested. Used by `agent::capture_context_snapshot` path.
 
-/// Full pipeline: structured description + resolved category label.
+/// Full pipeline: structured description + resolved category label (English keyword set).
+#[cfg(test)]
 pub(crate) fn parse_analysis(raw: &str) -> (String, String) {
+    parse_analysis_for_language(raw, REPORT_LANGUAGE_DEFAULT)
+}
+
+/// Same as `parse_analysis`, with the keyword fallback extended by `language`'s keyword set.
+pub(crate) fn parse_analysis_for_language(raw: &str, language: &str) -> (String, String) {
     let lower = raw.to_lowercase();
 
     let category = extract_category_from_field(&lower)
-        .unwrap_or_else(|| infer_category_from_content(&lower));
+        .unwrap_or_else(|| infer_category_from_content_in(&lower, language));
 
     let description = build_structured_description(raw);
 
@@ -20,139 +26,779 @@ fn normalize_category_value(s: &str) -> String {
         .collect()
 }
 
+/// Normalized key → canonical label for every category the agent emits.
+const CATEGORY_MAP: &[(&str, &str)] = &[
+    ("coding", "Coding"),
+    ("debugging", "Debugging"),
+    ("codereview", "CodeReview"),
+    ("testing", "Testing"),
+    ("documentation", "Documentation"),
+    ("design", "Design"),
+    ("planning", "Planning"),
+    ("meeting", "Meeting"),
+    ("communication", "Communication"),
+    ("research", "Research"),
+    ("learning", "Learning"),
+    ("devops", "DevOps"),
+    ("database", "Database"),
+    ("sales", "Sales"),
+    ("admin", "Admin"),
+    ("browsing", "Browsing"),
+    ("idle", "Idle"),
+    ("general", "General"),
+];
+
 /// Extract category from an explicit "CATEGORY: Xyz" line in the model output.
 /// The value may be multi-word (e.g. "Code Review"); we normalize instead of taking only the first word.
 fn extract_category_from_field(lower: &str) -> Option<String> {
-    const MAP: &[(&str, &str)] = &[
-        ("coding", "Coding"),
-        ("debugging", "Debugging"),
-        ("codereview", "CodeReview"),
-        ("testing", "Testing"),
-        ("documentation", "Documentation"),
-        ("design", "Design"),
-        ("planning", "Planning"),
-        ("meeting", "Meeting"),
-        ("communication", "Communication"),
-        ("research", "Research"),
-        ("learning", "Learning"),
-        ("devops", "DevOps"),
-        ("database", "Database"),
-        ("sales", "Sales"),
-        ("admin", "Admin"),
-        ("browsing", "Browsing"),
-        ("idle", "Idle"),
-        ("general", "General"),
-    ];
-
     let idx = lower.rfind("category:")?;
     let after = lower[idx + "category:".len()..].trim_start();
     let first_line = after.lines().next()?.trim();
     if first_line.is_empty() {
         return None;
     }
-    let norm = normalize_category_value(first_line);
-    for (key, label) in MAP {
-        if norm == *key {
-            return Some((*label).to_string());
-        }
+    canonical_category(first_line).map(str::to_string)
+}
+
+/// Canonical label for a user/model supplied category ("code review" → "CodeReview"), if known.
+pub(crate) fn canonical_category(value: &str) -> Option<&'static str> {
+    let norm = normalize_category_value(value);
+    CATEGORY_MAP
+        .iter()
+        .find(|(key, _)| norm == *key)
+        .map(|(_, label)| *label)
+}
+
+/// "Alex (MacBook-Pro)"; just the name when the device is unknown, just the device when the
+/// name is blank.
+pub(crate) fn developer_label(name: &str, device: Option<&str>) -> String {
+    let name = name.trim();
+    match device.map(str::trim).filter(|d| !d.is_empty()) {
+        Some(d) if name.is_empty() => d.to_string(),
+        Some(d) => format!("{} ({})", name, d),
+        None => name.to_string(),
     }
-    None
 }
 
-/// Fallback: infer category from keywords in the full content.
-fn infer_category_from_content(lower: &str) -> String {
-    if lower.contains("debugger") || lower.contains("breakpoint") {
-        "Debugging"
-    } else if lower.contains("pull request")
-        || lower.contains("reviewing code")
-        || lower.contains("code review")
-    {
-        "CodeReview"
-    } else if lower.contains("running tests")
-        || lower.contains("test results")
-        || lower.contains("test suite")
-    {
-        "Testing"
-    } else if lower.contains("microsoft excel")
-        || lower.contains("google sheets")
-        || lower.contains("libreoffice calc")
-        || lower.contains("spreadsheet")
-        || lower.contains(".xlsx")
-        || lower.contains(".xls")
-    {
+/// Longest description stored in `reports` (vision output is ~5 short lines; anything bigger is a bug).
+pub(crate) const REPORT_DESCRIPTION_MAX_CHARS: usize = 2000;
+/// Serialized size cap for `reports.metadata`.
+pub(crate) const REPORT_METADATA_MAX_BYTES: usize = 4096;
+
+/// Integration data attached to a report (ticket, PR number, app/file…). Must be a JSON object
+/// under `REPORT_METADATA_MAX_BYTES`; `null` / `{}` store nothing. Returns the text to store.
+pub(crate) fn normalize_report_metadata(metadata: Option<&serde_json::Value>) -> Result<Option<String>, String> {
+    let Some(value) = metadata.filter(|v| !v.is_null()) else {
+        return Ok(None);
+    };
+    let obj = value
+        .as_object()
+        .ok_or("Report metadata must be a JSON object.")?;
+    if obj.is_empty() {
+        return Ok(None);
+    }
+    let text = value.to_string();
+    if text.len() > REPORT_METADATA_MAX_BYTES {
+        return Err(format!(
+            "Report metadata is {} bytes (max {}).",
+            text.len(),
+            REPORT_METADATA_MAX_BYTES
+        ));
+    }
+    Ok(Some(text))
+}
+
+/// Validates a report before it hits SQLite: empty descriptions are rejected, long ones truncated
+/// with an ellipsis, unknown categories coerced to `General` so breakdown charts stay well-formed.
+pub(crate) fn sanitize_report(description: &str, activity_type: &str) -> Result<(String, String), String> {
+    let description = description.trim();
+    if description.is_empty() {
+        return Err("Activity description is empty.".to_string());
+    }
+    let description = if description.chars().count() > REPORT_DESCRIPTION_MAX_CHARS {
+        let mut cut: String = description
+            .chars()
+            .take(REPORT_DESCRIPTION_MAX_CHARS - 1)
+            .collect();
+        cut.push('…');
+        cut
+    } else {
+        description.to_string()
+    };
+    let category = canonical_category(activity_type).unwrap_or("General");
+    Ok((description, category.to_string()))
+}
+
+/// Keyword table for `infer_category_from_content`, in tie-break priority order (earlier wins).
+/// Each distinct keyword hit adds `weight` to the category's score.
+const CATEGORY_KEYWORDS: &[(&str, f32, &[&str])] = &[
+    ("Debugging", 1.0, &["debugger", "breakpoint", "stack trace", "traceback"]),
+    // In a call, whatever is being screen-shared (Jira, code…) is still the meeting.
+    ("Meeting", 1.0, &["zoom", "google meet", "teams meeting", "video call"]),
+    ("CodeReview", 1.0, &["pull request", "reviewing code", "code review", "merge request"]),
+    ("Testing", 1.0, &["running tests", "test results", "test suite", "failing test"]),
+    (
+        "Coding",
+        1.0,
+        &[
+            "writing code", "visual studio code", "vs code", "vscode", "intellij", "pycharm",
+            "webstorm", "rider", "xcode", "android studio", "neovim",
+        ],
+    ),
+    ("Documentation", 1.0, &["writing docs", "readme", "documentation", "docs"]),
+    ("Design", 1.0, &["figma", "sketch", "design tool", "mockup"]),
+    ("Planning", 1.0, &["jira", "trello", "backlog", "sprint board"]),
+    ("DevOps", 1.0, &["docker", "kubernetes", "pipeline", "ci/cd"]),
+    ("Database", 1.0, &["sql", "database", "supabase"]),
+    (
+        "Admin",
+        1.0,
         // Spreadsheets are often miscategorized as "Coding" when the prompt mentions a generic "editor".
-        "Admin"
-    } else if lower.contains("writing code")
-        || lower.contains("visual studio code")
-        || lower.contains("vs code")
-        || lower.contains("vscode")
-        || lower.contains("intellij")
-        || lower.contains("pycharm")
-        || lower.contains("webstorm")
-        || lower.contains("rider")
-        || lower.contains("xcode")
-        || lower.contains("android studio")
-        || lower.contains("neovim")
-    {
-        "Coding"
-    } else if lower.contains("writing docs") || lower.contains("readme") {
-        "Documentation"
-    } else if lower.contains("figma") || lower.contains("sketch") || lower.contains("design tool") {
-        "Design"
-    } else if lower.contains("jira") || lower.contains("trello") || lower.contains("backlog") {
-        "Planning"
-    } else if lower.contains("zoom")
-        || lower.contains("google meet")
-        || lower.contains("teams meeting")
-    {
-        "Meeting"
-    } else if lower.contains("slack") || lower.contains("discord") || lower.contains("email") {
-        "Communication"
-    } else if lower.contains("stackoverflow")
-        || lower.contains("searching")
-        || lower.contains("google search")
-    {
-        "Research"
-    } else if lower.contains("tutorial") || lower.contains("course") || lower.contains("learning") {
-        "Learning"
-    } else if lower.contains("docker")
-        || lower.contains("kubernetes")
-        || lower.contains("pipeline")
-        || lower.contains("ci/cd")
-    {
-        "DevOps"
-    } else if lower.contains("sql") || lower.contains("database") || lower.contains("supabase") {
-        "Database"
-    } else if lower.contains("crm") || lower.contains("hubspot") {
-        "Sales"
-    } else if lower.contains("settings") || lower.contains("configuration") {
-        "Admin"
-    } else if lower.contains("browser")
-        || lower.contains("chrome")
-        || lower.contains("firefox")
-        || lower.contains("linkedin")
-        || lower.contains("github.com")
+        &[
+            "microsoft excel", "google sheets", "libreoffice calc", "spreadsheet", ".xlsx", ".xls",
+            "settings", "configuration",
+        ],
+    ),
+    ("Communication", 1.0, &["slack", "discord", "email"]),
+    ("Research", 1.0, &["stackoverflow", "searching", "google search"]),
+    ("Learning", 1.0, &["tutorial", "course", "learning"]),
+    ("Sales", 1.0, &["crm", "hubspot"]),
+    // The browser is usually just the container for another activity, so its hits count half.
+    ("Browsing", 0.5, &["browser", "chrome", "firefox", "linkedin", "github.com"]),
+    ("Idle", 1.0, &["idle", "no activity", "lock screen"]),
+];
+
+/// `(category label, keywords)` added on top of `CATEGORY_KEYWORDS` for one language.
+type LocalizedKeywords = &'static [(&'static str, &'static [&'static str])];
+
+/// `reportLanguage` codes with a vision-prompt instruction, an English name for the prompt, and
+/// an extra keyword set (empty for English). App names ("vs code", "jira") stay in the English
+/// table since they are not translated.
+const REPORT_LANGUAGES: &[(&str, &str, LocalizedKeywords)] = &[
+    ("en", "English", &[]),
+    (
+        "es",
+        "Spanish",
+        &[
+            ("Debugging", &["depurando", "depurador", "punto de interrupción", "depuración"]),
+            ("Meeting", &["reunión", "videollamada", "llamada"]),
+            ("CodeReview", &["revisión de código", "revisando código", "revisando el pull request"]),
+            ("Testing", &["ejecutando tests", "ejecutando pruebas", "pruebas unitarias", "resultados de las pruebas"]),
+            ("Coding", &["escribiendo código", "programando", "editando código"]),
+            ("Documentation", &["documentación", "escribiendo documentación"]),
+            ("Design", &["diseño", "maqueta", "prototipo"]),
+            ("Planning", &["planificación", "tablero del sprint"]),
+            ("Database", &["base de datos", "consulta sql"]),
+            ("Admin", &["hoja de cálculo", "configuración", "ajustes"]),
+            ("Communication", &["correo", "mensaje", "chat"]),
+            ("Research", &["buscando", "investigando", "búsqueda en google"]),
+            ("Learning", &["curso", "aprendiendo"]),
+            ("Sales", &["ventas", "clientes potenciales"]),
+            ("Browsing", &["navegador", "navegando"]),
+            ("Idle", &["inactivo", "sin actividad", "pantalla de bloqueo"]),
+        ],
+    ),
+    (
+        "de",
+        "German",
+        &[
+            ("Debugging", &["debuggen", "haltepunkt", "fehlersuche"]),
+            ("Meeting", &["besprechung", "videoanruf", "videokonferenz"]),
+            ("CodeReview", &["code-review", "überprüfung des codes"]),
+            ("Testing", &["tests ausführen", "testergebnisse", "fehlschlagender test"]),
+            ("Coding", &["programmieren", "code schreiben", "quellcode"]),
+            ("Documentation", &["dokumentation"]),
+            ("Design", &["entwurf", "gestaltung"]),
+            ("Planning", &["planung", "sprint-board"]),
+            ("Database", &["datenbank"]),
+            ("Admin", &["tabellenkalkulation", "einstellungen", "konfiguration"]),
+            ("Communication", &["e-mail", "nachricht"]),
+            ("Research", &["recherche", "suche nach"]),
+            ("Learning", &["lernen", "kurs", "anleitung"]),
+            ("Sales", &["vertrieb", "verkauf"]),
+            ("Browsing", &["surfen"]),
+            ("Idle", &["inaktiv", "keine aktivität", "sperrbildschirm"]),
+        ],
+    ),
+];
+
+pub(crate) const REPORT_LANGUAGE_DEFAULT: &str = "en";
+
+/// Lowercased known code (`"ES"` → `"es"`); unknown codes are rejected so a typo does not
+/// silently fall back to English.
+pub(crate) fn normalize_report_language(code: &str) -> Result<&'static str, String> {
+    let code = code.trim().to_lowercase();
+    REPORT_LANGUAGES
+        .iter()
+        .find(|(c, _, _)| *c == code)
+        .map(|(c, _, _)| *c)
+        .ok_or_else(|| {
+            let known: Vec<&str> = REPORT_LANGUAGES.iter().map(|(c, _, _)| *c).collect();
+            format!("Unsupported reportLanguage '{}' (expected one of: {})", code, known.join(", "))
+        })
+}
+
+/// Appended to the vision prompt. Field labels and the CATEGORY value stay English so
+/// `parse_analysis` keeps working; only the free-text values are localized.
+pub(crate) fn report_language_instruction(code: &str) -> Option<String> {
+    let (_, name, _) = REPORT_LANGUAGES.iter().find(|(c, _, _)| *c == code)?;
+    if code == REPORT_LANGUAGE_DEFAULT {
+        return None;
+    }
+    Some(format!(
+        "Respond in {}: write every field value in {}, but keep the field labels (APP:, WINDOW TITLE:, …) and the CATEGORY value exactly as listed in English.",
+        name, name
+    ))
+}
+
+/// Default `captureJitterPercent`: each wait is the interval ± up to 10%.
+pub(crate) const CAPTURE_JITTER_PERCENT_DEFAULT: u32 = 10;
+pub(crate) const CAPTURE_JITTER_PERCENT_MAX: u32 = 50;
+
+/// `base_ms` shifted by a uniform offset in `±percent%`, derived from `seed` (splitmix64) so a
+/// given seed always yields the same wait. Desynchronizes captures / syncs across a team.
+pub(crate) fn jittered_interval_ms(base_ms: u64, percent: u32, seed: u64) -> u64 {
+    let spread = base_ms * percent.min(CAPTURE_JITTER_PERCENT_MAX) as u64 / 100;
+    if spread == 0 {
+        return base_ms;
+    }
+    let mut z = seed.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^= z >> 31;
+    base_ms - spread + z % (2 * spread + 1)
+}
+
+pub(crate) const ANALYSIS_MODE_VISION: &str = "vision";
+pub(crate) const ANALYSIS_MODE_WINDOW_ONLY: &str = "window_only";
+pub(crate) const ANALYSIS_MODE_HYBRID: &str = "hybrid";
+
+pub(crate) fn normalize_analysis_mode(mode: &str) -> Result<String, String> {
+    match mode.trim().to_lowercase().replace('-', "_").as_str() {
+        "vision" => Ok(ANALYSIS_MODE_VISION.to_string()),
+        "window_only" | "window" => Ok(ANALYSIS_MODE_WINDOW_ONLY.to_string()),
+        "hybrid" => Ok(ANALYSIS_MODE_HYBRID.to_string()),
+        other => Err(format!(
+            "Invalid analysisMode '{}' (expected \"vision\", \"window_only\" or \"hybrid\")",
+            other
+        )),
+    }
+}
+
+/// Report without a screenshot: the same `APP:` / `WINDOW TITLE:` lines the vision template
+/// starts with, and the category from the keyword fallback run on app + title.
+pub(crate) fn window_only_analysis(app: Option<&str>, title: Option<&str>, language: &str) -> (String, String) {
+    let app = app.map(str::trim).filter(|a| !a.is_empty());
+    let title = title.map(str::trim).filter(|t| !t.is_empty());
+    let mut lines = Vec::new();
+    if let Some(a) = app {
+        lines.push(format!("APP: {}", a));
+    }
+    if let Some(t) = title {
+        lines.push(format!("WINDOW TITLE: {}", t));
+    }
+    if lines.is_empty() {
+        return ("No active window detected".to_string(), "General".to_string());
+    }
+    let haystack = format!("{} {}", app.unwrap_or_default(), title.unwrap_or_default()).to_lowercase();
+    (lines.join("\n"), infer_category_from_content_in(&haystack, language))
+}
+
+/// `get_activity_log` page size when the caller passes no `limit`.
+pub(crate) const ACTIVITY_LOG_LIMIT_DEFAULT: u32 = 20;
+/// Default `activityLogMaxLimit`: the most rows one `get_activity_log` call may load.
+pub(crate) const ACTIVITY_LOG_MAX_LIMIT_DEFAULT: u32 = 500;
+pub(crate) const ACTIVITY_LOG_MAX_LIMIT_RANGE: std::ops::RangeInclusive<u32> = 1..=5000;
+
+/// `(effective limit, capped)` for a requested page size under `max`.
+pub(crate) fn effective_log_limit(requested: Option<u32>, max: u32) -> (u32, bool) {
+    let requested = requested.unwrap_or(ACTIVITY_LOG_LIMIT_DEFAULT).max(1);
+    (requested.min(max), requested > max)
+}
+
+pub(crate) const SPARKLINE_MAX_HOURS: u32 = 168;
+
+/// Zero-filled hourly counts for the `hours` hours ending at `now`'s hour (oldest first).
+/// `counts` is keyed by `strftime('%Y-%m-%d %H', created_at)` (UTC).
+pub(crate) fn hourly_sparkline(
+    counts: &std::collections::HashMap<String, i64>,
+    now: chrono::DateTime<chrono::Utc>,
+    hours: u32,
+) -> Vec<i64> {
+    let hours = hours.clamp(1, SPARKLINE_MAX_HOURS) as i64;
+    (0..hours)
+        .rev()
+        .map(|ago| {
+            let key = (now - chrono::Duration::hours(ago)).format("%Y-%m-%d %H").to_string();
+            counts.get(&key).copied().unwrap_or(0)
+        })
+        .collect()
+}
+
+/// Capture intervals are suggested in steps of this many ms (the settings presets are multiples).
+const CAPTURE_INTERVAL_STEP_MS: u64 = 15_000;
+
+fn format_secs(ms: u64) -> String {
+    if ms < 10_000 {
+        format!("{:.1}s", ms as f64 / 1000.0)
+    } else {
+        format!("{}s", (ms + 500) / 1000)
+    }
+}
+
+/// `(sustainable, suggested interval, message)` for `estimate_capture_cost`. One analysis should
+/// take at most half the interval; over the full interval captures pile up behind each other.
+/// A failed vision call returns fast, so its timing says nothing: no verdict, no suggestion.
+pub(crate) fn capture_cost_recommendation(
+    total_ms: u64,
+    interval_ms: u64,
+    vision_error: Option<&str>,
+) -> (bool, Option<u64>, String) {
+    if let Some(err) = vision_error {
+        return (
+            false,
+            None,
+            format!("Could not measure: the vision call failed ({}). Fix it and run the estimate again.", err),
+        );
+    }
+    let setup = format!(
+        "With a {} interval and {} per analysis",
+        format_secs(interval_ms),
+        format_secs(total_ms)
+    );
+    if total_ms * 2 <= interval_ms {
+        return (true, None, format!("{}, this is fine.", setup));
+    }
+    let suggested = (total_ms * 2).div_ceil(CAPTURE_INTERVAL_STEP_MS).max(1) * CAPTURE_INTERVAL_STEP_MS;
+    if total_ms <= interval_ms {
+        (
+            true,
+            Some(suggested),
+            format!(
+                "{}, this works but leaves little headroom; consider {} or a smaller model.",
+                setup,
+                format_secs(suggested)
+            ),
+        )
+    } else {
+        (
+            false,
+            Some(suggested),
+            format!(
+                "{}, captures cannot keep up: increase the interval to at least {} or use a smaller model.",
+                setup,
+                format_secs(suggested)
+            ),
+        )
+    }
+}
+
+/// Shortest loop stall `record_sleep_gap` accepts as a sleep / wake (or hibernate) gap.
+pub(crate) const SLEEP_GAP_MIN_SECS: i64 = 120;
+
+/// Timeline marker text for a sleep gap, in local wall-clock time: `Computer asleep 10:02–11:40 (1h 38m)`.
+pub(crate) fn sleep_gap_description(from: chrono::NaiveDateTime, to: chrono::NaiveDateTime) -> String {
+    let mins = (to - from).num_minutes().max(0);
+    let span = if mins >= 60 {
+        format!("{}h {}m", mins / 60, mins % 60)
+    } else {
+        format!("{}m", mins)
+    };
+    format!("Computer asleep {}–{} ({})", from.format("%H:%M"), to.format("%H:%M"), span)
+}
+
+/// `get_activity_timeline` refuses ranges that would need more buckets than this
+/// (hourly over a year is ~8800).
+pub(crate) const TIMELINE_MAX_BUCKETS: usize = 2000;
+
+/// Timeline bucket size: `"15min"`, `"hour"` or `"day"` (default).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum TimelineBucket {
+    Min15,
+    Hour,
+    Day,
+}
+
+impl TimelineBucket {
+    pub(crate) fn parse(raw: Option<&str>) -> Result<Self, String> {
+        match raw.map(|b| b.trim().to_ascii_lowercase()).as_deref() {
+            None | Some("") | Some("day") => Ok(Self::Day),
+            Some("hour") => Ok(Self::Hour),
+            Some("15min") => Ok(Self::Min15),
+            Some(other) => Err(format!(
+                "Invalid bucket '{}' (expected \"15min\", \"hour\" or \"day\")",
+                other
+            )),
+        }
+    }
+
+    pub(crate) fn as_str(self) -> &'static str {
+        match self {
+            Self::Min15 => "15min",
+            Self::Hour => "hour",
+            Self::Day => "day",
+        }
+    }
+
+    fn minutes(self) -> i64 {
+        match self {
+            Self::Min15 => 15,
+            Self::Hour => 60,
+            Self::Day => 24 * 60,
+        }
+    }
+
+    fn key_format(self) -> &'static str {
+        match self {
+            Self::Day => "%Y-%m-%d",
+            Self::Hour | Self::Min15 => "%Y-%m-%d %H:%M",
+        }
+    }
+
+    /// SQLite expression for the bucket key of `created_at` (local time); matches `timeline_keys`.
+    pub(crate) fn sql_key(self) -> &'static str {
+        match self {
+            Self::Day => "strftime('%Y-%m-%d', created_at, 'localtime')",
+            Self::Hour => "strftime('%Y-%m-%d %H:00', created_at, 'localtime')",
+            Self::Min15 => {
+                "strftime('%Y-%m-%d %H:', created_at, 'localtime') || \
+                 printf('%02d', CAST(strftime('%M', created_at, 'localtime') AS INTEGER) / 15 * 15)"
+            }
+        }
+    }
+}
+
+/// Every bucket key from the start of `from` to the end of `to` (inclusive local dates), oldest
+/// first. Errors when the range needs more than `TIMELINE_MAX_BUCKETS` buckets.
+pub(crate) fn timeline_keys(
+    from: chrono::NaiveDate,
+    to: chrono::NaiveDate,
+    bucket: TimelineBucket,
+) -> Result<Vec<String>, String> {
+    let days = (to - from).num_days() + 1;
+    let count = days * 24 * 60 / bucket.minutes();
+    if count as usize > TIMELINE_MAX_BUCKETS {
+        return Err(format!(
+            "{} days in '{}' buckets is {} buckets (max {}); use a larger bucket or a shorter range",
+            days,
+            bucket.as_str(),
+            count,
+            TIMELINE_MAX_BUCKETS
+        ));
+    }
+    let start = from.and_hms_opt(0, 0, 0).unwrap_or_default();
+    Ok((0..count)
+        .map(|i| {
+            (start + chrono::Duration::minutes(i * bucket.minutes()))
+                .format(bucket.key_format())
+                .to_string()
+        })
+        .collect())
+}
+
+/// One run of consecutive Idle reports in `get_activity_timeline`, so charts can draw "away"
+/// as its own block instead of a gap that looks like the app was closed. Local wall-clock times.
+#[derive(serde::Serialize, Clone, Debug, PartialEq)]
+pub struct IdleBlock {
+    pub start: String,
+    pub end: String,
+    pub reports: usize,
+}
+
+/// A `reports` row as seen by `merge_idle_blocks`: local capture time, type and duration, plus
+/// the exact `(from, to)` of a `record_sleep_gap` marker.
+pub(crate) struct TimelineRow {
+    pub at: chrono::NaiveDateTime,
+    pub activity_type: String,
+    pub duration_seconds: i64,
+    pub sleep_gap: Option<(chrono::NaiveDateTime, chrono::NaiveDateTime)>,
+}
+
+/// Merges runs of Idle rows (in capture order) into blocks; any other activity ends a run.
+/// A row covers `at..at + duration`, a sleep-gap marker its recorded span.
+pub(crate) fn merge_idle_blocks(rows: &[TimelineRow]) -> Vec<IdleBlock> {
+    let fmt = |t: chrono::NaiveDateTime| t.format("%Y-%m-%d %H:%M:%S").to_string();
+    let mut blocks = Vec::new();
+    let mut open: Option<(chrono::NaiveDateTime, chrono::NaiveDateTime, usize)> = None;
+    for row in rows {
+        if row.activity_type != "Idle" {
+            if let Some((start, end, n)) = open.take() {
+                blocks.push(IdleBlock { start: fmt(start), end: fmt(end), reports: n });
+            }
+            continue;
+        }
+        let (from, to) = row
+            .sleep_gap
+            .unwrap_or((row.at, row.at + chrono::Duration::seconds(row.duration_seconds.max(0))));
+        open = Some(match open {
+            Some((start, end, n)) => (start.min(from), end.max(to), n + 1),
+            None => (from, to, 1),
+        });
+    }
+    if let Some((start, end, n)) = open {
+        blocks.push(IdleBlock { start: fmt(start), end: fmt(end), reports: n });
+    }
+    blocks
+}
+
+/// `get_activity_heatmap` covers at most one year (plus a leap day).
+pub(crate) const HEATMAP_MAX_DAYS: u32 = 366;
+
+/// Local date → report count for the `days` days ending on `today`, zero-filled so every day
+/// is present. `counts` is keyed by `date(created_at, 'localtime')`.
+pub(crate) fn daily_heatmap(
+    counts: &std::collections::HashMap<String, i64>,
+    today: chrono::NaiveDate,
+    days: u32,
+) -> std::collections::BTreeMap<String, i64> {
+    let days = days.clamp(1, HEATMAP_MAX_DAYS) as i64;
+    (0..days)
+        .map(|ago| {
+            let key = (today - chrono::Duration::days(ago)).format("%Y-%m-%d").to_string();
+            let n = counts.get(&key).copied().unwrap_or(0);
+            (key, n)
+        })
+        .collect()
+}
+
+/// Default display name and color (`#rrggbb`) per canonical category, so charts are data-driven.
+const CATEGORY_DISPLAY_DEFAULTS: &[(&str, &str, &str)] = &[
+    ("Coding", "Coding", "#7c3aed"),
+    ("Debugging", "Debugging", "#dc2626"),
+    ("CodeReview", "Code review", "#c026d3"),
+    ("Testing", "Testing", "#0891b2"),
+    ("Documentation", "Documentation", "#2563eb"),
+    ("Design", "Design", "#db2777"),
+    ("Planning", "Planning", "#0d9488"),
+    ("Meeting", "Meeting", "#ea580c"),
+    ("Communication", "Communication", "#f59e0b"),
+    ("Research", "Research", "#4f46e5"),
+    ("Learning", "Learning", "#16a34a"),
+    ("DevOps", "DevOps", "#475569"),
+    ("Database", "Database", "#0284c7"),
+    ("Sales", "Sales", "#65a30d"),
+    ("Admin", "Admin", "#78716c"),
+    ("Browsing", "Browsing", "#a3a3a3"),
+    ("Idle", "Idle", "#d4d4d4"),
+    ("General", "General", "#6b7280"),
+];
+
+/// `(label, color)` for a stored category. Unknown categories keep their name and get a stable
+/// color derived from it, so a new type never renders blank.
+pub(crate) fn category_display_default(category: &str) -> (String, String) {
+    let canonical = canonical_category(category);
+    match CATEGORY_DISPLAY_DEFAULTS
+        .iter()
+        .find(|(c, _, _)| Some(*c) == canonical)
     {
-        "Browsing"
-    } else if lower.contains("idle") || lower.contains("no activity") || lower.contains("lock screen") {
-        "Idle"
+        Some((_, label, color)) => (label.to_string(), color.to_string()),
+        None => (category.to_string(), fallback_category_color(category)),
+    }
+}
+
+/// Every canonical category, in display order.
+pub(crate) fn default_category_names() -> impl Iterator<Item = &'static str> {
+    CATEGORY_DISPLAY_DEFAULTS.iter().map(|(c, _, _)| *c)
+}
+
+/// Hue from an FNV-1a hash of the name (fixed saturation / lightness) as `#rrggbb`.
+pub(crate) fn fallback_category_color(name: &str) -> String {
+    let hash = name
+        .bytes()
+        .fold(0x811c9dc5u32, |h, b| (h ^ b as u32).wrapping_mul(0x01000193));
+    let hue = (hash % 360) as f32;
+    let (s, l) = (0.55f32, 0.5f32);
+    let c = (1.0 - (2.0 * l - 1.0).abs()) * s;
+    let x = c * (1.0 - ((hue / 60.0) % 2.0 - 1.0).abs());
+    let (r, g, b) = match hue as u32 / 60 {
+        0 => (c, x, 0.0),
+        1 => (x, c, 0.0),
+        2 => (0.0, c, x),
+        3 => (0.0, x, c),
+        4 => (x, 0.0, c),
+        _ => (c, 0.0, x),
+    };
+    let m = l - c / 2.0;
+    let to_u8 = |v: f32| ((v + m) * 255.0).round() as u8;
+    format!("#{:02x}{:02x}{:02x}", to_u8(r), to_u8(g), to_u8(b))
+}
+
+/// Accepts `#rgb` / `#rrggbb` (hash optional) and returns lowercase `#rrggbb`.
+pub(crate) fn normalize_hex_color(raw: &str) -> Result<String, String> {
+    let hex = raw.trim().trim_start_matches('#');
+    if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(format!("Invalid color '{}' (expected #rrggbb)", raw.trim()));
+    }
+    match hex.len() {
+        6 => Ok(format!("#{}", hex.to_lowercase())),
+        3 => Ok(format!(
+            "#{}",
+            hex.chars().flat_map(|c| [c, c]).collect::<String>().to_lowercase()
+        )),
+        _ => Err(format!("Invalid color '{}' (expected #rrggbb)", raw.trim())),
+    }
+}
+
+/// `(offloaded, total)` layers from llama.cpp's `load_tensors: offloaded 24/33 layers to GPU`.
+/// Last occurrence wins: one server.log spans every automatic `--n-gpu-layers` retry.
+pub(crate) fn parse_gpu_offload(log: &str) -> Option<(u32, u32)> {
+    log.lines().rev().find_map(|line| {
+        let rest = line.split("offloaded ").nth(1)?;
+        let (ratio, tail) = rest.split_once(' ')?;
+        if !tail.starts_with("layers to GPU") {
+            return None;
+        }
+        let (done, total) = ratio.split_once('/')?;
+        Some((done.parse().ok()?, total.parse().ok()?))
+    })
+}
+
+/// `"gpu"`, `"partial"` or `"cpu"` plus a hint for the UI when the model did not fit in VRAM.
+pub(crate) fn gpu_placement(offloaded: u32, total: u32) -> (&'static str, Option<String>) {
+    if offloaded == 0 {
+        (
+            "cpu",
+            Some("Model running on CPU — expect slow captures. Try a smaller vision model or close GPU-heavy apps.".to_string()),
+        )
+    } else if offloaded < total {
+        (
+            "partial",
+            Some(format!(
+                "Only {}/{} model layers fit in GPU memory — captures will be slower. A smaller vision model may help.",
+                offloaded, total
+            )),
+        )
     } else {
-        "General"
+        ("gpu", None)
+    }
+}
+
+/// Fallback: infer category from keywords in the full content.
+/// Scored, not first-match: "reading documentation in a browser" is Documentation, not Browsing.
+#[cfg(test)]
+fn infer_category_from_content(lower: &str) -> String {
+    infer_category_from_content_in(lower, REPORT_LANGUAGE_DEFAULT)
+}
+
+/// English keywords plus `language`'s set (unknown language → English only).
+fn infer_category_from_content_in(lower: &str, language: &str) -> String {
+    let mut best: Option<(&str, f32)> = None;
+    for s in category_scores_in(lower, language) {
+        if best.map_or(true, |(_, b)| s.score > b) {
+            best = Some((s.category, s.score));
+        }
+    }
+    best.map(|(label, _)| label).unwrap_or("General").to_string()
+}
+
+/// One category's keyword score for `classify_description`.
+#[derive(serde::Serialize, Clone, Debug, PartialEq)]
+pub struct CategoryScore {
+    pub category: &'static str,
+    pub score: f32,
+    pub matched: Vec<&'static str>,
+}
+
+/// Categories with at least one keyword hit, in `CATEGORY_KEYWORDS` (tie-break) order.
+fn category_scores_in(lower: &str, language: &str) -> Vec<CategoryScore> {
+    let localized: LocalizedKeywords = REPORT_LANGUAGES
+        .iter()
+        .find(|(c, _, _)| *c == language)
+        .map(|(_, _, kw)| *kw)
+        .unwrap_or(&[]);
+    CATEGORY_KEYWORDS
+        .iter()
+        .filter_map(|(label, weight, keywords)| {
+            let extra = localized
+                .iter()
+                .filter(|(l, _)| l == label)
+                .flat_map(|(_, kw)| kw.iter());
+            let matched: Vec<&'static str> = keywords
+                .iter()
+                .chain(extra)
+                .copied()
+                .filter(|k| lower.contains(k))
+                .collect();
+            (!matched.is_empty()).then(|| CategoryScore {
+                category: label,
+                score: matched.len() as f32 * weight,
+                matched,
+            })
+        })
+        .collect()
+}
+
+/// Why a description got its category: an explicit `CATEGORY:` line (`"field"`), the keyword
+/// scores (`"keywords"`), or nothing matched (`"default"` → General).
+#[derive(serde::Serialize, Clone, Debug, PartialEq)]
+pub struct Classification {
+    pub category: String,
+    pub source: &'static str,
+    /// Highest first; ties keep keyword-table order (the earlier one wins).
+    pub scores: Vec<CategoryScore>,
+}
+
+/// Same decision as `parse_analysis_for_language`, with the keyword scores behind it.
+pub(crate) fn classify_description(description: &str, language: &str) -> Classification {
+    let lower = description.to_lowercase();
+    let mut scores = category_scores_in(&lower, language);
+    // stable sort: equal scores stay in tie-break order
+    scores.sort_by(|a, b| b.score.total_cmp(&a.score));
+    let (category, source) = match extract_category_from_field(&lower) {
+        Some(c) => (c, "field"),
+        None => match scores.first() {
+            Some(top) => (top.category.to_string(), "keywords"),
+            None => ("General".to_string(), "default"),
+        },
+    };
+    Classification { category, source, scores }
+}
+
+/// Template lines whose values carry the actual description; the rest (APP, WINDOW TITLE,
+/// CATEGORY, ...) is filled in even when the model saw nothing useful.
+const DESCRIPTION_FIELDS: [&str; 2] = ["VISIBLE CONTENT:", "CURRENT ACTION:"];
+const TEMPLATE_FIELDS: [&str; 8] = [
+    "APP:",
+    "WINDOW TITLE:",
+    "VISIBLE CONTENT:",
+    "FILES OR URLS:",
+    "CURRENT ACTION:",
+    "PROGRESS:",
+    "NEXT STEP:",
+    "CATEGORY:",
+];
+
+/// Characters of real description in a vision answer, compared with `minDescriptionLength`:
+/// the VISIBLE CONTENT and CURRENT ACTION values of the template ("None" counts as empty),
+/// or the whole cleaned answer when the model ignored the template.
+pub(crate) fn description_chars(raw: &str) -> usize {
+    let lines: Vec<String> = raw
+        .lines()
+        .map(|l| l.replace('#', "").replace("**", "").trim().to_string())
+        .filter(|l| !l.is_empty())
+        .collect();
+    let templated = lines
+        .iter()
+        .any(|l| TEMPLATE_FIELDS.iter().any(|f| l.to_uppercase().starts_with(f)));
+    if !templated {
+        return lines.iter().map(|l| l.chars().count()).sum();
     }
-    .to_string()
+    lines
+        .iter()
+        .filter_map(|l| {
+            let field = DESCRIPTION_FIELDS.iter().find(|f| l.to_uppercase().starts_with(*f))?;
+            let value = l[field.len()..].trim();
+            let placeholder = value.is_empty()
+                || value.eq_ignore_ascii_case("none")
+                || value.eq_ignore_ascii_case("n/a")
+                || value.eq_ignore_ascii_case("none visible");
+            (!placeholder).then(|| value.chars().count())
+        })
+        .sum()
 }
 
 fn build_structured_description(raw: &str) -> String {
-    let fields = [
-        "APP:",
-        "WINDOW TITLE:",
-        "VISIBLE CONTENT:",
-        "FILES OR URLS:",
-        "CURRENT ACTION:",
-        "PROGRESS:",
-        "NEXT STEP:",
-        "CATEGORY:",
-    ];
+    let fields = TEMPLATE_FIELDS;
 
     let mut parts: Vec<String> = Vec::new();
 
@@ -197,6 +843,171 @@ fn build_structured_description(raw: &str) -> String {
 mod tests {
     use super::*;
 
+    #[test]
+    fn category_display_defaults_and_fallback() {
+        assert_eq!(category_display_default("code review"), ("Code review".to_string(), "#c026d3".to_string()));
+        let (label, color) = category_display_default("Pairing");
+        assert_eq!(label, "Pairing");
+        assert_eq!(color, fallback_category_color("Pairing"), "stable per name");
+        assert_eq!(color.len(), 7);
+        assert_ne!(fallback_category_color("Pairing"), fallback_category_color("Support"));
+        assert_eq!(default_category_names().count(), CATEGORY_MAP.len());
+    }
+
+    #[test]
+    fn description_chars_ignores_template_labels() {
+        assert_eq!(description_chars("APP: Desktop\nWINDOW TITLE: Program Manager\nCATEGORY: Idle"), 0);
+        assert_eq!(description_chars("APP: Desktop\nVISIBLE CONTENT: None\nCURRENT ACTION: idle\nCATEGORY: Idle"), 4);
+        let full = "**APP:** Code\nVISIBLE CONTENT: sync.rs open in the editor\nCURRENT ACTION: fixing a borrow error";
+        assert_eq!(description_chars(full), "sync.rs open in the editor".len() + "fixing a borrow error".len());
+        assert_eq!(description_chars("The user is reading a pull request."), 35);
+    }
+
+    #[test]
+    fn window_only_analysis_uses_title_keywords() {
+        let (desc, cat) = window_only_analysis(Some("Code"), Some("main.rs - flowsight - Visual Studio Code"), "en");
+        assert_eq!(desc, "APP: Code\nWINDOW TITLE: main.rs - flowsight - Visual Studio Code");
+        assert_eq!(cat, "Coding");
+        assert_eq!(window_only_analysis(None, Some("  "), "en").1, "General");
+        assert_eq!(normalize_analysis_mode("Window-Only").unwrap(), ANALYSIS_MODE_WINDOW_ONLY);
+        assert!(normalize_analysis_mode("gpu").is_err());
+    }
+
+    #[test]
+    fn jitter_stays_in_band_and_is_seeded() {
+        for seed in 0..1000 {
+            let ms = jittered_interval_ms(60_000, 10, seed);
+            assert!((54_000..=66_000).contains(&ms), "{}", ms);
+        }
+        assert_eq!(jittered_interval_ms(60_000, 10, 42), jittered_interval_ms(60_000, 10, 42));
+        assert_ne!(jittered_interval_ms(60_000, 10, 1), jittered_interval_ms(60_000, 10, 2));
+        assert_eq!(jittered_interval_ms(60_000, 0, 7), 60_000);
+        assert!(jittered_interval_ms(60_000, 99, 3) >= 30_000, "percent capped at 50");
+    }
+
+    #[test]
+    fn hex_colors_are_normalized() {
+        assert_eq!(normalize_hex_color(" #ABC ").unwrap(), "#aabbcc");
+        assert_eq!(normalize_hex_color("7C3AED").unwrap(), "#7c3aed");
+        assert!(normalize_hex_color("#12345").is_err());
+        assert!(normalize_hex_color("red").is_err());
+    }
+
+    #[test]
+    fn report_metadata_must_be_a_small_object() {
+        let ok = serde_json::json!({"pr": 42, "build": "green"});
+        assert_eq!(
+            normalize_report_metadata(Some(&ok)).unwrap().as_deref(),
+            Some(r#"{"build":"green","pr":42}"#)
+        );
+        assert_eq!(normalize_report_metadata(None).unwrap(), None);
+        assert_eq!(normalize_report_metadata(Some(&serde_json::json!({}))).unwrap(), None);
+        assert!(normalize_report_metadata(Some(&serde_json::json!([1, 2]))).is_err());
+        let big = serde_json::json!({"blob": "x".repeat(REPORT_METADATA_MAX_BYTES)});
+        assert!(normalize_report_metadata(Some(&big)).is_err());
+    }
+
+    #[test]
+    fn log_limit_defaults_and_caps() {
+        assert_eq!(effective_log_limit(None, 500), (ACTIVITY_LOG_LIMIT_DEFAULT, false));
+        assert_eq!(effective_log_limit(Some(500), 500), (500, false));
+        assert_eq!(effective_log_limit(Some(1_000_000), 500), (500, true));
+        assert_eq!(effective_log_limit(Some(0), 500), (1, false));
+    }
+
+    #[test]
+    fn sparkline_zero_fills_and_orders_oldest_first() {
+        let now = chrono::DateTime::parse_from_rfc3339("2026-03-02T00:30:00Z")
+            .unwrap()
+            .with_timezone(&chrono::Utc);
+        let counts: std::collections::HashMap<String, i64> =
+            [("2026-03-01 22".to_string(), 3), ("2026-03-02 00".to_string(), 1)].into();
+        assert_eq!(hourly_sparkline(&counts, now, 4), vec![0, 3, 0, 1]);
+        assert_eq!(hourly_sparkline(&counts, now, 0), vec![1]);
+        assert_eq!(hourly_sparkline(&counts, now, 10_000).len(), SPARKLINE_MAX_HOURS as usize);
+    }
+
+    #[test]
+    fn capture_cost_recommendation_thresholds() {
+        let (ok, suggested, msg) = capture_cost_recommendation(12_000, 30_000, None);
+        assert!(ok && suggested.is_none());
+        assert_eq!(msg, "With a 30s interval and 12s per analysis, this is fine.");
+        assert_eq!(capture_cost_recommendation(20_000, 30_000, None).1, Some(45_000));
+        let (ok, suggested, _) = capture_cost_recommendation(40_000, 30_000, None);
+        assert!(!ok);
+        assert_eq!(suggested, Some(90_000));
+        let (ok, suggested, msg) = capture_cost_recommendation(40, 30_000, Some("Request failed: connection refused"));
+        assert!(!ok && suggested.is_none());
+        assert!(msg.starts_with("Could not measure"), "{}", msg);
+        assert_eq!(capture_cost_recommendation(800, 1_000, None).2, "With a 1.0s interval and 0.8s per analysis, this works but leaves little headroom; consider 15s or a smaller model.");
+    }
+
+    #[test]
+    fn timeline_buckets_are_validated_and_zero_fillable() {
+        let day = |d| chrono::NaiveDate::from_ymd_opt(2026, 3, d).unwrap();
+        assert_eq!(TimelineBucket::parse(None), Ok(TimelineBucket::Day));
+        assert_eq!(TimelineBucket::parse(Some(" Hour ")), Ok(TimelineBucket::Hour));
+        assert!(TimelineBucket::parse(Some("week")).is_err());
+
+        assert_eq!(timeline_keys(day(1), day(3), TimelineBucket::Day).unwrap(), ["2026-03-01", "2026-03-02", "2026-03-03"]);
+        let quarter = timeline_keys(day(2), day(2), TimelineBucket::Min15).unwrap();
+        assert_eq!(quarter.len(), 96);
+        assert_eq!(&quarter[..2], ["2026-03-02 00:00", "2026-03-02 00:15"]);
+        assert_eq!(timeline_keys(day(1), day(2), TimelineBucket::Hour).unwrap()[25], "2026-03-02 01:00");
+
+        let year_end = chrono::NaiveDate::from_ymd_opt(2026, 12, 31).unwrap();
+        assert!(timeline_keys(chrono::NaiveDate::from_ymd_opt(2026, 1, 1).unwrap(), year_end, TimelineBucket::Hour).is_err());
+    }
+
+    #[test]
+    fn consecutive_idle_reports_merge_into_blocks() {
+        let at = |h, m| chrono::NaiveDate::from_ymd_opt(2026, 3, 2).unwrap().and_hms_opt(h, m, 0).unwrap();
+        let row = |t, ty: &str, secs| TimelineRow { at: t, activity_type: ty.to_string(), duration_seconds: secs, sleep_gap: None };
+        let rows = vec![
+            row(at(9, 0), "Coding", 60),
+            row(at(9, 1), "Idle", 60),
+            row(at(9, 2), "Idle", 60),
+            row(at(9, 3), "Idle", 60),
+            row(at(9, 4), "Coding", 60),
+            TimelineRow { sleep_gap: Some((at(10, 0), at(11, 30))), ..row(at(11, 30), "Idle", 0) },
+        ];
+        let blocks = merge_idle_blocks(&rows);
+        assert_eq!(
+            blocks,
+            vec![
+                IdleBlock { start: "2026-03-02 09:01:00".into(), end: "2026-03-02 09:04:00".into(), reports: 3 },
+                IdleBlock { start: "2026-03-02 10:00:00".into(), end: "2026-03-02 11:30:00".into(), reports: 1 },
+            ]
+        );
+        assert!(merge_idle_blocks(&rows[..1]).is_empty());
+    }
+
+    #[test]
+    fn sleep_gap_marker_text() {
+        let at = |h, m| chrono::NaiveDate::from_ymd_opt(2026, 3, 2).unwrap().and_hms_opt(h, m, 0).unwrap();
+        assert_eq!(sleep_gap_description(at(10, 2), at(11, 40)), "Computer asleep 10:02–11:40 (1h 38m)");
+        assert_eq!(sleep_gap_description(at(9, 0), at(9, 5)), "Computer asleep 09:00–09:05 (5m)");
+    }
+
+    #[test]
+    fn heatmap_has_every_day_in_range() {
+        let today = chrono::NaiveDate::from_ymd_opt(2026, 3, 2).unwrap();
+        let counts: std::collections::HashMap<String, i64> =
+            [("2026-02-28".to_string(), 5), ("2026-02-20".to_string(), 9)].into();
+        let map = daily_heatmap(&counts, today, 4);
+        assert_eq!(
+            map.into_iter().collect::<Vec<_>>(),
+            vec![
+                ("2026-02-27".to_string(), 0),
+                ("2026-02-28".to_string(), 5),
+                ("2026-03-01".to_string(), 0),
+                ("2026-03-02".to_string(), 0),
+            ]
+        );
+        assert_eq!(daily_heatmap(&counts, today, 0).len(), 1);
+        assert_eq!(daily_heatmap(&counts, today, 10_000).len(), HEATMAP_MAX_DAYS as usize);
+    }
+
     #[test]
     fn parse_prefers_explicit_category_field() {
         let raw = "APP: X\nCATEGORY: debugging\n";
@@ -245,6 +1056,60 @@ mod tests {
         }
     }
 
+    #[test]
+    fn scored_inference_on_real_world_descriptions() {
+        let cases = [
+            ("VS Code open on sync.rs, debugger paused at a breakpoint", "Debugging"),
+            ("Reading documentation in a browser about tokio runtimes", "Documentation"),
+            ("GitHub pull request in Chrome, leaving code review comments", "CodeReview"),
+            ("Running tests in the VS Code terminal; test results show 3 failures", "Testing"),
+            ("budget.xlsx open in Microsoft Excel", "Admin"),
+            ("Zoom call with the Jira board screen-shared", "Meeting"),
+            ("Writing docs for the README in VS Code", "Documentation"),
+            ("Figma design tool with checkout mockups", "Design"),
+            ("Watching a Rust tutorial course in Firefox", "Learning"),
+            ("Searching stackoverflow in Chrome for a lifetime error", "Research"),
+            ("Supabase SQL editor running a database migration", "Database"),
+            ("Chrome open on LinkedIn feed", "Browsing"),
+        ];
+        for (text, expected) in cases {
+            assert_eq!(
+                infer_category_from_content(&text.to_lowercase()),
+                expected,
+                "text={text:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn scored_inference_ties_follow_priority() {
+        // one hit each: Coding ("vs code") vs Documentation ("readme") → Coding ranks first
+        assert_eq!(infer_category_from_content("editing readme in vs code"), "Coding");
+        assert_eq!(infer_category_from_content("nothing recognizable"), "General");
+    }
+
+    #[test]
+    fn localized_keywords_classify_non_english_descriptions() {
+        let (_, c) = parse_analysis_for_language("Depurando el servicio, punto de interrupción en main.rs", "es");
+        assert_eq!(c, "Debugging");
+        let (_, c) = parse_analysis_for_language("Besprechung mit dem Team per Videoanruf", "de");
+        assert_eq!(c, "Meeting");
+        // English app names still count in any language
+        let (_, c) = parse_analysis_for_language("Editando en VS Code", "es");
+        assert_eq!(c, "Coding");
+        // unknown language: English keywords only
+        assert_eq!(infer_category_from_content_in("reunión", "xx"), "General");
+    }
+
+    #[test]
+    fn report_language_validation_and_instruction() {
+        assert_eq!(normalize_report_language(" ES ").unwrap(), "es");
+        assert!(normalize_report_language("klingon").is_err());
+        assert!(report_language_instruction("en").is_none());
+        let es = report_language_instruction("es").unwrap();
+        assert!(es.starts_with("Respond in Spanish"));
+    }
+
     #[test]
     fn parse_generic_editor_word_not_auto_coding() {
         let raw = "VISIBLE: drafting text in a generic editor window";
@@ -273,6 +1138,32 @@ mod tests {
         assert_eq!(c, "General");
     }
 
+    #[test]
+    fn canonical_category_normalizes_and_rejects_unknown() {
+        assert_eq!(canonical_category("code review"), Some("CodeReview"));
+        assert_eq!(canonical_category("DEVOPS"), Some("DevOps"));
+        assert_eq!(canonical_category("other"), None);
+    }
+
+    #[test]
+    fn developer_label_combines_name_and_device() {
+        assert_eq!(developer_label("Alex", Some("MacBook-Pro")), "Alex (MacBook-Pro)");
+        assert_eq!(developer_label("Alex", Some("  ")), "Alex");
+        assert_eq!(developer_label("", Some("DESKTOP-42")), "DESKTOP-42");
+        assert_eq!(developer_label("Alex", None), "Alex");
+    }
+
+    #[test]
+    fn sanitize_report_truncates_and_coerces() {
+        assert!(sanitize_report("   ", "Coding").is_err());
+        let (d, c) = sanitize_report(&"a".repeat(5000), "figma stuff").unwrap();
+        assert_eq!(d.chars().count(), REPORT_DESCRIPTION_MAX_CHARS);
+        assert!(d.ends_with('…'));
+        assert_eq!(c, "General");
+        let (_, c) = sanitize_report("Reviewing PR", "code_review").unwrap();
+        assert_eq!(c, "CodeReview");
+    }
+
     #[test]
     fn markdown_stripped_in_description() {
         let raw = "### APP: Test\n**VISIBLE**: x";
@@ -280,4 +1171,33 @@ mod tests {
         assert!(!d.contains("###"));
         assert!(!d.contains("**"));
     }
+
+    #[test]
+    fn gpu_offload_uses_last_load_in_log() {
+        let log = "llm_load_tensors: offloaded 56/57 layers to GPU\n\
+                   CUDA error: out of memory\n\
+                   load_tensors: offloaded 24/57 layers to GPU\n\
+                   srv  log_server_r: request: POST /v1/chat/completions 200";
+        assert_eq!(parse_gpu_offload(log), Some((24, 57)));
+        assert_eq!(parse_gpu_offload("main: server is listening"), None);
+        assert_eq!(gpu_placement(24, 57).0, "partial");
+        assert_eq!(gpu_placement(0, 57).0, "cpu");
+        assert_eq!(gpu_placement(57, 57), ("gpu", None));
+    }
+
+    #[test]
+    fn classify_description_explains_the_keyword_pick() {
+        let c = classify_description("Reading documentation in a browser (Chrome)", "en");
+        assert_eq!(c.category, "Documentation");
+        assert_eq!(c.source, "keywords");
+        assert_eq!(c.scores[0].matched, ["documentation"]);
+        assert_eq!(c.scores[1].category, "Browsing");
+        assert_eq!(c.scores[1].score, 1.0);
+        let parsed = parse_analysis_for_language("Reading documentation in a browser (Chrome)", "en").1;
+        assert_eq!(parsed, c.category);
+
+        let field = classify_description("APP: Chrome\nCATEGORY: Code Review", "en");
+        assert_eq!((field.category.as_str(), field.source), ("CodeReview", "field"));
+        assert_eq!(classify_description("???", "en").source, "default");
+    }
 }
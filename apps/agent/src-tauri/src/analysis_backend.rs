@@ -0,0 +1,213 @@
+//! `analysisBackend`: where the vision call goes. `"local"` (default) is the embedded
+//! llama-server; `"openai_compatible"` posts the same chat-completions request to
+//! `{apiBase}/v1/chat/completions` (LM Studio, vLLM, a hosted API) with `apiKey` as a bearer
+//! token, so the agent can run on machines too weak for the local model.
+
+use std::sync::Mutex;
+
+pub(crate) const ANALYSIS_BACKEND_LOCAL: &str = "local";
+pub(crate) const ANALYSIS_BACKEND_OPENAI_COMPATIBLE: &str = "openai_compatible";
+
+const EXTERNAL_HEALTH_TIMEOUT_SECS: u64 = 10;
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(crate) enum VisionBackend {
+    /// Managed llama-server on 127.0.0.1 (port resolved per call).
+    #[default]
+    LocalLlama,
+    OpenAICompatible {
+        /// Normalized: no trailing `/` and no trailing `/v1`.
+        base: String,
+        api_key: Option<String>,
+        model: String,
+    },
+}
+
+static BACKEND: Mutex<VisionBackend> = Mutex::new(VisionBackend::LocalLlama);
+
+type ExternalClientKey = (VisionBackend, crate::http_client::ProxySettings);
+static EXTERNAL_CLIENT: Mutex<Option<(ExternalClientKey, reqwest::blocking::Client)>> = Mutex::new(None);
+
+/// Called by the agent after loading / updating config.
+pub(crate) fn set_backend(backend: VisionBackend) {
+    if let Ok(mut g) = BACKEND.lock() {
+        *g = backend;
+    }
+}
+
+pub(crate) fn current() -> VisionBackend {
+    BACKEND.lock().map(|g| g.clone()).unwrap_or_default()
+}
+
+pub(crate) fn normalize_analysis_backend(raw: &str) -> Result<String, String> {
+    match raw.trim().to_lowercase().replace('-', "_").as_str() {
+        "local" | "llama" => Ok(ANALYSIS_BACKEND_LOCAL.to_string()),
+        "openai_compatible" | "openai" => Ok(ANALYSIS_BACKEND_OPENAI_COMPATIBLE.to_string()),
+        other => Err(format!(
+            "Invalid analysisBackend '{}' (expected \"local\" or \"openai_compatible\")",
+            other
+        )),
+    }
+}
+
+/// Trims, requires `http(s)://` and strips a trailing `/` or `/v1` so both
+/// `http://host:1234` and `http://host:1234/v1/` work. Blank means "not set".
+pub(crate) fn normalize_api_base(raw: Option<&str>) -> Result<Option<String>, String> {
+    let Some(v) = raw.map(str::trim).filter(|v| !v.is_empty()) else {
+        return Ok(None);
+    };
+    let parsed = reqwest::Url::parse(v).map_err(|e| format!("Invalid apiBase '{}': {}", v, e))?;
+    if !matches!(parsed.scheme(), "http" | "https") {
+        return Err(format!("Invalid apiBase '{}': expected an http(s) URL", v));
+    }
+    let base = v.trim_end_matches('/');
+    let base = base.strip_suffix("/v1").unwrap_or(base);
+    Ok(Some(base.to_string()))
+}
+
+/// Resolves the backend from config values; `openai_compatible` needs `apiBase` and `apiModel`.
+pub(crate) fn resolve(
+    backend: Option<&str>,
+    api_base: Option<&str>,
+    api_key: Option<&str>,
+    api_model: Option<&str>,
+) -> Result<VisionBackend, String> {
+    if normalize_analysis_backend(backend.unwrap_or(ANALYSIS_BACKEND_LOCAL))? == ANALYSIS_BACKEND_LOCAL {
+        return Ok(VisionBackend::LocalLlama);
+    }
+    let base = normalize_api_base(api_base)?
+        .ok_or("analysisBackend \"openai_compatible\" requires apiBase")?;
+    let model = api_model
+        .map(str::trim)
+        .filter(|m| !m.is_empty())
+        .ok_or("analysisBackend \"openai_compatible\" requires apiModel")?;
+    Ok(VisionBackend::OpenAICompatible {
+        base,
+        api_key: api_key.map(str::trim).filter(|k| !k.is_empty()).map(str::to_string),
+        model: model.to_string(),
+    })
+}
+
+impl VisionBackend {
+    pub(crate) fn is_external(&self) -> bool {
+        matches!(self, VisionBackend::OpenAICompatible { .. })
+    }
+
+    pub(crate) fn chat_completions_url(&self) -> Result<String, String> {
+        match self {
+            VisionBackend::LocalLlama => crate::llama_port::managed_chat_completions_url().ok_or_else(|| {
+                "Local vision server URL unknown — start the embedded Local AI server first.".to_string()
+            }),
+            VisionBackend::OpenAICompatible { base, .. } => Ok(format!("{}/v1/chat/completions", base)),
+        }
+    }
+
+    /// `model` field of the request body.
+    pub(crate) fn model<'a>(&'a self, local_id: &'a str) -> &'a str {
+        match self {
+            VisionBackend::LocalLlama => local_id,
+            VisionBackend::OpenAICompatible { model, .. } => model,
+        }
+    }
+
+    /// Shared loopback client for the local server; for an external backend a proxy-aware client
+    /// with the bearer token, cached until the backend or proxy settings change.
+    /// Set the timeout per request.
+    pub(crate) fn client(&self) -> Result<reqwest::blocking::Client, String> {
+        let VisionBackend::OpenAICompatible { api_key, .. } = self else {
+            return Ok(crate::http_client::local_client());
+        };
+        let key = (self.clone(), crate::http_client::proxy_settings());
+        crate::http_client::cached_client(&EXTERNAL_CLIENT, &key, || {
+            let mut headers = reqwest::header::HeaderMap::new();
+            if let Some(key) = api_key {
+                let mut value = reqwest::header::HeaderValue::from_str(&format!("Bearer {}", key))
+                    .map_err(|_| "apiKey contains invalid characters".to_string())?;
+                value.set_sensitive(true);
+                headers.insert(reqwest::header::AUTHORIZATION, value);
+            }
+            crate::http_client::cloud_builder()
+                .default_headers(headers)
+                .build()
+                .map_err(|e| e.to_string())
+        })
+    }
+}
+
+/// `check_local_server` payload for an external backend: `GET {base}/v1/models` with the key.
+pub(crate) fn external_health(backend: &VisionBackend) -> serde_json::Value {
+    let VisionBackend::OpenAICompatible { base, model, .. } = backend else {
+        return serde_json::json!({ "online": false, "external": false });
+    };
+    let result = backend
+        .client()
+        .and_then(|client| {
+            client
+                .get(format!("{}/v1/models", base))
+                .timeout(std::time::Duration::from_secs(EXTERNAL_HEALTH_TIMEOUT_SECS))
+                .send()
+                .map_err(|e| e.to_string())
+        });
+    match result {
+        Ok(r) if r.status().is_success() => serde_json::json!({
+            "online": true,
+            "installed": true,
+            "external": true,
+            "apiBase": base,
+            "models": [model],
+            "hasVisionModel": true,
+        }),
+        Ok(r) => serde_json::json!({
+            "online": false,
+            "installed": true,
+            "external": true,
+            "apiBase": base,
+            "error": format!("External analysis backend status: {}", r.status()),
+        }),
+        Err(e) => serde_json::json!({
+            "online": false,
+            "installed": true,
+            "external": true,
+            "apiBase": base,
+            "error": e,
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn api_base_is_normalized() {
+        assert_eq!(
+            normalize_api_base(Some(" http://localhost:1234/v1/ ")).unwrap().as_deref(),
+            Some("http://localhost:1234")
+        );
+        assert_eq!(
+            normalize_api_base(Some("https://api.example.com")).unwrap().as_deref(),
+            Some("https://api.example.com")
+        );
+        assert_eq!(normalize_api_base(Some("  ")).unwrap(), None);
+        assert!(normalize_api_base(Some("ftp://host")).is_err());
+        assert!(normalize_api_base(Some("not a url")).is_err());
+    }
+
+    #[test]
+    fn resolve_requires_base_and_model_for_external() {
+        assert_eq!(resolve(None, None, None, None).unwrap(), VisionBackend::LocalLlama);
+        assert!(resolve(Some("openai_compatible"), None, None, Some("m")).is_err());
+        assert!(resolve(Some("openai_compatible"), Some("http://h"), None, None).is_err());
+        let b = resolve(Some("openai"), Some("http://h/v1"), Some(" "), Some("gpt-4o-mini")).unwrap();
+        assert_eq!(b.chat_completions_url().unwrap(), "http://h/v1/chat/completions");
+        assert_eq!(b.model("local"), "gpt-4o-mini");
+        assert_eq!(
+            b,
+            VisionBackend::OpenAICompatible {
+                base: "http://h".into(),
+                api_key: None,
+                model: "gpt-4o-mini".into()
+            }
+        );
+    }
+}
@@ -1,5 +1,4 @@
 use serde::{Deserialize, Serialize};
-use reqwest::blocking::Client;
 use crate::sync_env::{supabase_anon_key, supabase_url};
 use oauth2::{
     basic::BasicClient, AuthUrl, ClientId, RedirectUrl, TokenUrl,
@@ -42,7 +41,7 @@ fn panic_payload_to_string(payload: Box<dyn std::any::Any + Send>) -> String {
     }
 }
 
-fn html_escape(value: &str) -> String {
+pub(crate) fn html_escape(value: &str) -> String {
     value
         .replace('&', "&amp;")
         .replace('<', "&lt;")
@@ -137,7 +136,7 @@ fn set_oauth_state(verifier: String, provider: String) {
             let mut lock = poisoned.into_inner();
             lock.verifier = Some(verifier);
             lock.provider = Some(provider);
-            println!("[Auth] OAuth state mutex was poisoned; recovered.");
+            log::warn!("[Auth] OAuth state mutex was poisoned; recovered.");
         }
     }
 }
@@ -148,12 +147,20 @@ fn get_oauth_state() -> (Option<String>, Option<String>) {
         Ok(lock) => (lock.verifier.clone(), lock.provider.clone()),
         Err(poisoned) => {
             let lock = poisoned.into_inner();
-            println!("[Auth] OAuth state mutex was poisoned; recovered.");
+            log::warn!("[Auth] OAuth state mutex was poisoned; recovered.");
             (lock.verifier.clone(), lock.provider.clone())
         }
     }
 }
 
+/// Forgets any OAuth flow in progress (factory reset).
+pub(crate) fn clear_oauth_state() {
+    if let Some(mutex) = OAUTH_STATE.get() {
+        let mut lock = mutex.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        *lock = OAuthState::default();
+    }
+}
+
 // Provider configs
 #[derive(Clone)]
 struct ProviderConfig {
@@ -381,6 +388,7 @@ fn create_oauth_client(provider: &str) -> Result<BasicClient, String> {
 #[tauri::command]
 pub fn start_auth(provider: String) -> Result<String, String> {
     auth_log(format!("[Auth] start_auth requested for provider: {}", provider));
+    crate::http_client::ensure_network_allowed()?;
 
     let db_path = crate::paths::db_path()?;
 
@@ -481,7 +489,7 @@ fn listen_for_callback() {
         match Server::http("127.0.0.1:12345") {
             Ok(s) => { server_opt = Some(s); break; }
             Err(_) => {
-                println!("[Auth] Port 12345 busy (attempt {}), retrying...", attempt + 1);
+                log::warn!("[Auth] Port 12345 busy (attempt {}), retrying...", attempt + 1);
                 std::thread::sleep(std::time::Duration::from_secs(1));
             }
         }
@@ -489,7 +497,7 @@ fn listen_for_callback() {
     let server = match server_opt {
         Some(s) => s,
         None => {
-            println!("[Auth] Could not bind port 12345 after 5 attempts");
+            log::error!("[Auth] Could not bind port 12345 after 5 attempts");
             return;
         }
     };
@@ -499,22 +507,22 @@ fn listen_for_callback() {
     // recv_timeout con deadline manual).
     let deadline = std::time::Instant::now() + std::time::Duration::from_secs(120);
 
-    println!("[Auth] Listening for callback on 127.0.0.1:12345...");
+    log::info!("[Auth] Listening for callback on 127.0.0.1:12345...");
 
     loop {
         let remaining = deadline.saturating_duration_since(std::time::Instant::now());
         if remaining.is_zero() {
-            println!("[Auth] Listener timed out waiting for OAuth callback");
+            log::warn!("[Auth] Listener timed out waiting for OAuth callback");
             break;
         }
         let request = match server.recv_timeout(remaining) {
             Ok(Some(r)) => r,
             Ok(None) => {
-                println!("[Auth] Listener timed out waiting for OAuth callback");
+                log::warn!("[Auth] Listener timed out waiting for OAuth callback");
                 break;
             }
             Err(e) => {
-                println!("[Auth] Listener error: {}", e);
+                log::warn!("[Auth] Listener error: {}", e);
                 break;
             }
         };
@@ -523,7 +531,7 @@ fn listen_for_callback() {
         let parsed = match Url::parse(&url) {
             Ok(p) => p,
             Err(e) => {
-                println!("[Auth] Ignoring unparsable callback URL ({}): {}", e, url);
+                log::warn!("[Auth] Ignoring unparsable callback URL ({}): {}", e, url);
                 let _ = request.respond(Response::from_string("Bad request"));
                 continue;
             }
@@ -536,7 +544,7 @@ fn listen_for_callback() {
             let (verifier, provider) = match (verifier_opt, provider_opt) {
                 (Some(v), Some(p)) => (v, p),
                 _ => {
-                    println!("[Auth] Callback received but OAuth state is missing");
+                    log::warn!("[Auth] Callback received but OAuth state is missing");
                     let _ = request.respond(Response::from_string("Error: Invalid OAuth state"));
                     continue;
                 }
@@ -756,7 +764,7 @@ fn listen_for_supabase_callback() {
 }
 
 fn fetch_supabase_user(access_token: &str) -> Result<AuthUser, String> {
-    let client = Client::new();
+    let client = crate::http_client::cloud_client();
     let resp = client.get(format!("{}/auth/v1/user", supabase_url()))
         .header("apikey", supabase_anon_key())
         .header("Authorization", format!("Bearer {}", access_token))
@@ -805,7 +813,7 @@ fn exchange_code(provider: &str, code: &str, verifier: &str) -> Result<AuthSessi
 }
 
 fn fetch_user_info(provider: &str, access_token: &str) -> Result<AuthUser, String> {
-    let http_client = Client::new();
+    let http_client = crate::http_client::cloud_client();
     
     match provider {
         "google" => {
@@ -870,12 +878,8 @@ fn get_db_conn() -> Result<Connection, String> {
     // `initialize_agent` haya corrido (el usuario no está logueado todavía),
     // así que hay que garantizar el directorio acá o sqlite devuelve
     // "unable to open database file" y perdemos la sesión en silencio.
-    let base = dirs::data_local_dir().ok_or("No local data dir")?;
-    let dir = base.join("FlowSight");
-    if !dir.exists() {
-        std::fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
-    }
-    let db_path = dir.join("dev-agent.db");
+    // `paths::db_path` crea el directorio (y respeta `FLOWSIGHT_DATA_DIR`).
+    let db_path = crate::paths::db_path()?;
     let conn = Connection::open(&db_path).map_err(|e| e.to_string())?;
     // Asegurar tabla `config` por si somos los primeros en abrir la DB (antes
     // de que agent::init_db corra). Sin esto, los INSERT posteriores también
@@ -904,7 +908,7 @@ fn save_jira_specific_tokens(session: &AuthSession) {
         }
         
         // Fetch and save Cloud ID (needed for Jira API calls)
-        let http_client = Client::new();
+        let http_client = crate::http_client::cloud_client();
         match http_client.get("https://api.atlassian.com/oauth/token/accessible-resources")
             .bearer_auth(&session.access_token)
             .send()
@@ -916,14 +920,14 @@ fn save_jira_specific_tokens(session: &AuthSession) {
                             "INSERT OR REPLACE INTO config (key, value) VALUES ('jira_cloud_id', ?1)",
                             [cloud_id]
                         );
-                        println!("[Auth] Saved Jira cloud_id: {}", cloud_id);
+                        log::info!("[Auth] Saved Jira cloud_id: {}", cloud_id);
                     }
                 }
             }
-            Err(e) => println!("[Auth] Could not fetch Jira cloud_id: {}", e),
+            Err(e) => log::warn!("[Auth] Could not fetch Jira cloud_id: {}", e),
         }
         
-        println!("[Auth] Jira-specific tokens saved for jira.rs compatibility");
+        log::info!("[Auth] Jira-specific tokens saved for jira.rs compatibility");
     }
 }
 
@@ -935,11 +939,11 @@ fn save_auth_session(session: &AuthSession) {
                 "INSERT OR REPLACE INTO config (key, value) VALUES ('auth_session', ?1)",
                 [&json],
             ) {
-                Ok(_) => println!("[Auth] Session saved for: {}", session.user.email),
-                Err(e) => println!("[Auth] FAILED to persist session for {}: {}", session.user.email, e),
+                Ok(_) => log::info!("[Auth] Session saved for: {}", session.user.email),
+                Err(e) => log::error!("[Auth] FAILED to persist session for {}: {}", session.user.email, e),
             }
         }
-        Err(e) => println!("[Auth] FAILED to open DB while saving session: {}", e),
+        Err(e) => log::error!("[Auth] FAILED to open DB while saving session: {}", e),
     }
 }
 
@@ -967,7 +971,7 @@ pub fn logout() -> Result<(), String> {
     conn.execute("DELETE FROM config WHERE key = 'user_session'", [])
         .map_err(|e| e.to_string())?;
     crate::entitlements::clear_entitlements(&conn)?;
-    println!("[Auth] Logged out (cleared auth_session + user_session + entitlements)");
+    log::info!("[Auth] Logged out (cleared auth_session + user_session + entitlements)");
     Ok(())
 }
 
@@ -1000,12 +1004,13 @@ pub(crate) fn parse_tokens_from_oauth_code(code: &str) -> Result<(String, Option
 
 #[tauri::command]
 pub fn login_with_code(code: String) -> Result<AuthSession, String> {
+    crate::http_client::ensure_network_allowed()?;
     // Support both raw JWT tokens and full redirect URLs with hash fragments
     // e.g., "https://flowsight.site/#access_token=eyJ...&refresh_token=abc&..."
     let (access_token, refresh_token) = match parse_tokens_from_oauth_code(&code) {
         Ok((at, rt)) => {
             if code.contains("access_token=") {
-                println!("[Auth] Extracted tokens from URL (refresh_token: {})", rt.is_some());
+                log::debug!("[Auth] Extracted tokens from URL (refresh_token: {})", rt.is_some());
             }
             (at, rt)
         }
@@ -1013,7 +1018,7 @@ pub fn login_with_code(code: String) -> Result<AuthSession, String> {
     };
     
     // Validate against Supabase Auth API
-    let client = Client::new();
+    let client = crate::http_client::cloud_client();
     let resp = client.get(format!("{}/auth/v1/user", supabase_url()))
         .header("apikey", supabase_anon_key())
         .header("Authorization", format!("Bearer {}", access_token))
@@ -1046,7 +1051,7 @@ pub fn login_with_code(code: String) -> Result<AuthSession, String> {
     };
     
     save_auth_session(&session);
-    println!("[Auth] Login with code successful for: {}", session.user.email);
+    log::info!("[Auth] Login with code successful for: {}", session.user.email);
     Ok(session)
 }
 
@@ -0,0 +1,343 @@
+//! "Stuck on an error" detection: when the last few captures all show the same error context
+//! (stack trace, failing test, compiler error…), record a blocker with its summary and duration.
+//!
+//! Detection runs after every `save_activity` on the local `reports` table; blockers live in the
+//! local `blockers` table (created in `FlowSightAgent::init_db`).
+
+use rusqlite::{params, Connection};
+use serde::Serialize;
+use std::collections::HashSet;
+
+/// Consecutive error captures with the same context before we call it a blocker.
+pub(crate) const BLOCKER_MIN_INTERVALS: usize = 3;
+/// How many recent reports the detector looks at.
+const BLOCKER_LOOKBACK: usize = 20;
+/// Token overlap (Jaccard) above which two error descriptions count as the same context.
+const SAME_CONTEXT_MIN_SIMILARITY: f32 = 0.35;
+const SUMMARY_MAX_CHARS: usize = 200;
+
+const ERROR_MARKERS: &[&str] = &[
+    "error",
+    "exception",
+    "traceback",
+    "stack trace",
+    "stacktrace",
+    "panic",
+    "segfault",
+    "failing test",
+    "tests failed",
+    "test failed",
+    "failed",
+    "cannot find",
+    "undefined",
+    "crash",
+];
+
+#[derive(Serialize, Clone, Debug)]
+pub struct Blocker {
+    pub id: i64,
+    pub summary: String,
+    pub started_at: String,
+    pub last_seen_at: String,
+    pub occurrences: i64,
+    pub duration_seconds: i64,
+    pub resolved: bool,
+}
+
+/// Minimal view of a `reports` row used by the detector (newest first).
+#[derive(Clone, Debug)]
+pub(crate) struct ReportRow {
+    pub id: i64,
+    pub description: String,
+    pub activity_type: String,
+    pub duration_seconds: i64,
+}
+
+#[derive(Debug, PartialEq)]
+pub(crate) struct Streak {
+    pub first_report_id: i64,
+    pub last_report_id: i64,
+    pub occurrences: usize,
+    pub duration_seconds: i64,
+    pub summary: String,
+    /// `(report id, duration)` of every report in the run, newest first.
+    pub members: Vec<(i64, i64)>,
+}
+
+impl Streak {
+    /// Captures and seconds in this run that came after `report_id`.
+    fn since(&self, report_id: i64) -> (i64, i64) {
+        self.members
+            .iter()
+            .filter(|(id, _)| *id > report_id)
+            .fold((0, 0), |(n, secs), (_, d)| (n + 1, secs + d))
+    }
+}
+
+pub(crate) fn is_error_report(activity_type: &str, description: &str) -> bool {
+    let lower = description.to_lowercase();
+    let has_marker = ERROR_MARKERS.iter().any(|m| lower.contains(m));
+    // Idle / meetings mentioning "error" in a slide title are not someone stuck on a bug.
+    has_marker && !matches!(activity_type, "Idle" | "Meeting" | "Sales")
+}
+
+fn context_tokens(description: &str) -> HashSet<String> {
+    description
+        .to_lowercase()
+        .split(|c: char| !c.is_alphanumeric() && c != '_' && c != '.')
+        .map(|w| w.trim_matches('.'))
+        .filter(|w| w.len() >= 4)
+        .map(str::to_string)
+        .collect()
+}
+
+pub(crate) fn same_context(a: &str, b: &str) -> bool {
+    let (ta, tb) = (context_tokens(a), context_tokens(b));
+    if ta.is_empty() || tb.is_empty() {
+        return false;
+    }
+    let inter = ta.intersection(&tb).count() as f32;
+    let union = ta.union(&tb).count() as f32;
+    inter / union >= SAME_CONTEXT_MIN_SIMILARITY
+}
+
+/// Leading run of error reports (newest first) that share the newest report's context.
+pub(crate) fn detect_streak(rows: &[ReportRow]) -> Option<Streak> {
+    let newest = rows.first()?;
+    if !is_error_report(&newest.activity_type, &newest.description) {
+        return None;
+    }
+    let run: Vec<&ReportRow> = rows
+        .iter()
+        .take_while(|r| {
+            is_error_report(&r.activity_type, &r.description)
+                && same_context(&newest.description, &r.description)
+        })
+        .collect();
+    if run.len() < BLOCKER_MIN_INTERVALS {
+        return None;
+    }
+    Some(Streak {
+        first_report_id: run.last()?.id,
+        last_report_id: newest.id,
+        occurrences: run.len(),
+        duration_seconds: run.iter().map(|r| r.duration_seconds).sum(),
+        summary: newest.description.chars().take(SUMMARY_MAX_CHARS).collect(),
+        members: run.iter().map(|r| (r.id, r.duration_seconds)).collect(),
+    })
+}
+
+fn recent_reports(conn: &Connection) -> Result<Vec<ReportRow>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, COALESCE(description, ''), COALESCE(activity_type, ''), COALESCE(duration_seconds, 30)
+             FROM reports ORDER BY id DESC LIMIT ?1",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([BLOCKER_LOOKBACK as i64], |r| {
+            Ok(ReportRow {
+                id: r.get(0)?,
+                description: r.get(1)?,
+                activity_type: r.get(2)?,
+                duration_seconds: r.get(3)?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+    Ok(rows.flatten().collect())
+}
+
+/// Runs after each saved report. Opens or extends the blocker for the current error streak;
+/// a streak whose blocker was already resolved by the user is not re-opened.
+///
+/// The detector only sees the last `BLOCKER_LOOKBACK` reports, so a long streak's window start
+/// keeps moving. The blocker to extend is therefore the one whose `last_report_id` falls inside
+/// the current run, and its counters grow by the captures seen since then.
+pub(crate) fn detect_blockers(conn: &Connection) -> Result<Option<i64>, String> {
+    let Some(streak) = detect_streak(&recent_reports(conn)?) else {
+        return Ok(None);
+    };
+    let existing: Option<(i64, i64, Option<String>)> = conn
+        .query_row(
+            "SELECT id, last_report_id, resolved_at FROM blockers
+             WHERE last_report_id BETWEEN ?1 AND ?2 ORDER BY id DESC LIMIT 1",
+            [streak.first_report_id, streak.last_report_id],
+            |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?)),
+        )
+        .ok();
+    match existing {
+        Some((id, prev_last, resolved_at)) => {
+            let (new_captures, new_secs) = streak.since(prev_last);
+            if resolved_at.is_some() {
+                // Keep following the run so it doesn't come back once its start scrolls out.
+                conn.execute(
+                    "UPDATE blockers SET last_report_id = ?1 WHERE id = ?2",
+                    params![streak.last_report_id, id],
+                )
+                .map_err(|e| e.to_string())?;
+                return Ok(None);
+            }
+            conn.execute(
+                "UPDATE blockers SET last_report_id = ?1, occurrences = occurrences + ?2,
+                 duration_seconds = duration_seconds + ?3, summary = ?4,
+                 last_seen_at = CURRENT_TIMESTAMP WHERE id = ?5",
+                params![
+                    streak.last_report_id,
+                    new_captures,
+                    new_secs,
+                    streak.summary,
+                    id
+                ],
+            )
+            .map_err(|e| e.to_string())?;
+            Ok(Some(id))
+        }
+        None => {
+            conn.execute(
+                "INSERT INTO blockers (first_report_id, last_report_id, occurrences, duration_seconds, summary)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![
+                    streak.first_report_id,
+                    streak.last_report_id,
+                    streak.occurrences as i64,
+                    streak.duration_seconds,
+                    streak.summary
+                ],
+            )
+            .map_err(|e| e.to_string())?;
+            log::info!(
+                "[Blockers] new blocker after {} error captures: {}",
+                streak.occurrences,
+                streak.summary
+            );
+            Ok(Some(conn.last_insert_rowid()))
+        }
+    }
+}
+
+#[tauri::command]
+pub fn get_blockers(include_resolved: Option<bool>) -> Result<Vec<Blocker>, String> {
+    let db_path = crate::paths::db_path()?;
+    let conn = Connection::open(&db_path).map_err(|e| e.to_string())?;
+    let sql = if include_resolved.unwrap_or(false) {
+        "SELECT id, summary, started_at, last_seen_at, occurrences, duration_seconds, resolved_at IS NOT NULL
+         FROM blockers ORDER BY id DESC"
+    } else {
+        "SELECT id, summary, started_at, last_seen_at, occurrences, duration_seconds, resolved_at IS NOT NULL
+         FROM blockers WHERE resolved_at IS NULL ORDER BY id DESC"
+    };
+    let mut stmt = conn.prepare(sql).map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([], |r| {
+            Ok(Blocker {
+                id: r.get(0)?,
+                summary: r.get(1)?,
+                started_at: r.get(2)?,
+                last_seen_at: r.get(3)?,
+                occurrences: r.get(4)?,
+                duration_seconds: r.get(5)?,
+                resolved: r.get(6)?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+    Ok(rows.flatten().collect())
+}
+
+#[tauri::command]
+pub fn resolve_blocker(id: i64) -> Result<bool, String> {
+    let db_path = crate::paths::db_path()?;
+    let conn = Connection::open(&db_path).map_err(|e| e.to_string())?;
+    let n = conn
+        .execute(
+            "UPDATE blockers SET resolved_at = CURRENT_TIMESTAMP WHERE id = ?1 AND resolved_at IS NULL",
+            [id],
+        )
+        .map_err(|e| e.to_string())?;
+    Ok(n > 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(id: i64, ty: &str, desc: &str) -> ReportRow {
+        ReportRow {
+            id,
+            description: desc.to_string(),
+            activity_type: ty.to_string(),
+            duration_seconds: 60,
+        }
+    }
+
+    const RUST_ERR: &str = "VS Code terminal: cargo build error E0382 borrow of moved value in sync.rs";
+
+    #[test]
+    fn three_same_errors_make_a_streak() {
+        let rows = vec![
+            row(12, "Debugging", RUST_ERR),
+            row(11, "Debugging", "Terminal shows cargo build error E0382 borrow of moved value sync.rs"),
+            row(10, "Coding", RUST_ERR),
+            row(9, "Coding", "Editing README.md"),
+        ];
+        let s = detect_streak(&rows).unwrap();
+        assert_eq!((s.first_report_id, s.last_report_id, s.occurrences), (10, 12, 3));
+        assert_eq!(s.duration_seconds, 180);
+    }
+
+    #[test]
+    fn different_errors_or_short_runs_are_not_blockers() {
+        let rows = vec![
+            row(3, "Debugging", RUST_ERR),
+            row(2, "Testing", "Jest: 4 tests failed in checkout.spec.ts snapshot mismatch"),
+            row(1, "Debugging", RUST_ERR),
+        ];
+        assert_eq!(detect_streak(&rows), None);
+        assert_eq!(detect_streak(&rows[..1]), None);
+    }
+
+    #[test]
+    fn non_error_newest_report_ends_streak() {
+        assert!(!is_error_report("Coding", "Writing the retention module"));
+        assert!(!is_error_report("Meeting", "Slides about error budgets"));
+        assert!(is_error_report("Debugging", "Python Traceback in worker.py"));
+    }
+
+    #[test]
+    fn long_streak_keeps_one_blocker_and_stays_resolved() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE reports (id INTEGER PRIMARY KEY, description TEXT, activity_type TEXT,
+                duration_seconds INTEGER);
+             CREATE TABLE blockers (id INTEGER PRIMARY KEY, first_report_id INTEGER UNIQUE,
+                last_report_id INTEGER, occurrences INTEGER, duration_seconds INTEGER, summary TEXT,
+                started_at TEXT DEFAULT CURRENT_TIMESTAMP, last_seen_at TEXT DEFAULT CURRENT_TIMESTAMP,
+                resolved_at TEXT);",
+        )
+        .unwrap();
+        let capture = |conn: &Connection| {
+            conn.execute(
+                "INSERT INTO reports (description, activity_type, duration_seconds) VALUES (?1, 'Debugging', 60)",
+                [RUST_ERR],
+            )
+            .unwrap();
+            detect_blockers(conn).unwrap()
+        };
+        let total = BLOCKER_LOOKBACK + 10;
+        let ids: Vec<Option<i64>> = (0..total).map(|_| capture(&conn)).collect();
+        let first = ids[BLOCKER_MIN_INTERVALS - 1].unwrap();
+        assert!(ids[BLOCKER_MIN_INTERVALS - 1..].iter().all(|id| *id == Some(first)));
+        let (count, occurrences, secs): (i64, i64, i64) = conn
+            .query_row("SELECT COUNT(*), MAX(occurrences), MAX(duration_seconds) FROM blockers", [], |r| {
+                Ok((r.get(0)?, r.get(1)?, r.get(2)?))
+            })
+            .unwrap();
+        assert_eq!((count, occurrences, secs), (1, total as i64, 60 * total as i64));
+
+        conn.execute("UPDATE blockers SET resolved_at = CURRENT_TIMESTAMP", []).unwrap();
+        for _ in 0..BLOCKER_LOOKBACK + 5 {
+            assert_eq!(capture(&conn), None);
+        }
+        let count: i64 = conn.query_row("SELECT COUNT(*) FROM blockers", [], |r| r.get(0)).unwrap();
+        assert_eq!(count, 1);
+    }
+}
@@ -0,0 +1,383 @@
+//! `captureTarget`: analyze the whole primary screen (default) or only the foreground window,
+//! so email/chat next to the IDE never reaches the vision model. Any failure to resolve the
+//! window geometry falls back to the full screen.
+//!
+//! `captureMonitor` picks which display "the screen" is on multi-monitor setups; `list_screens`
+//! feeds the settings picker.
+//!
+//! `enableScreenCapture = false` turns every grab here into `CAPTURE_DISABLED_ERR`, so no
+//! trigger (timer, manual, change detection, picker thumbnails) can read the screen.
+//!
+//! `imageGrayscale` / `imageContrast` / `imageSharpen` post-process the (already downscaled)
+//! frame sent to the vision model; all off by default.
+
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use serde::Serialize;
+use std::sync::atomic::{AtomicBool, AtomicI64, AtomicU64, Ordering};
+use std::sync::Mutex;
+
+pub(crate) const CAPTURE_TARGET_SCREEN: &str = "screen";
+pub(crate) const CAPTURE_TARGET_ACTIVE_WINDOW: &str = "active_window";
+
+/// Windows smaller than this (tooltips, tray popups) are not worth analyzing on their own.
+const MIN_WINDOW_SIDE_PX: u32 = 64;
+
+/// Set by the agent after loading / updating config; read by every frame grab.
+static ACTIVE_WINDOW_ONLY: AtomicBool = AtomicBool::new(false);
+/// `enableScreenCapture`; checked before any pixels are read.
+static SCREEN_CAPTURE_ENABLED: AtomicBool = AtomicBool::new(true);
+/// Configured `captureMonitor` index, `-1` = unset.
+static CAPTURE_MONITOR: AtomicI64 = AtomicI64::new(-1);
+
+pub(crate) const IMAGE_CONTRAST_RANGE: std::ops::RangeInclusive<f32> = -100.0..=100.0;
+/// `unsharpen` parameters: mild enough not to halo anti-aliased editor text.
+const SHARPEN_SIGMA: f32 = 1.0;
+const SHARPEN_THRESHOLD: i32 = 2;
+
+/// Optional steps applied after the resize, in this order: grayscale, contrast, sharpen.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub(crate) struct Preprocessing {
+    pub grayscale: bool,
+    /// `image::adjust_contrast` percentage; `0.0` = off.
+    pub contrast: f32,
+    pub sharpen: bool,
+}
+
+static PREPROCESSING: Mutex<Preprocessing> = Mutex::new(Preprocessing {
+    grayscale: false,
+    contrast: 0.0,
+    sharpen: false,
+});
+
+pub(crate) fn set_preprocessing(p: Preprocessing) {
+    if let Ok(mut g) = PREPROCESSING.lock() {
+        *g = p;
+    }
+}
+
+pub(crate) fn validate_image_contrast(c: f32) -> Result<f32, String> {
+    if IMAGE_CONTRAST_RANGE.contains(&c) {
+        Ok(c)
+    } else {
+        Err(format!("imageContrast must be between -100 and 100 (got {})", c))
+    }
+}
+
+fn apply_preprocessing(img: image::DynamicImage, p: Preprocessing) -> image::DynamicImage {
+    // plain Luma8 (no alpha channel): the smallest PNG
+    let mut img = if p.grayscale {
+        image::DynamicImage::ImageLuma8(img.to_luma8())
+    } else {
+        img
+    };
+    if p.contrast != 0.0 {
+        img = img.adjust_contrast(p.contrast);
+    }
+    if p.sharpen {
+        img = img.unsharpen(SHARPEN_SIGMA, SHARPEN_THRESHOLD);
+    }
+    img
+}
+
+/// Frame as sent to the vision model, with the configured preprocessing.
+pub(crate) fn preprocess(img: image::DynamicImage) -> image::DynamicImage {
+    let p = PREPROCESSING.lock().map(|g| *g).unwrap_or_default();
+    apply_preprocessing(img, p)
+}
+
+/// `maxImageBytes` default: base64 payloads above this are shrunk before the vision call
+/// (some Ollama / proxy setups reject or time out on very large request bodies).
+pub(crate) const MAX_IMAGE_BYTES_DEFAULT: u64 = 2_000_000;
+/// Smallest accepted `maxImageBytes`; a half-size JPEG of a 960x540 frame fits well below it.
+pub(crate) const MAX_IMAGE_BYTES_MIN: u64 = 100_000;
+/// JPEG quality of a shrunk frame: text in editors / browsers stays legible.
+const SHRINK_JPEG_QUALITY: u8 = 70;
+
+static MAX_IMAGE_BYTES: AtomicU64 = AtomicU64::new(MAX_IMAGE_BYTES_DEFAULT);
+
+pub(crate) fn set_max_image_bytes(bytes: u64) {
+    MAX_IMAGE_BYTES.store(bytes.max(MAX_IMAGE_BYTES_MIN), Ordering::Relaxed);
+}
+
+pub(crate) fn max_image_bytes() -> u64 {
+    MAX_IMAGE_BYTES.load(Ordering::Relaxed)
+}
+
+pub(crate) fn validate_max_image_bytes(bytes: u64) -> Result<u64, String> {
+    if bytes >= MAX_IMAGE_BYTES_MIN {
+        Ok(bytes)
+    } else {
+        Err(format!(
+            "maxImageBytes must be at least {} (got {})",
+            MAX_IMAGE_BYTES_MIN, bytes
+        ))
+    }
+}
+
+/// Half-size JPEG re-encode of a base64 frame: `(base64, width, height)`.
+pub(crate) fn shrink_base64_image(b64: &str) -> Result<(String, u32, u32), String> {
+    let bytes = BASE64.decode(b64).map_err(|e| format!("Invalid image data: {}", e))?;
+    let img = image::load_from_memory(&bytes).map_err(|e| e.to_string())?;
+    let (w, h) = ((img.width() / 2).max(1), (img.height() / 2).max(1));
+    let small = img.resize(w, h, image::imageops::FilterType::Triangle).to_rgb8();
+    let mut jpeg = Vec::new();
+    image::codecs::jpeg::JpegEncoder::new_with_quality(&mut jpeg, SHRINK_JPEG_QUALITY)
+        .encode_image(&small)
+        .map_err(|e| e.to_string())?;
+    Ok((BASE64.encode(&jpeg), small.width(), small.height()))
+}
+
+/// MIME type for the data URL: frames are PNG unless `shrink_base64_image` re-encoded them.
+pub(crate) fn image_mime(b64: &str) -> &'static str {
+    if b64.starts_with("/9j/") {
+        "image/jpeg"
+    } else {
+        "image/png"
+    }
+}
+
+const THUMBNAIL_MAX_W: u32 = 320;
+const THUMBNAIL_MAX_H: u32 = 180;
+
+pub(crate) const CAPTURE_DISABLED_ERR: &str = "Screen capture is disabled in settings (enableScreenCapture).";
+
+pub(crate) fn set_screen_capture_enabled(on: bool) {
+    SCREEN_CAPTURE_ENABLED.store(on, Ordering::Relaxed);
+}
+
+pub(crate) fn screen_capture_enabled() -> bool {
+    SCREEN_CAPTURE_ENABLED.load(Ordering::Relaxed)
+}
+
+pub(crate) fn set_capture_monitor(index: Option<u32>) {
+    CAPTURE_MONITOR.store(index.map_or(-1, i64::from), Ordering::Relaxed);
+}
+
+/// Index to capture among `count` displays: the configured one if it is still connected,
+/// otherwise the first. `None` when no display is attached.
+pub(crate) fn monitor_index(count: usize) -> Option<usize> {
+    pick_monitor(CAPTURE_MONITOR.load(Ordering::Relaxed), count)
+}
+
+fn pick_monitor(configured: i64, count: usize) -> Option<usize> {
+    if count == 0 {
+        return None;
+    }
+    match usize::try_from(configured) {
+        Ok(i) if i < count => Some(i),
+        Ok(i) => {
+            log::debug!("[Capture] captureMonitor {} not connected ({} display(s)); using 0", i, count);
+            Some(0)
+        }
+        Err(_) => Some(0),
+    }
+}
+
+pub(crate) fn set_capture_target(target: &str) {
+    ACTIVE_WINDOW_ONLY.store(target == CAPTURE_TARGET_ACTIVE_WINDOW, Ordering::Relaxed);
+}
+
+pub(crate) fn normalize_capture_target(target: &str) -> Result<String, String> {
+    match target.trim().to_lowercase().replace('-', "_").as_str() {
+        "screen" => Ok(CAPTURE_TARGET_SCREEN.to_string()),
+        "active_window" | "window" => Ok(CAPTURE_TARGET_ACTIVE_WINDOW.to_string()),
+        other => Err(format!(
+            "Invalid captureTarget '{}' (expected \"screen\" or \"active_window\")",
+            other
+        )),
+    }
+}
+
+/// Window rect `(x, y, w, h)` in global coordinates clipped to the display rect; returns the
+/// area relative to the display, or `None` if what is left is too small.
+pub(crate) fn window_area_on_display(
+    window: (f64, f64, f64, f64),
+    display: (i32, i32, u32, u32),
+) -> Option<(i32, i32, u32, u32)> {
+    let (wx, wy, ww, wh) = window;
+    let (dx, dy, dw, dh) = display;
+    let x1 = (wx.round() as i64).max(dx as i64);
+    let y1 = (wy.round() as i64).max(dy as i64);
+    let x2 = ((wx + ww).round() as i64).min(dx as i64 + dw as i64);
+    let y2 = ((wy + wh).round() as i64).min(dy as i64 + dh as i64);
+    let (w, h) = (x2 - x1, y2 - y1);
+    if w < MIN_WINDOW_SIDE_PX as i64 || h < MIN_WINDOW_SIDE_PX as i64 {
+        return None;
+    }
+    Some(((x1 - dx as i64) as i32, (y1 - dy as i64) as i32, w as u32, h as u32))
+}
+
+fn grab_active_window() -> Result<image::DynamicImage, String> {
+    use screenshots::Screen;
+
+    let win = active_win_pos_rs::get_active_window()
+        .map_err(|_| "active window unavailable".to_string())?;
+    let p = win.position;
+    let screen = Screen::from_point((p.x + p.width / 2.0) as i32, (p.y + p.height / 2.0) as i32)
+        .map_err(|e| e.to_string())?;
+    let d = screen.display_info;
+    let (x, y, w, h) = window_area_on_display((p.x, p.y, p.width, p.height), (d.x, d.y, d.width, d.height))
+        .ok_or_else(|| format!("window '{}' too small or off-screen", win.app_name))?;
+    let captured = screen.capture_area(x, y, w, h).map_err(|e| e.to_string())?;
+    let (width, height) = captured.dimensions();
+    Ok(image::DynamicImage::ImageRgba8(
+        image::RgbaImage::from_raw(width, height, captured.into_raw())
+            .ok_or("Failed to create image")?,
+    ))
+}
+
+/// Frame for analysis / change detection according to `captureTarget`.
+pub(crate) fn grab_frame() -> Result<image::DynamicImage, String> {
+    if !screen_capture_enabled() {
+        return Err(CAPTURE_DISABLED_ERR.to_string());
+    }
+    if ACTIVE_WINDOW_ONLY.load(Ordering::Relaxed) {
+        match grab_active_window() {
+            Ok(img) => return Ok(img),
+            Err(e) => log::debug!("[Capture] active window capture failed ({}); using full screen", e),
+        }
+    }
+    crate::agent::grab_primary_screen()
+}
+
+#[derive(Serialize, Debug)]
+pub struct ScreenInfo {
+    pub index: usize,
+    pub id: u32,
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+    pub scale_factor: f32,
+    pub is_primary: bool,
+    /// This is the display `captureMonitor` currently resolves to.
+    pub selected: bool,
+    /// Small PNG (base64) for the settings picker; `None` if that display could not be captured.
+    pub thumbnail: Option<String>,
+}
+
+pub(crate) fn thumbnail_base64(img: &image::DynamicImage) -> Option<String> {
+    let small = img.thumbnail(THUMBNAIL_MAX_W, THUMBNAIL_MAX_H);
+    let mut png = Vec::new();
+    small
+        .write_to(&mut std::io::Cursor::new(&mut png), image::ImageFormat::Png)
+        .ok()?;
+    Some(BASE64.encode(&png))
+}
+
+/// Connected displays in `captureMonitor` index order, with a thumbnail of each.
+#[tauri::command]
+pub async fn list_screens() -> Result<Vec<ScreenInfo>, String> {
+    tauri::async_runtime::spawn_blocking(|| {
+        let screens = screenshots::Screen::all().map_err(|e| e.to_string())?;
+        let selected = monitor_index(screens.len());
+        let thumbnails = screen_capture_enabled();
+        Ok(screens
+            .iter()
+            .enumerate()
+            .map(|(index, screen)| {
+                let d = screen.display_info;
+                let thumbnail = thumbnails.then(|| screen.capture().ok()).flatten().and_then(|captured| {
+                    let (w, h) = captured.dimensions();
+                    let img = image::RgbaImage::from_raw(w, h, captured.into_raw())?;
+                    thumbnail_base64(&image::DynamicImage::ImageRgba8(img))
+                });
+                ScreenInfo {
+                    index,
+                    id: d.id,
+                    x: d.x,
+                    y: d.y,
+                    width: d.width,
+                    height: d.height,
+                    scale_factor: d.scale_factor,
+                    is_primary: d.is_primary,
+                    selected: selected == Some(index),
+                    thumbnail,
+                }
+            })
+            .collect())
+    })
+    .await
+    .map_err(|e| format!("Task join error: {}", e))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn window_area_is_clipped_to_display() {
+        // fully inside a display at (1920, 0)
+        assert_eq!(
+            window_area_on_display((2000.0, 100.0, 800.0, 600.0), (1920, 0, 1920, 1080)),
+            Some((80, 100, 800, 600))
+        );
+        // maximized window with negative borders (Windows reports -8,-8)
+        assert_eq!(
+            window_area_on_display((-8.0, -8.0, 1936.0, 1056.0), (0, 0, 1920, 1080)),
+            Some((0, 0, 1920, 1048))
+        );
+        // tooltip-sized or off-screen
+        assert_eq!(window_area_on_display((10.0, 10.0, 40.0, 20.0), (0, 0, 1920, 1080)), None);
+        assert_eq!(window_area_on_display((5000.0, 0.0, 800.0, 600.0), (0, 0, 1920, 1080)), None);
+    }
+
+    #[test]
+    fn monitor_falls_back_to_first_display() {
+        assert_eq!(pick_monitor(-1, 2), Some(0));
+        assert_eq!(pick_monitor(1, 2), Some(1));
+        assert_eq!(pick_monitor(3, 2), Some(0), "unplugged monitor");
+        assert_eq!(pick_monitor(0, 0), None);
+    }
+
+    #[test]
+    fn disabled_capture_never_grabs() {
+        set_screen_capture_enabled(false);
+        let res = grab_frame();
+        set_screen_capture_enabled(true);
+        assert_eq!(res.err().as_deref(), Some(CAPTURE_DISABLED_ERR));
+    }
+
+    #[test]
+    fn preprocessing_is_off_by_default_and_composable() {
+        let img = image::DynamicImage::ImageRgba8(image::RgbaImage::from_pixel(
+            4,
+            4,
+            image::Rgba([200, 40, 40, 255]),
+        ));
+        assert_eq!(apply_preprocessing(img.clone(), Preprocessing::default()), img);
+        let all = apply_preprocessing(
+            img,
+            Preprocessing { grayscale: true, contrast: 30.0, sharpen: true },
+        );
+        assert!(matches!(all, image::DynamicImage::ImageLuma8(_)));
+        assert!(validate_image_contrast(150.0).is_err());
+    }
+
+    #[test]
+    fn oversized_frames_shrink_to_half_size_jpeg() {
+        let img = image::DynamicImage::ImageRgba8(image::RgbaImage::from_pixel(
+            200,
+            100,
+            image::Rgba([10, 120, 200, 255]),
+        ));
+        let mut png = Vec::new();
+        img.write_to(&mut std::io::Cursor::new(&mut png), image::ImageFormat::Png)
+            .unwrap();
+        let b64 = BASE64.encode(&png);
+        assert_eq!(image_mime(&b64), "image/png");
+        let (small, w, h) = shrink_base64_image(&b64).unwrap();
+        assert_eq!((w, h), (100, 50));
+        assert_eq!(image_mime(&small), "image/jpeg");
+        assert!(shrink_base64_image("not base64!").is_err());
+        assert!(validate_max_image_bytes(MAX_IMAGE_BYTES_MIN - 1).is_err());
+        assert_eq!(validate_max_image_bytes(MAX_IMAGE_BYTES_DEFAULT), Ok(MAX_IMAGE_BYTES_DEFAULT));
+    }
+
+    #[test]
+    fn capture_target_validation() {
+        assert_eq!(normalize_capture_target("Active-Window").unwrap(), CAPTURE_TARGET_ACTIVE_WINDOW);
+        assert_eq!(normalize_capture_target("screen").unwrap(), CAPTURE_TARGET_SCREEN);
+        assert!(normalize_capture_target("monitor").is_err());
+    }
+}
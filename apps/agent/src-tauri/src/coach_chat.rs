@@ -1,4 +1,3 @@
-use reqwest::blocking::Client;
 use rusqlite::{params, Connection};
 use serde::{Deserialize, Serialize};
 use std::time::Duration;
@@ -54,12 +53,8 @@ fn save_messages(conn: &Connection, messages: &[CoachChatMessage]) -> Result<(),
     Ok(())
 }
 
-fn coach_http_client() -> Result<Client, String> {
-    Client::builder()
-        .timeout(Duration::from_secs(120))
-        .build()
-        .map_err(|e| e.to_string())
-}
+/// Coach replies run a cloud LLM; give them longer than the default.
+const COACH_REQUEST_TIMEOUT: Duration = Duration::from_secs(120);
 
 #[tauri::command]
 pub fn get_coach_chat_messages() -> Result<Vec<CoachChatMessage>, String> {
@@ -92,7 +87,7 @@ pub fn get_coach_chat_usage() -> Result<serde_json::Value, String> {
         .or_else(|| entitlements.active_team_id.clone())
         .unwrap_or_default();
 
-    let client = coach_http_client()?;
+    let client = crate::http_client::cloud_client();
     let url = format!(
         "{}/functions/v1/coach-chat?teamId={}",
         supabase_url(),
@@ -101,6 +96,7 @@ pub fn get_coach_chat_usage() -> Result<serde_json::Value, String> {
 
     let resp = client
         .get(&url)
+        .timeout(COACH_REQUEST_TIMEOUT)
         .header("apikey", supabase_anon_key())
         .header("Authorization", format!("Bearer {}", session.access_token))
         .send()
@@ -178,10 +174,11 @@ pub fn send_coach_chat_message(message: String) -> Result<serde_json::Value, Str
         "local_context": local_context,
     });
 
-    let client = coach_http_client()?;
+    let client = crate::http_client::cloud_client();
     let url = format!("{}/functions/v1/coach-chat", supabase_url());
     let resp = client
         .post(&url)
+        .timeout(COACH_REQUEST_TIMEOUT)
         .header("apikey", supabase_anon_key())
         .header("Authorization", format!("Bearer {}", session.access_token))
         .header("Content-Type", "application/json")
@@ -1,6 +1,5 @@
 use crate::sync::get_user_session_from_conn;
 use crate::sync_env::{supabase_anon_key, supabase_url};
-use reqwest::blocking::Client;
 use rusqlite::Connection;
 use serde::{Deserialize, Serialize};
 
@@ -87,7 +86,7 @@ pub fn clear_entitlements(conn: &Connection) -> Result<(), String> {
 }
 
 pub fn refresh_entitlements_from_supabase(access_token: &str) -> Result<Entitlements, String> {
-    let client = Client::new();
+    let client = crate::http_client::cloud_client();
     let url = format!("{}/rest/v1/rpc/get_user_entitlements", supabase_url());
 
     let resp = client
@@ -109,6 +108,7 @@ pub fn refresh_entitlements_from_supabase(access_token: &str) -> Result<Entitlem
 }
 
 pub fn require_feature(db_path: &std::path::Path, feature: &str) -> Result<(), String> {
+    crate::http_client::ensure_network_allowed()?;
     let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
     let entitlements = load_entitlements(&conn);
     let allowed = match feature {
@@ -144,6 +144,7 @@ pub fn save_entitlements_command(entitlements: Entitlements) -> Result<(), Strin
 
 #[tauri::command]
 pub fn refresh_entitlements() -> Result<Entitlements, String> {
+    crate::http_client::ensure_network_allowed()?;
     let db_path = crate::paths::db_path()?;
     let conn = Connection::open(&db_path).map_err(|e| e.to_string())?;
 
@@ -177,7 +178,7 @@ pub fn fetch_cloud_insights(limit: Option<u32>) -> Result<Vec<serde_json::Value>
         team_filter
     );
 
-    let client = Client::new();
+    let client = crate::http_client::cloud_client();
     let resp = client
         .get(&url)
         .header("apikey", supabase_anon_key())
@@ -217,7 +218,7 @@ pub fn request_cloud_insights(period_days: Option<i32>, team_id: Option<String>)
         body["local_report"] = local_report;
     }
 
-    let client = Client::new();
+    let client = crate::http_client::cloud_client();
     let url = format!("{}/functions/v1/generate-insights", supabase_url());
     let resp = client
         .post(&url)
@@ -0,0 +1,102 @@
+//! Typed errors for Tauri commands.
+//!
+//! Commands used to return `Result<_, String>`, so the renderer could not tell "sign in again"
+//! apart from a flaky network or a locked database. `AgentError` serializes as
+//! `{ "kind": "NotRegistered", "message": "..." }`; the renderer's `invoke` wrapper turns that
+//! into an `Error` whose `message` keeps the old text and whose `kind` can be branched on.
+//!
+//! Internal helpers still return `String` errors; `?` maps them to `Other`.
+
+use serde::ser::SerializeStruct;
+use serde::{Serialize, Serializer};
+
+#[derive(Debug, thiserror::Error)]
+pub enum AgentError {
+    /// `AgentState` is still `None` (startup not finished).
+    #[error("Agent not initialized — wait for startup to finish.")]
+    NotInitialized,
+    #[error("Database error: {0}")]
+    Database(String),
+    #[error("Network error: {0}")]
+    Network(String),
+    /// The managed local llama-server (the vision backend; historically called "ollama").
+    #[error("Local model server error: {0}")]
+    LlamaServer(String),
+    /// Invalid setting / bad argument from the renderer.
+    #[error("{0}")]
+    Config(String),
+    /// No cloud session: the renderer should prompt for sign-in / re-registration.
+    #[error("Not logged in. Please sign in first.")]
+    NotRegistered,
+    /// The same operation is already running (e.g. a sync); retry once it finishes.
+    #[error("{0}")]
+    Busy(String),
+    #[error("{0}")]
+    Other(String),
+}
+
+impl AgentError {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            AgentError::NotInitialized => "NotInitialized",
+            AgentError::Database(_) => "Database",
+            AgentError::Network(_) => "Network",
+            AgentError::LlamaServer(_) => "LlamaServer",
+            AgentError::Config(_) => "Config",
+            AgentError::NotRegistered => "NotRegistered",
+            AgentError::Busy(_) => "Busy",
+            AgentError::Other(_) => "Other",
+        }
+    }
+}
+
+impl Serialize for AgentError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut s = serializer.serialize_struct("AgentError", 2)?;
+        s.serialize_field("kind", self.kind())?;
+        s.serialize_field("message", &self.to_string())?;
+        s.end()
+    }
+}
+
+impl From<String> for AgentError {
+    fn from(e: String) -> Self {
+        AgentError::Other(e)
+    }
+}
+
+impl From<&str> for AgentError {
+    fn from(e: &str) -> Self {
+        AgentError::Other(e.to_string())
+    }
+}
+
+impl From<rusqlite::Error> for AgentError {
+    fn from(e: rusqlite::Error) -> Self {
+        AgentError::Database(e.to_string())
+    }
+}
+
+impl From<reqwest::Error> for AgentError {
+    fn from(e: reqwest::Error) -> Self {
+        AgentError::Network(e.to_string())
+    }
+}
+
+pub type AgentResult<T> = Result<T, AgentError>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn serializes_kind_and_message() {
+        let v = serde_json::to_value(AgentError::NotRegistered).unwrap();
+        assert_eq!(v["kind"], "NotRegistered");
+        assert_eq!(v["message"], "Not logged in. Please sign in first.");
+        let v = serde_json::to_value(AgentError::from("boom".to_string())).unwrap();
+        assert_eq!(v, serde_json::json!({"kind": "Other", "message": "boom"}));
+        let v = serde_json::to_value(AgentError::Busy("A sync is already in progress".into())).unwrap();
+        assert_eq!(v["kind"], "Busy");
+    }
+}
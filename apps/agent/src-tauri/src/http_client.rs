@@ -0,0 +1,353 @@
+//! reqwest client builders shared by every HTTP caller.
+//!
+//! Cloud traffic (Supabase, Jira, Linear, coach) goes through the proxy configured in settings
+//! (`httpProxy` / `httpsProxy`); when none is set reqwest falls back to the standard
+//! `HTTP_PROXY` / `HTTPS_PROXY` / `NO_PROXY` env vars. Setting only one of the two keeps the
+//! env var for the other scheme (see `effective_proxies`). The embedded llama-server on
+//! 127.0.0.1 never goes through a proxy — corporate proxies reject or hang on loopback CONNECTs.
+//!
+//! Clients are built once and shared (`cloud_client`, `local_client`) so backlog syncs and
+//! back-to-back vision calls reuse pooled connections; callers set timeouts per request.
+//! The cloud client is rebuilt when the proxy settings change.
+
+use reqwest::blocking::{Client, ClientBuilder};
+use reqwest::{NoProxy, Proxy};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, OnceLock};
+
+/// Hosts that bypass an explicitly configured proxy.
+const NO_PROXY_HOSTS: &str = "localhost,127.0.0.1,::1";
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(crate) struct ProxySettings {
+    pub http: Option<String>,
+    pub https: Option<String>,
+}
+
+static PROXY_SETTINGS: Mutex<ProxySettings> = Mutex::new(ProxySettings {
+    http: None,
+    https: None,
+});
+
+/// Called by the agent after loading / updating config.
+pub(crate) fn set_proxy_settings(settings: ProxySettings) {
+    if let Ok(mut g) = PROXY_SETTINGS.lock() {
+        *g = settings;
+    }
+}
+
+pub(crate) fn proxy_settings() -> ProxySettings {
+    PROXY_SETTINGS.lock().map(|g| g.clone()).unwrap_or_default()
+}
+
+/// Trims the value; blank means "no proxy". Errors if reqwest cannot parse it as a proxy URL.
+pub(crate) fn normalize_proxy_url(raw: Option<&str>) -> Result<Option<String>, String> {
+    let Some(v) = raw.map(str::trim).filter(|v| !v.is_empty()) else {
+        return Ok(None);
+    };
+    let with_scheme = if v.contains("://") {
+        v.to_string()
+    } else {
+        format!("http://{}", v)
+    };
+    Proxy::all(&with_scheme).map_err(|e| format!("Invalid proxy URL '{}': {}", v, e))?;
+    Ok(Some(with_scheme))
+}
+
+/// Per-scheme proxy URLs plus the no-proxy list once settings and env vars are merged.
+#[derive(Debug, PartialEq)]
+pub(crate) struct EffectiveProxies {
+    pub http: Option<String>,
+    pub https: Option<String>,
+    pub no_proxy: String,
+}
+
+/// Any explicit proxy turns off reqwest's own env detection, so the env vars are merged here:
+/// each scheme uses its setting, else its env var; HTTPS (all Supabase traffic) falls back to
+/// `httpProxy` when neither is set. `NO_PROXY` is added to the loopback hosts.
+pub(crate) fn effective_proxies(
+    settings: &ProxySettings,
+    env: impl Fn(&str) -> Option<String>,
+) -> EffectiveProxies {
+    let env_var = |upper: &str| {
+        env(upper)
+            .or_else(|| env(&upper.to_lowercase()))
+            .filter(|v| !v.trim().is_empty())
+    };
+    let http = settings.http.clone().or_else(|| env_var("HTTP_PROXY"));
+    let https = settings
+        .https
+        .clone()
+        .or_else(|| env_var("HTTPS_PROXY"))
+        .or_else(|| settings.http.clone());
+    let no_proxy = match env_var("NO_PROXY") {
+        Some(extra) => format!("{},{}", NO_PROXY_HOSTS, extra.trim()),
+        None => NO_PROXY_HOSTS.to_string(),
+    };
+    EffectiveProxies { http, https, no_proxy }
+}
+
+fn parsed_proxy(scheme: &str, url: &str, build: fn(&str) -> reqwest::Result<Proxy>) -> Option<Proxy> {
+    build(url)
+        .map_err(|e| log::warn!("[HTTP] Ignoring invalid {} proxy '{}': {}", scheme, url, e))
+        .ok()
+}
+
+fn apply_proxy(mut builder: ClientBuilder, settings: &ProxySettings) -> ClientBuilder {
+    if settings.http.is_none() && settings.https.is_none() {
+        return builder; // reqwest reads the env vars itself
+    }
+    let eff = effective_proxies(settings, |k| std::env::var(k).ok());
+    if let Some(p) = eff.http.as_deref().and_then(|u| parsed_proxy("HTTP", u, |u| Proxy::http(u))) {
+        builder = builder.proxy(p.no_proxy(NoProxy::from_string(&eff.no_proxy)));
+    }
+    if let Some(p) = eff.https.as_deref().and_then(|u| parsed_proxy("HTTPS", u, |u| Proxy::https(u))) {
+        builder = builder.proxy(p.no_proxy(NoProxy::from_string(&eff.no_proxy)));
+    }
+    builder
+}
+
+/// `localOnly`: no registration, sync, token refresh, integrations or coach; only the local
+/// vision server is contacted. Checked at every cloud entry point via `ensure_network_allowed`.
+static LOCAL_ONLY: AtomicBool = AtomicBool::new(false);
+
+pub(crate) const LOCAL_ONLY_ERR: &str = "Disabled in local-only mode (turn off Local only in Settings to use cloud features).";
+
+pub(crate) fn set_local_only(on: bool) {
+    LOCAL_ONLY.store(on, Ordering::Relaxed);
+}
+
+pub(crate) fn local_only() -> bool {
+    LOCAL_ONLY.load(Ordering::Relaxed)
+}
+
+pub(crate) fn ensure_network_allowed() -> Result<(), String> {
+    if local_only() {
+        Err(LOCAL_ONLY_ERR.to_string())
+    } else {
+        Ok(())
+    }
+}
+
+/// Builder for cloud endpoints (proxy-aware). Add timeouts etc. before `.build()`.
+pub(crate) fn cloud_builder() -> ClientBuilder {
+    apply_proxy(Client::builder(), &proxy_settings())
+}
+
+/// Returns the client cached under `key`, building (and replacing) it when the key changed.
+/// `Client` is an `Arc` handle, so the clone shares the connection pool.
+pub(crate) fn cached_client<K: Clone + PartialEq>(
+    slot: &Mutex<Option<(K, Client)>>,
+    key: &K,
+    build: impl FnOnce() -> Result<Client, String>,
+) -> Result<Client, String> {
+    let mut guard = slot.lock().map_err(|_| "HTTP client cache poisoned".to_string())?;
+    if let Some((k, client)) = guard.as_ref() {
+        if k == key {
+            return Ok(client.clone());
+        }
+    }
+    let client = build()?;
+    *guard = Some((key.clone(), client.clone()));
+    Ok(client)
+}
+
+static CLOUD_CLIENT: Mutex<Option<(ProxySettings, Client)>> = Mutex::new(None);
+
+/// Shared proxy-aware client for cloud calls (no client-level timeout).
+pub(crate) fn cloud_client() -> Client {
+    let settings = proxy_settings();
+    cached_client(&CLOUD_CLIENT, &settings, || {
+        apply_proxy(Client::builder(), &settings)
+            .build()
+            .map_err(|e| e.to_string())
+    })
+    .unwrap_or_else(|e| {
+        log::warn!("[Http] proxy-aware client build failed ({}); using defaults", e);
+        Client::new()
+    })
+}
+
+/// Builder for the managed llama-server (loopback, never proxied).
+pub(crate) fn local_builder() -> ClientBuilder {
+    Client::builder().no_proxy()
+}
+
+static LOCAL_CLIENT: OnceLock<Client> = OnceLock::new();
+
+/// Shared loopback client for the managed llama-server; set `.timeout()` on each request.
+pub(crate) fn local_client() -> Client {
+    LOCAL_CLIENT
+        .get_or_init(|| local_builder().build().unwrap_or_else(|_| Client::new()))
+        .clone()
+}
+
+/// Max chars of a response body quoted in error messages.
+const BODY_SNIPPET_MAX_CHARS: usize = 300;
+
+/// Whitespace-collapsed, truncated body for error messages (HTML error pages are long).
+pub(crate) fn body_snippet(body: &str) -> String {
+    let collapsed = body.split_whitespace().collect::<Vec<_>>().join(" ");
+    if collapsed.chars().count() <= BODY_SNIPPET_MAX_CHARS {
+        return collapsed;
+    }
+    let mut out: String = collapsed.chars().take(BODY_SNIPPET_MAX_CHARS).collect();
+    out.push('…');
+    out
+}
+
+/// Reads the body as text first, then parses JSON. Non-2xx or non-JSON bodies (a proxy's 502
+/// page, a server on the wrong port) become errors with the HTTP status and a body snippet
+/// instead of an opaque "error decoding response body".
+pub(crate) fn read_json(resp: reqwest::blocking::Response, what: &str) -> Result<serde_json::Value, String> {
+    let status = resp.status();
+    let body = resp
+        .text()
+        .map_err(|e| format!("{} ({}): could not read response body: {}", what, status, e))?;
+    if !status.is_success() {
+        return Err(format!("{} failed ({}): {}", what, status, body_snippet(&body)));
+    }
+    serde_json::from_str(&body).map_err(|e| {
+        format!(
+            "{} returned a non-JSON response ({}, {}): {}",
+            what,
+            status,
+            e,
+            body_snippet(&body)
+        )
+    })
+}
+
+/// Cloud troubleshooting in three steps, each `{ ok, message }`: `reachable` (any HTTP answer
+/// through the current proxy settings), `authenticated` (stored session accepted) and
+/// `registered` (developer profile exists). Later steps are skipped when an earlier one fails;
+/// `ok` is true only when all three pass.
+#[tauri::command]
+pub fn test_cloud_connection() -> Result<serde_json::Value, String> {
+    ensure_network_allowed()?;
+    let settings = proxy_settings();
+    let client = cloud_builder()
+        .timeout(std::time::Duration::from_secs(10))
+        .build()
+        .map_err(|e| e.to_string())?;
+    let url = format!("{}/auth/v1/health", crate::sync_env::supabase_url());
+    let proxied = settings.http.is_some() || settings.https.is_some();
+    let skipped = serde_json::json!({ "ok": false, "message": "Not checked (server unreachable)." });
+    let (reachable, status) = match client
+        .get(&url)
+        .header("apikey", crate::sync_env::supabase_anon_key())
+        .send()
+    {
+        Ok(r) => (
+            serde_json::json!({
+                "ok": true,
+                "message": format!("Server answered (HTTP {}).", r.status().as_u16()),
+            }),
+            Some(r.status().as_u16()),
+        ),
+        Err(e) => (
+            serde_json::json!({
+                "ok": false,
+                "message": if proxied {
+                    format!("Cannot reach the server through the configured proxy: {}", e)
+                } else {
+                    format!("Cannot reach the server: {}", e)
+                },
+            }),
+            None,
+        ),
+    };
+    let (authenticated, registered) = if reachable["ok"] == true {
+        crate::sync::cloud_session_checks(&client)
+    } else {
+        (skipped.clone(), skipped)
+    };
+    Ok(serde_json::json!({
+        "ok": reachable["ok"] == true && authenticated["ok"] == true && registered["ok"] == true,
+        "reachable": reachable,
+        "authenticated": authenticated,
+        "registered": registered,
+        "status": status,
+        "viaConfiguredProxy": proxied,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalize_blank_and_scheme() {
+        assert_eq!(normalize_proxy_url(None).unwrap(), None);
+        assert_eq!(normalize_proxy_url(Some("   ")).unwrap(), None);
+        assert_eq!(
+            normalize_proxy_url(Some(" proxy.corp:8080 ")).unwrap().as_deref(),
+            Some("http://proxy.corp:8080")
+        );
+        assert_eq!(
+            normalize_proxy_url(Some("https://proxy.corp:3128")).unwrap().as_deref(),
+            Some("https://proxy.corp:3128")
+        );
+    }
+
+    #[test]
+    fn single_setting_keeps_env_for_other_scheme() {
+        let env = |k: &str| match k {
+            "HTTPS_PROXY" => Some("http://env-https:3128".to_string()),
+            "no_proxy" => Some("intranet.corp".to_string()),
+            _ => None,
+        };
+        let only_http = ProxySettings { http: Some("http://corp:8080".into()), https: None };
+        let eff = effective_proxies(&only_http, env);
+        assert_eq!(eff.http.as_deref(), Some("http://corp:8080"));
+        assert_eq!(eff.https.as_deref(), Some("http://env-https:3128"));
+        assert_eq!(eff.no_proxy, "localhost,127.0.0.1,::1,intranet.corp");
+
+        // no HTTPS env var: Supabase traffic goes through httpProxy instead of direct
+        let eff = effective_proxies(&only_http, |_| None);
+        assert_eq!(eff.https.as_deref(), Some("http://corp:8080"));
+        assert_eq!(eff.no_proxy, NO_PROXY_HOSTS);
+    }
+
+    #[test]
+    fn body_snippet_collapses_and_truncates() {
+        assert_eq!(body_snippet("<html>\n  <h1>502 Bad Gateway</h1>\n</html>"), "<html> <h1>502 Bad Gateway</h1> </html>");
+        let long = "x".repeat(1000);
+        let snip = body_snippet(&long);
+        assert_eq!(snip.chars().count(), BODY_SNIPPET_MAX_CHARS + 1);
+        assert!(snip.ends_with('…'));
+    }
+
+    #[test]
+    fn local_only_blocks_cloud_entry_points() {
+        set_local_only(true);
+        assert_eq!(ensure_network_allowed(), Err(LOCAL_ONLY_ERR.to_string()));
+        // refused before the (nonexistent) DB is even opened
+        let missing = std::path::Path::new("/nonexistent/flowsight.db");
+        assert_eq!(
+            crate::entitlements::require_feature(missing, "sync"),
+            Err(LOCAL_ONLY_ERR.to_string())
+        );
+        set_local_only(false);
+        assert!(ensure_network_allowed().is_ok());
+    }
+
+    #[test]
+    fn cached_client_rebuilds_only_when_key_changes() {
+        let slot: Mutex<Option<(u32, Client)>> = Mutex::new(None);
+        let mut builds = 0;
+        for key in [1, 1, 2, 2, 1] {
+            cached_client(&slot, &key, || {
+                builds += 1;
+                local_builder().build().map_err(|e| e.to_string())
+            })
+            .unwrap();
+        }
+        assert_eq!(builds, 3);
+    }
+
+    #[test]
+    fn normalize_rejects_garbage() {
+        assert!(normalize_proxy_url(Some("http://exa mple:80")).is_err());
+    }
+}
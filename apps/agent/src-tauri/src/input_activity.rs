@@ -0,0 +1,226 @@
+//! Keyboard/mouse *intensity* between captures (typing vs. mostly reading).
+//!
+//! PRIVACY: the global hook only increments two counters (key presses, mouse clicks). Key codes,
+//! characters, pointer positions and target windows are never read, stored or uploaded.
+//! Opt-in via the `inputActivityTracking` setting; when off, events are ignored at the hook.
+//!
+//! Windows and macOS install their own counting-only hooks (low-level hooks / a listen-only
+//! CGEventTap) on a background thread. rdev is not used there: on macOS it translates every key
+//! through the TIS keyboard-layout APIs inside the tap, which must run on the main thread and
+//! crash from a background one. Linux keeps rdev (X11 record, no main-thread requirement).
+
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Mutex, Once};
+use std::time::Instant;
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static KEY_PRESSES: AtomicU64 = AtomicU64::new(0);
+static CLICKS: AtomicU64 = AtomicU64::new(0);
+static WINDOW_START: Mutex<Option<Instant>> = Mutex::new(None);
+static LISTENER: Once = Once::new();
+
+/// A window shorter than this is too young to call idle (tracking was just turned on).
+const IDLE_MIN_WINDOW_SECS: u64 = 30;
+
+/// Events per minute thresholds for `activity_level`.
+const LEVEL_LOW_MAX_EPM: f64 = 20.0;
+const LEVEL_MEDIUM_MAX_EPM: f64 = 80.0;
+
+/// Turns counting on/off. The OS hook is installed once, on first enable, and stays installed;
+/// disabling just makes the callback a no-op. Re-applying the current state keeps the window.
+pub(crate) fn set_enabled(enabled: bool) {
+    if ENABLED.swap(enabled, Ordering::Relaxed) != enabled {
+        reset_window();
+    }
+    if enabled {
+        LISTENER.call_once(|| {
+            std::thread::spawn(|| {
+                if let Err(e) = run_hook() {
+                    log::warn!("[InputActivity] global input hook unavailable: {}", e);
+                }
+            });
+        });
+    }
+}
+
+fn count(counter: &AtomicU64) {
+    if ENABLED.load(Ordering::Relaxed) {
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// Low-level keyboard/mouse hooks; blocks the calling thread in its message loop.
+#[cfg(windows)]
+fn run_hook() -> Result<(), String> {
+    use windows_sys::Win32::Foundation::{LPARAM, LRESULT, WPARAM};
+    use windows_sys::Win32::UI::WindowsAndMessaging::{
+        CallNextHookEx, GetMessageW, SetWindowsHookExW, HC_ACTION, MSG, WH_KEYBOARD_LL,
+        WH_MOUSE_LL, WM_KEYDOWN, WM_LBUTTONDOWN, WM_MBUTTONDOWN, WM_RBUTTONDOWN, WM_SYSKEYDOWN,
+        WM_XBUTTONDOWN,
+    };
+
+    unsafe extern "system" fn keyboard_proc(code: i32, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
+        if code == HC_ACTION as i32 && matches!(wparam as u32, WM_KEYDOWN | WM_SYSKEYDOWN) {
+            count(&KEY_PRESSES);
+        }
+        CallNextHookEx(std::ptr::null_mut(), code, wparam, lparam)
+    }
+
+    unsafe extern "system" fn mouse_proc(code: i32, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
+        if code == HC_ACTION as i32
+            && matches!(
+                wparam as u32,
+                WM_LBUTTONDOWN | WM_RBUTTONDOWN | WM_MBUTTONDOWN | WM_XBUTTONDOWN
+            )
+        {
+            count(&CLICKS);
+        }
+        CallNextHookEx(std::ptr::null_mut(), code, wparam, lparam)
+    }
+
+    // SAFETY: both procs only touch atomics and chain to the next hook; LL hooks are called on
+    // this thread, which keeps pumping messages below for as long as the process lives.
+    unsafe {
+        let keyboard =
+            SetWindowsHookExW(WH_KEYBOARD_LL, Some(keyboard_proc), std::ptr::null_mut(), 0);
+        let mouse = SetWindowsHookExW(WH_MOUSE_LL, Some(mouse_proc), std::ptr::null_mut(), 0);
+        if keyboard.is_null() && mouse.is_null() {
+            return Err("SetWindowsHookExW failed".to_string());
+        }
+        let mut msg = MSG::default();
+        while GetMessageW(&mut msg, std::ptr::null_mut(), 0, 0) > 0 {}
+    }
+    Ok(())
+}
+
+/// Listen-only event tap on this thread's own run loop. Only the event *type* is looked at, so
+/// no keyboard-layout (TIS) calls are made off the main thread.
+/// Needs the Input Monitoring permission; without it the tap cannot be created.
+#[cfg(target_os = "macos")]
+fn run_hook() -> Result<(), String> {
+    use core_foundation::runloop::CFRunLoop;
+    use core_graphics::event::{
+        CGEventTap, CGEventTapLocation, CGEventTapOptions, CGEventTapPlacement, CGEventType,
+        CallbackResult,
+    };
+
+    CGEventTap::with_enabled(
+        CGEventTapLocation::HID,
+        CGEventTapPlacement::HeadInsertEventTap,
+        CGEventTapOptions::ListenOnly,
+        vec![
+            CGEventType::KeyDown,
+            CGEventType::LeftMouseDown,
+            CGEventType::RightMouseDown,
+            CGEventType::OtherMouseDown,
+        ],
+        |_proxy, event_type, _event| {
+            match event_type {
+                CGEventType::KeyDown => count(&KEY_PRESSES),
+                CGEventType::LeftMouseDown
+                | CGEventType::RightMouseDown
+                | CGEventType::OtherMouseDown => count(&CLICKS),
+                _ => {}
+            }
+            CallbackResult::Keep
+        },
+        CFRunLoop::run_current,
+    )
+    .map_err(|_| "CGEventTapCreate failed (Input Monitoring permission not granted?)".to_string())
+}
+
+#[cfg(target_os = "linux")]
+fn run_hook() -> Result<(), String> {
+    rdev::listen(|event| match event.event_type {
+        rdev::EventType::KeyPress(_) => count(&KEY_PRESSES),
+        rdev::EventType::ButtonPress(_) => count(&CLICKS),
+        _ => {}
+    })
+    .map_err(|e| format!("{:?}", e))
+}
+
+#[cfg(not(any(windows, target_os = "macos", target_os = "linux")))]
+fn run_hook() -> Result<(), String> {
+    Err("not supported on this platform".to_string())
+}
+
+fn reset_window() {
+    KEY_PRESSES.store(0, Ordering::Relaxed);
+    CLICKS.store(0, Ordering::Relaxed);
+    if let Ok(mut start) = WINDOW_START.lock() {
+        *start = Some(Instant::now());
+    }
+}
+
+/// Counts since the previous call, as `(key_presses, clicks, window_secs)`. `None` when disabled.
+pub(crate) fn take_window() -> Option<(u64, u64, u64)> {
+    if !ENABLED.load(Ordering::Relaxed) {
+        return None;
+    }
+    let keys = KEY_PRESSES.swap(0, Ordering::Relaxed);
+    let clicks = CLICKS.swap(0, Ordering::Relaxed);
+    let secs = WINDOW_START
+        .lock()
+        .ok()
+        .and_then(|mut start| start.replace(Instant::now()))
+        .map(|t| t.elapsed().as_secs())
+        .unwrap_or(0);
+    Some((keys, clicks, secs))
+}
+
+/// Seconds without a key press or click in the current window, when that makes the user idle.
+/// Does not start a new window: `save_activity` still drains it with `take_window`.
+/// `None` when tracking is off or there was input.
+pub(crate) fn idle_secs() -> Option<u64> {
+    if !ENABLED.load(Ordering::Relaxed) {
+        return None;
+    }
+    let secs = WINDOW_START
+        .lock()
+        .ok()
+        .and_then(|start| *start)
+        .map(|t| t.elapsed().as_secs())?;
+    let events = KEY_PRESSES.load(Ordering::Relaxed) + CLICKS.load(Ordering::Relaxed);
+    is_idle(events, secs).then_some(secs)
+}
+
+fn is_idle(events: u64, window_secs: u64) -> bool {
+    events == 0 && window_secs >= IDLE_MIN_WINDOW_SECS
+}
+
+/// `none` / `low` / `medium` / `high` from events per minute over the window.
+pub(crate) fn activity_level(events: u64, window_secs: u64) -> &'static str {
+    if events == 0 {
+        return "none";
+    }
+    let epm = events as f64 * 60.0 / window_secs.max(1) as f64;
+    if epm < LEVEL_LOW_MAX_EPM {
+        "low"
+    } else if epm < LEVEL_MEDIUM_MAX_EPM {
+        "medium"
+    } else {
+        "high"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{activity_level, is_idle};
+
+    #[test]
+    fn level_buckets_by_events_per_minute() {
+        assert_eq!(activity_level(0, 60), "none");
+        assert_eq!(activity_level(10, 60), "low");
+        assert_eq!(activity_level(50, 60), "medium");
+        assert_eq!(activity_level(300, 60), "high");
+        // same count over a longer window is less intense
+        assert_eq!(activity_level(50, 600), "low");
+    }
+
+    #[test]
+    fn idle_needs_no_input_over_a_full_window() {
+        assert!(is_idle(0, 120));
+        assert!(!is_idle(1, 120), "a single click is activity");
+        assert!(!is_idle(0, 5), "window just started");
+    }
+}
@@ -1,5 +1,4 @@
 use chrono::Local;
-use reqwest::blocking::Client;
 use rusqlite::{params, Connection};
 use serde::Serialize;
 use std::collections::HashMap;
@@ -8,7 +7,7 @@ use tauri::Emitter;
 
 use crate::vision_model::LLAMA_CHAT_MODEL_ID;
 
-const FOCUS_CATEGORIES: &[&str] = &[
+pub(crate) const FOCUS_CATEGORIES: &[&str] = &[
     "Coding", "Debugging", "CodeReview", "Testing", "Design", "DevOps", "Database",
 ];
 const DISTRACTION_CATEGORIES: &[&str] = &["Browsing", "Idle"];
@@ -475,10 +474,7 @@ fn call_local_llm_with_system(
         "Local AI server offline.".to_string()
     })?;
 
-    let client = Client::builder()
-        .timeout(Duration::from_secs(LLM_PASS_TIMEOUT_SECS))
-        .build()
-        .map_err(|e| e.to_string())?;
+    let client = crate::http_client::local_client();
 
     let body = serde_json::json!({
         "model": LLAMA_CHAT_MODEL_ID,
@@ -496,17 +492,12 @@ fn call_local_llm_with_system(
 
     let resp = client
         .post(&chat_url)
+        .timeout(Duration::from_secs(LLM_PASS_TIMEOUT_SECS))
         .json(&body)
         .send()
         .map_err(|e| e.to_string())?;
 
-    if !resp.status().is_success() {
-        let status = resp.status();
-        let err_body = resp.text().unwrap_or_default();
-        return Err(format!("Local AI request failed ({}): {}", status, err_body));
-    }
-
-    let json: serde_json::Value = resp.json().map_err(|e| e.to_string())?;
+    let json = crate::http_client::read_json(resp, "Local AI request")?;
     let raw = json["choices"][0]["message"]["content"]
         .as_str()
         .unwrap_or("")
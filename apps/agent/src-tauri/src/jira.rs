@@ -1,5 +1,4 @@
 use serde::{Deserialize, Serialize};
-use reqwest::blocking::Client;
 use std::error::Error;
 use oauth2::{
     basic::BasicClient, AuthUrl, ClientId, RedirectUrl, TokenUrl,
@@ -103,12 +102,12 @@ fn listen_for_callback() {
     let server = match Server::http("0.0.0.0:12345") {
         Ok(s) => s,
         Err(_) => {
-            println!("Jira OAuth: Port 12345 busy. Assuming existing listener will handle callback.");
+            log::warn!("Jira OAuth: Port 12345 busy. Assuming existing listener will handle callback.");
             return;
         }
     };
     
-    println!("Listening for Jira Callback on 12345...");
+    log::info!("Listening for Jira Callback on 12345...");
 
     for request in server.incoming_requests() {
         let url = format!("http://localhost:12345{}", request.url());
@@ -143,7 +142,7 @@ fn listen_for_callback() {
                      break; // Stop server
                 },
                 Err(e) => {
-                     println!("OAuth Token Exchange Failed: {:#?}", e);
+                     log::warn!("OAuth Token Exchange Failed: {:#?}", e);
                      let _ = request.respond(Response::from_string(format!("Error Exchange Failed: {:#?}", e)));
                 }
             }
@@ -155,7 +154,7 @@ fn save_tokens(access: &str, refresh: Option<&str>) {
    let db_path = match crate::paths::db_path() {
        Ok(p) => p,
        Err(e) => {
-           eprintln!("[jira] save_tokens: {}", e);
+           log::error!("[jira] save_tokens: {}", e);
            return;
        }
    };
@@ -188,7 +187,7 @@ fn refresh_access_token() -> Result<String, String> {
     let client_secret = get_client_secret();
     
     // Build the token refresh request
-    let http_client = Client::new();
+    let http_client = crate::http_client::cloud_client();
     let mut params = vec![
         ("grant_type", "refresh_token"),
         ("refresh_token", &refresh_token),
@@ -210,7 +209,7 @@ fn refresh_access_token() -> Result<String, String> {
     if !resp.status().is_success() {
         let status = resp.status();
         let body = resp.text().unwrap_or_default();
-        println!("[Jira] Token refresh failed: {} - {}", status, body);
+        log::warn!("[Jira] Token refresh failed: {} - {}", status, body);
         return Err(format!("Token refresh failed ({}). Please reconnect to Jira.", status));
     }
     
@@ -224,7 +223,7 @@ fn refresh_access_token() -> Result<String, String> {
     
     // Save the new tokens
     save_tokens(&new_access, new_refresh.as_deref());
-    println!("[Jira] Token refreshed successfully");
+    log::info!("[Jira] Token refreshed successfully");
     
     Ok(new_access)
 }
@@ -242,7 +241,7 @@ fn get_valid_token() -> Result<String, String> {
     ).map_err(|_| "Not connected to Jira".to_string())?;
     
     // Quick validation: try to access a lightweight endpoint
-    let http_client = Client::new();
+    let http_client = crate::http_client::cloud_client();
     let test_resp = http_client.get("https://api.atlassian.com/oauth/token/accessible-resources")
         .bearer_auth(&access_token)
         .send();
@@ -250,7 +249,7 @@ fn get_valid_token() -> Result<String, String> {
     match test_resp {
         Ok(resp) if resp.status().as_u16() == 401 => {
             // Token expired, try to refresh
-            println!("[Jira] Access token expired, attempting refresh...");
+            log::info!("[Jira] Access token expired, attempting refresh...");
             refresh_access_token()
         }
         Ok(resp) if resp.status().is_success() => {
@@ -263,14 +262,14 @@ fn get_valid_token() -> Result<String, String> {
         }
         Err(e) => {
             // Network error, return current token and let caller handle it
-            println!("[Jira] Network check failed: {}, using cached token", e);
+            log::warn!("[Jira] Network check failed: {}, using cached token", e);
             Ok(access_token)
         }
     }
 }
 
 fn fetch_cloud_id(token: &str) -> Result<String, Box<dyn Error>> {
-    let client = Client::new();
+    let client = crate::http_client::cloud_client();
     let resp = client.get("https://api.atlassian.com/oauth/token/accessible-resources")
         .bearer_auth(token)
         .send()?;
@@ -294,7 +293,7 @@ pub fn fetch_jira_tasks() -> Result<Vec<JiraIssue>, String> {
         .map_err(|_| "Jira Cloud ID not found".to_string())?;
 
     // 2. Fetch Issues
-    let client = Client::new();
+    let client = crate::http_client::cloud_client();
     let url = format!("https://api.atlassian.com/ex/jira/{}/rest/api/3/search/jql", cloud_id);
     let jql = "statusCategory != Done ORDER BY updated DESC";
     
@@ -309,11 +308,11 @@ pub fn fetch_jira_tasks() -> Result<Vec<JiraIssue>, String> {
         .send()
         .map_err(|e| e.to_string())?;
         
-    println!("[Jira] Fetch Status: {}", resp.status());
+    log::debug!("[Jira] Fetch Status: {}", resp.status());
 
     // Handle 401 with retry after refresh
     if resp.status().as_u16() == 401 {
-        println!("[Jira] Got 401, attempting token refresh...");
+        log::warn!("[Jira] Got 401, attempting token refresh...");
         let new_token = refresh_access_token()?;
         
         // Retry with new token
@@ -340,7 +339,7 @@ pub fn fetch_jira_tasks() -> Result<Vec<JiraIssue>, String> {
 }
 
 fn parse_jira_issues(text_resp: String) -> Result<Vec<JiraIssue>, String> {
-    println!("[Jira] Raw Response: {}", text_resp);
+    log::debug!("[Jira] Raw Response: {}", text_resp);
     let json: serde_json::Value = serde_json::from_str(&text_resp).map_err(|e| e.to_string())?;
     
     let mut issues = Vec::new();
@@ -374,7 +373,7 @@ pub fn fetch_jira_profile() -> Result<JiraUser, String> {
         .map_err(|_| "Jira Cloud ID not found".to_string())?;
         
     // 2. Call /myself
-    let client = Client::new();
+    let client = crate::http_client::cloud_client();
     let url = format!("https://api.atlassian.com/ex/jira/{}/rest/api/3/myself", cloud_id);
     
     let resp = client.get(&url)
@@ -384,7 +383,7 @@ pub fn fetch_jira_profile() -> Result<JiraUser, String> {
 
     // Handle 401 with retry after refresh
     if resp.status().as_u16() == 401 {
-        println!("[Jira] Profile fetch got 401, refreshing token...");
+        log::warn!("[Jira] Profile fetch got 401, refreshing token...");
         let new_token = refresh_access_token()?;
         
         let retry_resp = client.get(&url)
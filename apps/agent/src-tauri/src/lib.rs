@@ -8,6 +8,7 @@ mod jira;
 mod sync_env;
 mod sync_pure;
 mod sync;
+mod error;
 mod auth;
 mod linear;
 mod oauth_env;
@@ -15,16 +16,30 @@ mod entitlements;
 mod insights_local;
 mod coach_chat;
 mod user_preferences;
+mod retention;
+mod http_client;
+mod blockers;
+mod input_activity;
+mod screen_change;
+mod capture_target;
+mod power;
+mod analysis_backend;
+mod report_export;
+mod meetings;
 pub mod context;
 pub mod paths;
 
 use tauri::Manager;
 
 use agent::{
-    AgentState, initialize_agent, get_config, update_config,
+    AgentState, initialize_agent, get_config, update_config, reset_config_value,
     get_status, start_monitoring, stop_monitoring,
-    capture_screen_command, save_activity,
-    get_activity_log, get_today_history, get_week_summary,
+    capture_screen_command, factory_reset, record_sleep_gap, save_activity, set_activity_type,
+    get_activity_log, get_today_history, get_history_range, get_activity_timeline,
+    get_week_summary,
+    get_activity_sparkline, get_activity_heatmap, capture_preview, estimate_capture_cost,
+    get_capture_schedule,
+    classify_description, get_category_display, set_category_display,
     check_ollama, check_local_server,
     llama_managed_process_status, llama_server_log_tail, restart_llama_server_cpu_only,
 };
@@ -37,11 +52,15 @@ pub fn run() {
             initialize_agent,
             get_config,
             update_config,
+            reset_config_value,
             get_status,
             start_monitoring,
             stop_monitoring,
     capture_screen_command,
+    factory_reset,
+    record_sleep_gap,
     save_activity,
+    set_activity_type,
     get_activity_log,
     check_ollama,
     check_local_server,
@@ -50,10 +69,12 @@ pub fn run() {
             jira::start_jira_oauth,
             jira::fetch_jira_profile,
             sync::force_sync_now,
+            sync::resync_all,
             sync::save_user_session,
             sync::clear_user_session,
             sync::get_current_user,
             sync::upload_activity_report,
+            sync::update_display_name,
             sync::join_team,
             sync::get_user_teams,
             sync::set_active_team,
@@ -69,6 +90,18 @@ pub fn run() {
             insights_local::generate_local_status_report,
             user_preferences::get_user_preferences,
             user_preferences::save_user_preferences_command,
+            retention::clear_old_reports,
+            retention::get_retention_status,
+            retention::get_storage_info,
+            http_client::test_cloud_connection,
+            blockers::get_blockers,
+            blockers::resolve_blocker,
+            screen_change::check_screen_changed,
+            capture_target::list_screens,
+            report_export::export_timeline_html,
+            report_export::export_reports_csv,
+            report_export::cancel_csv_export,
+            meetings::get_meeting_time,
             agent::start_server,
             agent::stop_server,
             llama_managed_process_status,
@@ -85,7 +118,17 @@ pub fn run() {
             linear::fetch_linear_profile,
             // History commands
             get_today_history,
+            get_history_range,
+            get_activity_timeline,
             get_week_summary,
+            get_activity_sparkline,
+            get_activity_heatmap,
+            get_capture_schedule,
+            classify_description,
+            get_category_display,
+            set_category_display,
+            capture_preview,
+            estimate_capture_cost,
             paths::get_flowsight_user_paths,
             paths::save_pdf_to_downloads,
             paths::open_path_in_file_manager,
@@ -107,9 +150,10 @@ pub fn run() {
       // así que sin esto no hay forma de diagnosticar crashes post-login.
       // Los archivos quedan en %LOCALAPPDATA%\ai.flowsight.agent\logs\ (Windows)
       // o equivalente del OS según tauri-plugin-log.
+      // FLOWSIGHT_LOG_LEVEL (error|warn|info|debug|trace) sube/baja el nivel; default info.
       app.handle().plugin(
         tauri_plugin_log::Builder::default()
-          .level(log::LevelFilter::Info)
+          .level(log_level_from_env(std::env::var(LOG_LEVEL_ENV).ok().as_deref()))
           .targets([
             tauri_plugin_log::Target::new(tauri_plugin_log::TargetKind::Stdout),
             tauri_plugin_log::Target::new(tauri_plugin_log::TargetKind::LogDir { file_name: None }),
@@ -118,6 +162,54 @@ pub fn run() {
       )?;
       Ok(())
     })
-    .run(tauri::generate_context!())
-    .expect("error while running tauri application");
+    .build(tauri::generate_context!())
+    .expect("error while building tauri application")
+    .run(|app, event| {
+      if let tauri::RunEvent::Exit = event {
+        shutdown(app);
+      }
+    });
+}
+
+/// How long quitting waits for an in-flight cloud upload before giving up.
+const SHUTDOWN_SYNC_WAIT_SECS: u64 = 5;
+
+const LOG_LEVEL_ENV: &str = "FLOWSIGHT_LOG_LEVEL";
+
+/// Unset or unparsable => `Info`.
+fn log_level_from_env(raw: Option<&str>) -> log::LevelFilter {
+  raw
+    .and_then(|v| v.trim().parse().ok())
+    .unwrap_or(log::LevelFilter::Info)
+}
+
+/// Stops monitoring and lets a running sync finish marking its rows, so the next launch does
+/// not upload the same batch again.
+fn shutdown(app: &tauri::AppHandle) {
+  if let Some(state) = app.try_state::<AgentState>() {
+    if let Ok(mut guard) = state.lock() {
+      if let Some(agent) = guard.as_mut() {
+        agent.is_running = false;
+      }
+    }
+  }
+  if !sync::wait_for_sync_idle(std::time::Duration::from_secs(SHUTDOWN_SYNC_WAIT_SECS)) {
+    log::warn!(
+      "[Shutdown] cloud sync still running after {}s; exiting anyway",
+      SHUTDOWN_SYNC_WAIT_SECS
+    );
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn log_level_env_parses_with_info_default() {
+    assert_eq!(log_level_from_env(None), log::LevelFilter::Info);
+    assert_eq!(log_level_from_env(Some(" debug ")), log::LevelFilter::Debug);
+    assert_eq!(log_level_from_env(Some("WARN")), log::LevelFilter::Warn);
+    assert_eq!(log_level_from_env(Some("loud")), log::LevelFilter::Info);
+  }
 }
@@ -1,5 +1,4 @@
 use serde::{Deserialize, Serialize};
-use reqwest::blocking::Client;
 use rusqlite::Connection;
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -11,10 +10,7 @@ pub struct LinearIssue {
 }
 
 fn get_db_conn() -> Result<Connection, String> {
-    let db_path = dirs::data_local_dir()
-        .unwrap()
-        .join("FlowSight")
-        .join("dev-agent.db");
+    let db_path = crate::paths::db_path()?;
     Connection::open(db_path).map_err(|e| e.to_string())
 }
 
@@ -47,7 +43,7 @@ pub fn fetch_linear_tasks() -> Result<Vec<LinearIssue>, String> {
     crate::entitlements::require_feature(&db_path, "integrations")?;
     let access_token = get_linear_token()?;
     
-    let client = Client::new();
+    let client = crate::http_client::cloud_client();
     
     // GraphQL query to get assigned issues
     let query = r#"{
@@ -80,7 +76,7 @@ pub fn fetch_linear_tasks() -> Result<Vec<LinearIssue>, String> {
         }
     }
     
-    println!("[Linear] Fetched {} issues", issues.len());
+    log::info!("[Linear] Fetched {} issues", issues.len());
     Ok(issues)
 }
 
@@ -98,7 +94,7 @@ pub fn fetch_linear_profile() -> Result<LinearUser, String> {
     crate::entitlements::require_feature(&db_path, "integrations")?;
     let access_token = get_linear_token()?;
     
-    let client = Client::new();
+    let client = crate::http_client::cloud_client();
     
     let query = r#"{"query": "{ viewer { id name email avatarUrl } }"}"#;
     
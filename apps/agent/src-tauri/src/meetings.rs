@@ -0,0 +1,143 @@
+//! Meeting time: contiguous runs of `Meeting` captures, so "how much of the week was meetings"
+//! has a direct answer instead of being buried in the category breakdown.
+
+use chrono::{Local, NaiveDateTime, TimeZone, Utc};
+use rusqlite::{params, Connection};
+use serde::Serialize;
+
+/// A single `Meeting` capture is often Slack/Teams open in the background; a real meeting keeps
+/// the classification for at least this many consecutive captures.
+pub(crate) const MEETING_MIN_CONSECUTIVE: usize = 2;
+/// Cap on the time credited between two captures (laptop closed mid-call, app restarted…).
+pub(crate) const MEETING_GAP_CAP_SECS: i64 = 300;
+const MEETING_MAX_DAYS: u32 = 90;
+
+#[derive(Serialize, Debug, PartialEq)]
+pub struct MeetingBlock {
+    pub start: String,
+    pub end: String,
+    pub seconds: i64,
+    pub captures: usize,
+}
+
+/// `rows` oldest first: `(created_at UTC, activity_type, duration_seconds)`.
+/// Each capture is credited with the gap to the next one (capped); the last one with its own duration.
+pub(crate) fn meeting_blocks(rows: &[(NaiveDateTime, String, i64)]) -> Vec<MeetingBlock> {
+    let mut blocks = Vec::new();
+    let mut i = 0;
+    while i < rows.len() {
+        if rows[i].1 != "Meeting" {
+            i += 1;
+            continue;
+        }
+        let start = i;
+        while i < rows.len() && rows[i].1 == "Meeting" {
+            i += 1;
+        }
+        let run = &rows[start..i];
+        if run.len() < MEETING_MIN_CONSECUTIVE {
+            continue;
+        }
+        let seconds: i64 = run
+            .iter()
+            .enumerate()
+            .map(|(k, (at, _, dur))| match rows.get(start + k + 1) {
+                Some((next, _, _)) => (*next - *at).num_seconds().clamp(0, MEETING_GAP_CAP_SECS),
+                None => (*dur).clamp(0, MEETING_GAP_CAP_SECS),
+            })
+            .sum();
+        let fmt = |t: &NaiveDateTime| {
+            Utc.from_utc_datetime(t)
+                .with_timezone(&Local)
+                .format("%Y-%m-%d %H:%M")
+                .to_string()
+        };
+        blocks.push(MeetingBlock {
+            start: fmt(&run[0].0),
+            end: fmt(&run[run.len() - 1].0),
+            seconds,
+            captures: run.len(),
+        });
+    }
+    blocks
+}
+
+/// Meeting time over the last `days` local days (including today).
+#[tauri::command]
+pub fn get_meeting_time(days: u32) -> Result<serde_json::Value, String> {
+    let days = days.clamp(1, MEETING_MAX_DAYS);
+    let db_path = crate::paths::db_path()?;
+    let conn = Connection::open(&db_path).map_err(|e| e.to_string())?;
+    let since = (Local::now().date_naive() - chrono::Duration::days(days as i64 - 1))
+        .format("%Y-%m-%d")
+        .to_string();
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT created_at, COALESCE(activity_type, ''), COALESCE(duration_seconds, 30)
+             FROM reports
+             WHERE date(created_at, 'localtime') >= ?1
+             ORDER BY datetime(created_at) ASC",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows: Vec<(NaiveDateTime, String, i64)> = stmt
+        .query_map(params![since], |r| {
+            Ok((r.get::<_, String>(0)?, r.get::<_, String>(1)?, r.get::<_, i64>(2)?))
+        })
+        .map_err(|e| e.to_string())?
+        .filter_map(|r| r.ok())
+        .filter_map(|(at, ty, dur)| {
+            NaiveDateTime::parse_from_str(&at, "%Y-%m-%d %H:%M:%S")
+                .ok()
+                .map(|t| (t, ty, dur))
+        })
+        .collect();
+
+    let blocks = meeting_blocks(&rows);
+    let total_seconds: i64 = blocks.iter().map(|b| b.seconds).sum();
+    Ok(serde_json::json!({
+        "days": days,
+        "since": since,
+        "total_seconds": total_seconds,
+        "meeting_count": blocks.len(),
+        "meetings": blocks,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn at(h: u32, m: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2026, 3, 2)
+            .unwrap()
+            .and_hms_opt(h, m, 0)
+            .unwrap()
+    }
+
+    fn row(h: u32, m: u32, ty: &str) -> (NaiveDateTime, String, i64) {
+        (at(h, m), ty.to_string(), 60)
+    }
+
+    #[test]
+    fn single_meeting_capture_is_ignored() {
+        let rows = vec![row(9, 0, "Coding"), row(9, 1, "Meeting"), row(9, 2, "Coding")];
+        assert!(meeting_blocks(&rows).is_empty());
+    }
+
+    #[test]
+    fn run_is_credited_by_gaps_with_cap() {
+        let rows = vec![
+            row(10, 0, "Meeting"),
+            row(10, 1, "Meeting"),
+            // 30 min gap (laptop closed) is capped
+            row(10, 31, "Meeting"),
+            row(10, 32, "Coding"),
+        ];
+        let blocks = meeting_blocks(&rows);
+        assert_eq!(blocks.len(), 1);
+        assert_eq!(blocks[0].captures, 3);
+        assert_eq!(blocks[0].seconds, 60 + MEETING_GAP_CAP_SECS + 60);
+    }
+}
@@ -10,8 +10,10 @@
 //! de recursos read-only bundlados con el instalador de Tauri se resuelven
 //! vía `resource_local_llm_dir` y requieren `AppHandle`.
 
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
 
+use serde::Serialize;
 use serde_json::json;
 use tauri::{AppHandle, Manager};
 
@@ -20,9 +22,22 @@ const DB_FILE: &str = "dev-agent.db";
 const SERVER_LOG_FILE: &str = "server.log";
 const AGENT_ERROR_LOG_FILE: &str = "agent_error.log";
 const CRASH_LOG_FILE: &str = "crash.log";
+const AUTH_LOG_FILE: &str = "auth.log";
 const SCREENSHOTS_TMP_DIR: &str = "screenshots_tmp";
+/// Overrides the whole data directory (DB, logs, screenshots): encrypted volume, portable
+/// installs, or isolated instances for testing. Env var because the DB cannot hold its own path.
+const DATA_DIR_ENV: &str = "FLOWSIGHT_DATA_DIR";
+
+/// `FLOWSIGHT_DATA_DIR` when set and non-blank (used as-is), else `<local data>/FlowSight`.
+fn resolve_app_data_dir(env_override: Option<std::ffi::OsString>, local_data: Option<PathBuf>) -> Option<PathBuf> {
+    match env_override.filter(|v| !v.to_string_lossy().trim().is_empty()) {
+        Some(dir) => Some(PathBuf::from(dir)),
+        None => local_data.map(|base| base.join(APP_DIR_NAME)),
+    }
+}
 
 /// Carpeta local de datos de FlowSight dentro del perfil del usuario (creada si no existe).
+/// `FLOWSIGHT_DATA_DIR` la reemplaza por completo.
 ///
 /// Se resuelve con `dirs::data_local_dir()` (Known Folders en Windows, equivalentes en otros
 /// SO): **ruta real en disco**, sin depender del idioma de la interfaz ni de variables de entorno
@@ -32,8 +47,8 @@ const SCREENSHOTS_TMP_DIR: &str = "screenshots_tmp";
 /// en release portable y en instalaciones a `Program Files` (donde el
 /// directorio de instalación NO es escribible por el usuario estándar).
 pub fn app_data_dir() -> Result<PathBuf, String> {
-    let base = dirs::data_local_dir().ok_or_else(|| "No local data dir available".to_string())?;
-    let dir = base.join(APP_DIR_NAME);
+    let dir = resolve_app_data_dir(std::env::var_os(DATA_DIR_ENV), dirs::data_local_dir())
+        .ok_or_else(|| "No local data dir available".to_string())?;
     if !dir.exists() {
         std::fs::create_dir_all(&dir).map_err(|e| format!("Failed to create {:?}: {}", dir, e))?;
     }
@@ -56,7 +71,7 @@ pub fn server_log_path() -> Result<PathBuf, String> {
 }
 
 pub fn auth_log_path() -> Result<PathBuf, String> {
-    Ok(app_data_dir()?.join("auth.log"))
+    Ok(app_data_dir()?.join(AUTH_LOG_FILE))
 }
 
 pub fn agent_error_log_path() -> Result<PathBuf, String> {
@@ -127,6 +142,72 @@ pub fn prune_screenshots_tmp_older_than(max_age: std::time::Duration) -> Result<
     Ok(removed)
 }
 
+/// What `factory_reset` deleted (file names relative to the data dir) and what it could not.
+#[derive(Serialize, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct WipeSummary {
+    pub removed: Vec<String>,
+    pub screenshots_removed: usize,
+    pub failed: Vec<String>,
+}
+
+/// Borra todo lo que FlowSight escribe en `dir`: la BD (con `-wal` / `-shm` / `-journal`),
+/// los logs y `screenshots_tmp`. Un `.env` puesto a mano por el usuario se conserva.
+/// Best effort: lo que no se pudo borrar (p. ej. un log abierto por llama-server en Windows)
+/// queda en `failed` en vez de abortar.
+pub(crate) fn wipe_user_data(dir: &Path) -> WipeSummary {
+    WIPE_IN_PROGRESS.store(true, Ordering::SeqCst);
+    let summary = remove_user_data(dir);
+    WIPE_IN_PROGRESS.store(false, Ordering::SeqCst);
+    summary
+}
+
+static WIPE_IN_PROGRESS: AtomicBool = AtomicBool::new(false);
+
+/// Background threads skip their pass while `wipe_user_data` runs, so they don't reopen
+/// (and recreate) the DB being deleted.
+pub(crate) fn wipe_in_progress() -> bool {
+    WIPE_IN_PROGRESS.load(Ordering::SeqCst)
+}
+
+fn remove_user_data(dir: &Path) -> WipeSummary {
+    let mut summary = WipeSummary::default();
+    let files = [
+        DB_FILE.to_string(),
+        format!("{}-wal", DB_FILE),
+        format!("{}-shm", DB_FILE),
+        format!("{}-journal", DB_FILE),
+        SERVER_LOG_FILE.to_string(),
+        AUTH_LOG_FILE.to_string(),
+        AGENT_ERROR_LOG_FILE.to_string(),
+        CRASH_LOG_FILE.to_string(),
+    ];
+    for name in files {
+        let path = dir.join(&name);
+        if !path.exists() {
+            continue;
+        }
+        match std::fs::remove_file(&path) {
+            Ok(()) => summary.removed.push(name),
+            Err(e) => summary.failed.push(format!("{}: {}", name, e)),
+        }
+    }
+    let shots = dir.join(SCREENSHOTS_TMP_DIR);
+    if shots.exists() {
+        summary.screenshots_removed = std::fs::read_dir(&shots)
+            .map(|entries| entries.filter_map(Result::ok).count())
+            .unwrap_or(0);
+        match std::fs::remove_dir_all(&shots) {
+            Ok(()) => summary.removed.push(SCREENSHOTS_TMP_DIR.to_string()),
+            Err(e) => {
+                summary.screenshots_removed = 0;
+                summary.failed.push(format!("{}: {}", SCREENSHOTS_TMP_DIR, e));
+            }
+        }
+    }
+    summary
+}
+
 /// Resuelve el directorio de recursos bundlados donde vive `local_llm/`.
 ///
 /// En un `.exe` instalado, Tauri descomprime los `bundle.resources` dentro
@@ -227,8 +308,46 @@ pub fn get_flowsight_user_paths() -> Result<serde_json::Value, String> {
     let dir = app_data_dir()?;
     Ok(json!({
         "appDataDir": dir.to_string_lossy(),
+        "appDataDirFromEnv": std::env::var_os(DATA_DIR_ENV).is_some_and(|v| !v.to_string_lossy().trim().is_empty()),
         "serverLog": server_log_path()?.to_string_lossy(),
         "authLog": auth_log_path()?.to_string_lossy(),
         "agentErrorLog": agent_error_log_path()?.to_string_lossy(),
     }))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn data_dir_env_overrides_default() {
+        let local = Some(PathBuf::from("/home/u/.local/share"));
+        assert_eq!(
+            resolve_app_data_dir(Some("/mnt/secure/fs".into()), local.clone()),
+            Some(PathBuf::from("/mnt/secure/fs"))
+        );
+        assert_eq!(
+            resolve_app_data_dir(Some("  ".into()), local.clone()),
+            Some(PathBuf::from("/home/u/.local/share/FlowSight"))
+        );
+        assert_eq!(resolve_app_data_dir(None, None), None);
+    }
+
+    #[test]
+    fn wipe_removes_db_logs_and_screenshots_but_keeps_env() {
+        let dir = std::env::temp_dir().join(format!("flowsight_wipe_{}", std::process::id()));
+        std::fs::create_dir_all(dir.join(SCREENSHOTS_TMP_DIR)).unwrap();
+        for name in [DB_FILE, "dev-agent.db-wal", SERVER_LOG_FILE, ".env"] {
+            std::fs::write(dir.join(name), b"x").unwrap();
+        }
+        std::fs::write(dir.join(SCREENSHOTS_TMP_DIR).join("capture_1.png"), b"x").unwrap();
+
+        let summary = wipe_user_data(&dir);
+        assert_eq!(summary.removed, [DB_FILE, "dev-agent.db-wal", SERVER_LOG_FILE, SCREENSHOTS_TMP_DIR]);
+        assert_eq!(summary.screenshots_removed, 1);
+        assert!(summary.failed.is_empty());
+        assert!(dir.join(".env").exists());
+        assert!(!dir.join(DB_FILE).exists());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,125 @@
+//! AC vs battery detection for `captureIntervalOnBattery` / `skipVisionOnBattery`: laptops
+//! unplugged capture less often (and optionally skip the vision model) to save battery.
+//!
+//! No extra crate: `GetSystemPowerStatus` on Windows, `/sys/class/power_supply` on Linux,
+//! `pmset` on macOS. Anything undetectable is `Unknown` and treated like AC.
+
+use serde::Serialize;
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum PowerSource {
+    Ac,
+    Battery,
+    Unknown,
+}
+
+/// Capture interval for the current power source (ms). `on_battery: None` => same as on AC.
+pub(crate) fn effective_capture_interval(base_ms: u64, on_battery_ms: Option<u64>, source: PowerSource) -> u64 {
+    match (source, on_battery_ms) {
+        (PowerSource::Battery, Some(ms)) => ms.max(base_ms),
+        _ => base_ms,
+    }
+}
+
+/// Linux `power_supply` entries as `(type, online, status)`: any online `Mains` wins, then a
+/// discharging `Battery`; desktops without a battery are AC.
+fn classify_supplies(supplies: &[(String, Option<String>, Option<String>)]) -> PowerSource {
+    let has_battery = supplies.iter().any(|(t, _, _)| t == "Battery");
+    if supplies
+        .iter()
+        .any(|(t, online, _)| t == "Mains" && online.as_deref() == Some("1"))
+    {
+        return PowerSource::Ac;
+    }
+    if supplies
+        .iter()
+        .any(|(t, _, status)| t == "Battery" && status.as_deref() == Some("Discharging"))
+    {
+        return PowerSource::Battery;
+    }
+    if supplies.is_empty() || !has_battery {
+        PowerSource::Ac
+    } else {
+        PowerSource::Unknown
+    }
+}
+
+#[cfg(windows)]
+pub(crate) fn power_source() -> PowerSource {
+    use windows_sys::Win32::System::Power::{GetSystemPowerStatus, SYSTEM_POWER_STATUS};
+    let mut status: SYSTEM_POWER_STATUS = unsafe { std::mem::zeroed() };
+    if unsafe { GetSystemPowerStatus(&mut status) } == 0 {
+        return PowerSource::Unknown;
+    }
+    // BatteryFlag 128 = no system battery (desktop)
+    match (status.ACLineStatus, status.BatteryFlag) {
+        (_, 128) | (1, _) => PowerSource::Ac,
+        (0, _) => PowerSource::Battery,
+        _ => PowerSource::Unknown,
+    }
+}
+
+#[cfg(target_os = "linux")]
+pub(crate) fn power_source() -> PowerSource {
+    let Ok(entries) = std::fs::read_dir("/sys/class/power_supply") else {
+        return PowerSource::Unknown;
+    };
+    let read = |p: std::path::PathBuf| std::fs::read_to_string(p).ok().map(|s| s.trim().to_string());
+    let supplies: Vec<_> = entries
+        .filter_map(Result::ok)
+        .filter_map(|e| {
+            let dir = e.path();
+            Some((read(dir.join("type"))?, read(dir.join("online")), read(dir.join("status"))))
+        })
+        .collect();
+    classify_supplies(&supplies)
+}
+
+#[cfg(target_os = "macos")]
+pub(crate) fn power_source() -> PowerSource {
+    let Ok(out) = std::process::Command::new("pmset").args(["-g", "batt"]).output() else {
+        return PowerSource::Unknown;
+    };
+    let text = String::from_utf8_lossy(&out.stdout);
+    if text.contains("'AC Power'") {
+        PowerSource::Ac
+    } else if text.contains("'Battery Power'") {
+        PowerSource::Battery
+    } else {
+        PowerSource::Unknown
+    }
+}
+
+#[cfg(not(any(windows, target_os = "linux", target_os = "macos")))]
+pub(crate) fn power_source() -> PowerSource {
+    PowerSource::Unknown
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn supply(t: &str, online: Option<&str>, status: Option<&str>) -> (String, Option<String>, Option<String>) {
+        (t.into(), online.map(Into::into), status.map(Into::into))
+    }
+
+    #[test]
+    fn linux_supplies_are_classified() {
+        let plugged = [supply("Mains", Some("1"), None), supply("Battery", None, Some("Charging"))];
+        assert_eq!(classify_supplies(&plugged), PowerSource::Ac);
+        let unplugged = [supply("Mains", Some("0"), None), supply("Battery", None, Some("Discharging"))];
+        assert_eq!(classify_supplies(&unplugged), PowerSource::Battery);
+        assert_eq!(classify_supplies(&[]), PowerSource::Ac, "desktop without supplies");
+        assert_eq!(classify_supplies(&[supply("Battery", None, Some("Full"))]), PowerSource::Unknown);
+    }
+
+    #[test]
+    fn battery_interval_only_applies_unplugged_and_never_shortens() {
+        assert_eq!(effective_capture_interval(60_000, Some(300_000), PowerSource::Battery), 300_000);
+        assert_eq!(effective_capture_interval(60_000, Some(300_000), PowerSource::Ac), 60_000);
+        assert_eq!(effective_capture_interval(60_000, Some(300_000), PowerSource::Unknown), 60_000);
+        assert_eq!(effective_capture_interval(60_000, None, PowerSource::Battery), 60_000);
+        assert_eq!(effective_capture_interval(60_000, Some(10_000), PowerSource::Battery), 60_000);
+    }
+}
@@ -0,0 +1,378 @@
+//! Self-contained HTML export of the local timeline (performance reviews, sharing with a lead).
+//! Inline CSS only, no scripts or remote assets, so the file opens offline from disk.
+//!
+//! `export_reports_csv` streams raw `reports` rows to disk instead: rows go straight from the
+//! SQLite cursor to a buffered writer, so memory stays flat however large the history is.
+
+use crate::agent::{history_entries_between, summarize_entries, DayHistoryEntry};
+use crate::auth::html_escape;
+use crate::insights_local::FOCUS_CATEGORIES;
+use chrono::{Local, NaiveDateTime, TimeZone, Utc};
+use rusqlite::Connection;
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tauri::Emitter;
+
+const EXPORT_MAX_DAYS: u32 = 366;
+
+const STYLE: &str = "body{font-family:-apple-system,Segoe UI,Roboto,sans-serif;margin:32px;color:#1f2937}\
+h1{margin:0 0 4px}h2{margin-top:32px;font-size:18px}.muted{color:#6b7280}\
+.cards{display:flex;gap:16px;margin-top:16px}.card{border:1px solid #e5e7eb;border-radius:8px;padding:12px 16px}\
+.card b{display:block;font-size:22px}table{border-collapse:collapse;width:100%;margin-top:8px;font-size:14px}\
+th,td{border-bottom:1px solid #e5e7eb;padding:6px 8px;text-align:left;vertical-align:top}\
+.bar{background:#6366f1;height:8px;border-radius:4px}";
+
+fn hours(secs: i32) -> String {
+    format!("{:.1}h", secs as f64 / 3600.0)
+}
+
+/// `created_at` is UTC (`CURRENT_TIMESTAMP`); show local wall-clock time in the export.
+fn local_time(created_at: &str) -> String {
+    NaiveDateTime::parse_from_str(created_at, "%Y-%m-%d %H:%M:%S")
+        .map(|t| {
+            Utc.from_utc_datetime(&t)
+                .with_timezone(&Local)
+                .format("%Y-%m-%d %H:%M")
+                .to_string()
+        })
+        .unwrap_or_else(|_| created_at.to_string())
+}
+
+pub(crate) fn render_timeline_html(dev_name: &str, from: &str, to: &str, entries: &[DayHistoryEntry]) -> String {
+    let (total_seconds, mut categories, _) = summarize_entries(entries);
+    categories.sort_by_key(|c| std::cmp::Reverse(c.total_seconds));
+    let focus_seconds: i32 = entries
+        .iter()
+        .filter(|e| FOCUS_CATEGORIES.contains(&e.category.as_str()))
+        .map(|e| e.duration_seconds)
+        .sum();
+    let focus_pct = if total_seconds > 0 {
+        focus_seconds as f64 * 100.0 / total_seconds as f64
+    } else {
+        0.0
+    };
+
+    let mut days: BTreeMap<String, (i32, BTreeMap<&str, i32>)> = BTreeMap::new();
+    for e in entries {
+        let day = local_time(&e.time).chars().take(10).collect::<String>();
+        let slot = days.entry(day).or_default();
+        slot.0 += e.duration_seconds;
+        *slot.1.entry(e.category.as_str()).or_insert(0) += e.duration_seconds;
+    }
+
+    let mut html = String::with_capacity(4096 + entries.len() * 200);
+    html.push_str("<!DOCTYPE html><html><head><meta charset=\"utf-8\"><title>FlowSight timeline</title><style>");
+    html.push_str(STYLE);
+    html.push_str("</style></head><body>");
+    html.push_str(&format!(
+        "<h1>{}</h1><div class=\"muted\">FlowSight activity · {} → {} · generated {}</div>",
+        html_escape(dev_name),
+        html_escape(from),
+        html_escape(to),
+        Local::now().format("%Y-%m-%d %H:%M")
+    ));
+    html.push_str(&format!(
+        "<div class=\"cards\"><div class=\"card\"><b>{}</b>tracked</div><div class=\"card\"><b>{}</b>focus</div>\
+         <div class=\"card\"><b>{:.0}%</b>focus share</div><div class=\"card\"><b>{}</b>captures</div></div>",
+        hours(total_seconds),
+        hours(focus_seconds),
+        focus_pct,
+        entries.len()
+    ));
+
+    html.push_str("<h2>Activity breakdown</h2><table><tr><th>Category</th><th>Time</th><th>Share</th><th></th></tr>");
+    for c in &categories {
+        let pct = if total_seconds > 0 {
+            c.total_seconds as f64 * 100.0 / total_seconds as f64
+        } else {
+            0.0
+        };
+        html.push_str(&format!(
+            "<tr><td>{}</td><td>{}</td><td>{:.0}%</td><td style=\"width:40%\"><div class=\"bar\" style=\"width:{:.0}%\"></div></td></tr>",
+            html_escape(&c.category),
+            hours(c.total_seconds),
+            pct,
+            pct
+        ));
+    }
+    html.push_str("</table>");
+
+    html.push_str("<h2>Day by day</h2><table><tr><th>Date</th><th>Tracked</th><th>Top activity</th></tr>");
+    for (day, (secs, cats)) in &days {
+        let top = cats
+            .iter()
+            .max_by_key(|(_, s)| **s)
+            .map(|(c, _)| *c)
+            .unwrap_or("-");
+        html.push_str(&format!(
+            "<tr><td>{}</td><td>{}</td><td>{}</td></tr>",
+            html_escape(day),
+            hours(*secs),
+            html_escape(top)
+        ));
+    }
+    html.push_str("</table>");
+
+    html.push_str("<h2>Timeline</h2><table><tr><th>Time</th><th>Category</th><th>Ticket</th><th>Description</th></tr>");
+    for e in entries.iter().rev() {
+        html.push_str(&format!(
+            "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+            html_escape(&local_time(&e.time)),
+            html_escape(&e.category),
+            html_escape(e.ticket.as_deref().unwrap_or("")),
+            html_escape(&e.description)
+        ));
+    }
+    html.push_str("</table></body></html>");
+    html
+}
+
+/// Writes the last `days` days (including today) of the local timeline to `path` as one HTML file.
+#[tauri::command]
+pub fn export_timeline_html(days: u32, path: String) -> Result<String, String> {
+    let days = days.clamp(1, EXPORT_MAX_DAYS);
+    let db_path = crate::paths::db_path()?;
+    let conn = Connection::open(&db_path).map_err(|e| e.to_string())?;
+
+    let to = Local::now().date_naive();
+    let from = to - chrono::Duration::days(days as i64 - 1);
+    let (from, to) = (from.format("%Y-%m-%d").to_string(), to.format("%Y-%m-%d").to_string());
+    let entries = history_entries_between(&conn, &from, &to)?;
+
+    let config_value = |key: &str| {
+        conn.query_row("SELECT value FROM config WHERE key = ?1", [key], |r| r.get::<_, String>(0))
+            .ok()
+    };
+    let dev_name = crate::agent_pure::developer_label(
+        &config_value("dev_name").unwrap_or_else(whoami::realname),
+        Some(&config_value("device_name").unwrap_or_else(whoami::devicename)),
+    );
+
+    let html = render_timeline_html(&dev_name, &from, &to, &entries);
+    std::fs::write(&path, html).map_err(|e| format!("Cannot write {}: {}", path, e))?;
+    log::info!("[Export] timeline ({} entries, {} day(s)) written to {}", entries.len(), days, path);
+    Ok(path)
+}
+
+/// Rows between `export-progress` events / buffer flushes.
+const CSV_PROGRESS_ROWS: usize = 5000;
+const CSV_HEADER: &str = "id,created_at,activity_type,jira_ticket,duration_seconds,synced,description\n";
+
+/// Set by `cancel_csv_export`; checked before every row.
+static CSV_EXPORT_CANCEL: AtomicBool = AtomicBool::new(false);
+
+/// RFC 4180 field: quoted only when it contains a comma, quote or line break.
+fn csv_field(value: &str) -> std::borrow::Cow<'_, str> {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\"")).into()
+    } else {
+        value.into()
+    }
+}
+
+/// `csv_field` for free text (model output, window titles): a leading `=`, `+`, `-`, `@`, tab
+/// or CR would run as a formula in Excel / Sheets, so such cells get a `'` prefix.
+fn csv_text_field(value: &str) -> std::borrow::Cow<'_, str> {
+    if value.starts_with(['=', '+', '-', '@', '\t', '\r']) {
+        csv_field(&format!("'{}", value)).into_owned().into()
+    } else {
+        csv_field(value)
+    }
+}
+
+/// Streams reports whose local date is in `from..=to` (either bound optional) as CSV.
+/// `on_progress(rows)` runs every `CSV_PROGRESS_ROWS` rows after a flush; `is_cancelled()` is
+/// checked before every row and stops the export. Returns `(rows written, completed)`.
+pub(crate) fn write_reports_csv<W: Write>(
+    conn: &Connection,
+    from: Option<&str>,
+    to: Option<&str>,
+    out: W,
+    mut on_progress: impl FnMut(usize),
+    is_cancelled: impl Fn() -> bool,
+) -> Result<(usize, bool), String> {
+    let mut out = std::io::BufWriter::new(out);
+    let io_err = |e: std::io::Error| e.to_string();
+    out.write_all(CSV_HEADER.as_bytes()).map_err(io_err)?;
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, created_at, activity_type, jira_ticket_id, duration_seconds, synced, description
+             FROM reports
+             WHERE (?1 IS NULL OR date(created_at, 'localtime') >= ?1)
+               AND (?2 IS NULL OR date(created_at, 'localtime') <= ?2)
+             ORDER BY id",
+        )
+        .map_err(|e| e.to_string())?;
+    let mut rows = stmt.query(rusqlite::params![from, to]).map_err(|e| e.to_string())?;
+    let mut written = 0usize;
+    while let Some(row) = rows.next().map_err(|e| e.to_string())? {
+        if is_cancelled() {
+            out.flush().map_err(io_err)?;
+            return Ok((written, false));
+        }
+        let text = |i: usize| row.get::<_, Option<String>>(i).ok().flatten().unwrap_or_default();
+        writeln!(
+            out,
+            "{},{},{},{},{},{},{}",
+            row.get::<_, i64>(0).map_err(|e| e.to_string())?,
+            csv_field(&local_time(&text(1))),
+            csv_text_field(&text(2)),
+            csv_text_field(&text(3)),
+            row.get::<_, Option<i64>>(4).ok().flatten().unwrap_or(0),
+            row.get::<_, Option<i64>>(5).ok().flatten().unwrap_or(0) != 0,
+            csv_text_field(&text(6)),
+        )
+        .map_err(io_err)?;
+        written += 1;
+        if written % CSV_PROGRESS_ROWS == 0 {
+            out.flush().map_err(io_err)?;
+            on_progress(written);
+        }
+    }
+    out.flush().map_err(io_err)?;
+    Ok((written, true))
+}
+
+/// Writes `path` via a `.part` file renamed on success; a cancelled export deletes it, so
+/// `path` is either complete or absent. Emits `export-progress` `{ rows, done, cancelled }`.
+#[tauri::command]
+pub async fn export_reports_csv(
+    app: tauri::AppHandle,
+    path: String,
+    from: Option<String>,
+    to: Option<String>,
+) -> Result<serde_json::Value, String> {
+    CSV_EXPORT_CANCEL.store(false, Ordering::Relaxed);
+    tauri::async_runtime::spawn_blocking(move || {
+        let db_path = crate::paths::db_path()?;
+        let conn = Connection::open(&db_path).map_err(|e| e.to_string())?;
+        let part = format!("{}.part", path);
+        let file = std::fs::File::create(&part).map_err(|e| format!("Cannot write {}: {}", part, e))?;
+
+        let result = write_reports_csv(
+            &conn,
+            from.as_deref(),
+            to.as_deref(),
+            file,
+            |rows| {
+                let _ = app.emit("export-progress", serde_json::json!({ "rows": rows, "done": false }));
+            },
+            || CSV_EXPORT_CANCEL.load(Ordering::Relaxed),
+        );
+        let (rows, completed) = match result {
+            Ok(v) => v,
+            Err(e) => {
+                let _ = std::fs::remove_file(&part);
+                return Err(e);
+            }
+        };
+        if completed {
+            std::fs::rename(&part, &path).map_err(|e| format!("Cannot write {}: {}", path, e))?;
+            log::info!("[Export] {} report(s) written to {}", rows, path);
+        } else {
+            let _ = std::fs::remove_file(&part);
+            log::info!("[Export] CSV export cancelled after {} row(s)", rows);
+        }
+        let _ = app.emit(
+            "export-progress",
+            serde_json::json!({ "rows": rows, "done": true, "cancelled": !completed }),
+        );
+        Ok(serde_json::json!({
+            "path": completed.then_some(path),
+            "rows": rows,
+            "cancelled": !completed,
+        }))
+    })
+    .await
+    .map_err(|e| format!("Task join error: {}", e))?
+}
+
+#[tauri::command]
+pub fn cancel_csv_export() {
+    CSV_EXPORT_CANCEL.store(true, Ordering::Relaxed);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(time: &str, category: &str, desc: &str, secs: i32) -> DayHistoryEntry {
+        DayHistoryEntry {
+            time: time.to_string(),
+            description: desc.to_string(),
+            category: category.to_string(),
+            ticket: None,
+            duration_seconds: secs,
+        }
+    }
+
+    #[test]
+    fn html_is_self_contained_and_escaped() {
+        let entries = vec![
+            entry("2026-03-02 10:00:00", "Coding", "Editing <script>alert(1)</script>", 1800),
+            entry("2026-03-02 11:00:00", "Browsing", "Reading docs", 600),
+        ];
+        let html = render_timeline_html("Dev & Co", "2026-03-02", "2026-03-02", &entries);
+        assert!(html.contains("Dev &amp; Co"));
+        assert!(html.contains("&lt;script&gt;"));
+        assert!(!html.contains("<script>"));
+        assert!(!html.contains("http://") && !html.contains("https://"));
+        assert!(html.contains("75%"), "focus share = 1800 / 2400");
+    }
+
+    #[test]
+    fn csv_streams_escaped_rows_and_stops_on_cancel() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE reports (id INTEGER PRIMARY KEY, description TEXT, activity_type TEXT,
+                synced INTEGER DEFAULT 0, created_at TEXT, jira_ticket_id TEXT, duration_seconds INTEGER);",
+        )
+        .unwrap();
+        conn.execute(
+            "INSERT INTO reports (description, activity_type, created_at, duration_seconds)
+             VALUES ('Fixing \"auth\", again', 'Coding', '2026-03-02 10:00:00', 30)",
+            [],
+        )
+        .unwrap();
+        let mut buf = Vec::new();
+        let (rows, done) = write_reports_csv(&conn, None, None, &mut buf, |_| {}, || false).unwrap();
+        assert_eq!((rows, done), (1, true));
+        let csv = String::from_utf8(buf).unwrap();
+        assert!(csv.starts_with(CSV_HEADER));
+        assert!(csv.contains(",Coding,,30,false,\"Fixing \"\"auth\"\", again\"\n"));
+
+        for _ in 1..CSV_PROGRESS_ROWS + 10 {
+            conn.execute(
+                "INSERT INTO reports (description, activity_type, created_at) VALUES ('x', 'Coding', '2026-03-02 10:00:00')",
+                [],
+            )
+            .unwrap();
+        }
+        // cancel is seen on the next row, not at the next progress tick
+        let checks = std::cell::Cell::new(0);
+        let cancel_after_three = || {
+            checks.set(checks.get() + 1);
+            checks.get() > 3
+        };
+        let (rows, done) = write_reports_csv(&conn, None, None, std::io::sink(), |_| {}, cancel_after_three).unwrap();
+        assert_eq!((rows, done), (3, false));
+        let mut progress = Vec::new();
+        let (rows, done) =
+            write_reports_csv(&conn, None, None, std::io::sink(), |n| progress.push(n), || false).unwrap();
+        assert_eq!((rows, done, progress), (CSV_PROGRESS_ROWS + 10, true, vec![CSV_PROGRESS_ROWS]));
+        let (rows, _) =
+            write_reports_csv(&conn, Some("2030-01-01"), None, std::io::sink(), |_| {}, || false).unwrap();
+        assert_eq!(rows, 0);
+    }
+
+    #[test]
+    fn csv_text_cells_cannot_start_a_formula() {
+        assert_eq!(csv_text_field("=HYPERLINK(\"http://x\")"), "\"'=HYPERLINK(\"\"http://x\"\")\"");
+        assert_eq!(csv_text_field("+1 555"), "'+1 555");
+        assert_eq!(csv_text_field("@SUM(A1)"), "'@SUM(A1)");
+        assert_eq!(csv_text_field("-2+3"), "'-2+3");
+        assert_eq!(csv_text_field("Editing main.rs"), "Editing main.rs");
+    }
+}
@@ -0,0 +1,458 @@
+//! Local history retention: deletes `reports` rows older than `retention_days`, once a day at a
+//! configurable local hour so the sweep never competes with captures during the workday.
+//!
+//! Retention is opt-in (`retention_days` unset = keep everything, the historical behavior).
+//!
+//! Separately, `max_unsynced_reports` bounds the offline upload queue: when a developer stays
+//! offline long enough to exceed it, the oldest unsynced rows are dropped on each save so the
+//! next sync cannot flood the cloud with weeks of backlog.
+//!
+//! `get_storage_info` shows how much disk the local history uses, to decide when to clean up.
+
+use chrono::{Duration as ChronoDuration, Local, NaiveDateTime, NaiveTime, Timelike};
+use rusqlite::{params, Connection};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::thread;
+use std::time::Duration;
+
+/// Local hour (0-23) used when `retention_sweep_hour` is not configured.
+pub(crate) const RETENTION_SWEEP_HOUR_DEFAULT: u32 = 3;
+/// Longest accepted `retention_days` (10 years); anything bigger is a typo, not a policy.
+pub(crate) const RETENTION_DAYS_MAX: u32 = 3650;
+
+/// `retentionDays` from the renderer: `0` turns retention off, otherwise 1..=`RETENTION_DAYS_MAX`.
+pub(crate) fn validate_retention_days(days: u32) -> Result<Option<u32>, String> {
+    match days {
+        0 => Ok(None),
+        d if d <= RETENTION_DAYS_MAX => Ok(Some(d)),
+        d => Err(format!(
+            "retentionDays must be between 1 and {} (0 keeps everything); got {}",
+            RETENTION_DAYS_MAX, d
+        )),
+    }
+}
+
+/// How often the background thread wakes up to check whether a sweep is due.
+const RETENTION_POLL_SECS: u64 = 300;
+const LAST_SWEEP_KEY: &str = "last_retention_sweep";
+const UNSYNCED_EVICTION_KEY: &str = "unsynced_eviction";
+
+fn read_config_value(conn: &Connection, key: &str) -> Option<String> {
+    conn.query_row("SELECT value FROM config WHERE key = ?1", [key], |r| r.get(0))
+        .ok()
+}
+
+/// `(retention_days, sweep_hour)` as persisted by `FlowSightAgent::save_config`.
+pub(crate) fn load_retention_settings(conn: &Connection) -> (Option<u32>, u32) {
+    let days = read_config_value(conn, "retention_days")
+        .and_then(|v| v.parse::<u32>().ok())
+        .filter(|&d| d > 0);
+    let hour = read_config_value(conn, "retention_sweep_hour")
+        .and_then(|v| v.parse::<u32>().ok())
+        .filter(|&h| h < 24)
+        .unwrap_or(RETENTION_SWEEP_HOUR_DEFAULT);
+    (days, hour)
+}
+
+fn load_last_sweep(conn: &Connection) -> Option<(NaiveDateTime, usize)> {
+    let raw = read_config_value(conn, LAST_SWEEP_KEY)?;
+    let v: serde_json::Value = serde_json::from_str(&raw).ok()?;
+    let at = NaiveDateTime::parse_from_str(v["at"].as_str()?, "%Y-%m-%d %H:%M:%S").ok()?;
+    let removed = v["removed"].as_u64().unwrap_or(0) as usize;
+    Some((at, removed))
+}
+
+/// First `hour:00` local time strictly after `now`.
+pub(crate) fn next_sweep_at(now: NaiveDateTime, hour: u32) -> NaiveDateTime {
+    let at = NaiveTime::from_hms_opt(hour.min(23), 0, 0).unwrap_or(NaiveTime::MIN);
+    let today = now.date().and_time(at);
+    if today > now {
+        today
+    } else {
+        today + ChronoDuration::days(1)
+    }
+}
+
+/// A sweep is due once per calendar day, as soon as the local clock passes `hour`.
+pub(crate) fn sweep_due(now: NaiveDateTime, last_sweep: Option<NaiveDateTime>, hour: u32) -> bool {
+    if now.hour() < hour.min(23) {
+        return false;
+    }
+    match last_sweep {
+        Some(last) => last.date() < now.date(),
+        None => true,
+    }
+}
+
+/// Bumped whenever retention deletes reports. The sweep thread and the eviction path don't hold
+/// the agent state, so the summary cache compares this instead of being invalidated directly.
+static REPORTS_DELETED: AtomicU64 = AtomicU64::new(0);
+
+pub(crate) fn reports_deleted_generation() -> u64 {
+    REPORTS_DELETED.load(Ordering::Relaxed)
+}
+
+fn note_deleted(removed: usize) -> usize {
+    if removed > 0 {
+        REPORTS_DELETED.fetch_add(1, Ordering::Relaxed);
+    }
+    removed
+}
+
+/// Deletes reports older than `days` (compared in UTC, like `created_at`). Returns rows removed.
+/// Reports that never reached the cloud are kept unless `include_unsynced` (local-only mode,
+/// where nothing is ever uploaded); the unsynced cap is what bounds those.
+pub(crate) fn delete_reports_older_than(
+    conn: &Connection,
+    days: u32,
+    include_unsynced: bool,
+) -> Result<usize, String> {
+    conn.execute(
+        "DELETE FROM reports WHERE created_at < datetime('now', ?1) AND (?2 OR synced = 1)",
+        params![format!("-{} days", days), include_unsynced],
+    )
+    .map(note_deleted)
+    .map_err(|e| e.to_string())
+}
+
+/// Configured cap on unsynced reports (`None` = unbounded, the historical behavior).
+pub(crate) fn load_max_unsynced(conn: &Connection) -> Option<u32> {
+    read_config_value(conn, "max_unsynced_reports")
+        .and_then(|v| v.parse::<u32>().ok())
+        .filter(|&n| n > 0)
+}
+
+pub(crate) fn count_unsynced(conn: &Connection) -> Result<i64, String> {
+    conn.query_row("SELECT COUNT(*) FROM reports WHERE synced = 0", [], |r| r.get(0))
+        .map_err(|e| e.to_string())
+}
+
+/// Deletes the oldest unsynced reports beyond `cap`. Returns rows removed.
+pub(crate) fn evict_unsynced_over_cap(conn: &Connection, cap: u32) -> Result<usize, String> {
+    conn.execute(
+        "DELETE FROM reports WHERE id IN (
+             SELECT id FROM reports WHERE synced = 0
+             ORDER BY id DESC LIMIT -1 OFFSET ?1
+         )",
+        params![cap],
+    )
+    .map(note_deleted)
+    .map_err(|e| e.to_string())
+}
+
+/// `(total evicted so far, last eviction time)`.
+fn load_eviction_stats(conn: &Connection) -> (u64, Option<String>) {
+    let Some(v) = read_config_value(conn, UNSYNCED_EVICTION_KEY)
+        .and_then(|raw| serde_json::from_str::<serde_json::Value>(&raw).ok())
+    else {
+        return (0, None);
+    };
+    (
+        v["total"].as_u64().unwrap_or(0),
+        v["at"].as_str().map(str::to_string),
+    )
+}
+
+/// Called after every saved report; a no-op unless `max_unsynced_reports` is set and exceeded.
+pub(crate) fn enforce_unsynced_cap(conn: &Connection) -> Result<usize, String> {
+    let Some(cap) = load_max_unsynced(conn) else {
+        return Ok(0);
+    };
+    let removed = evict_unsynced_over_cap(conn, cap)?;
+    if removed > 0 {
+        let (total, _) = load_eviction_stats(conn);
+        let meta = serde_json::json!({
+            "at": Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+            "total": total + removed as u64,
+        });
+        let _ = conn.execute(
+            "INSERT OR REPLACE INTO config (key, value) VALUES (?1, ?2)",
+            params![UNSYNCED_EVICTION_KEY, meta.to_string()],
+        );
+        log::warn!(
+            "[Retention] offline queue over {} report(s): dropped {} oldest unsynced",
+            cap,
+            removed
+        );
+    }
+    Ok(removed)
+}
+
+/// Pending upload queue for `get_status`: size, cap and whether history was ever dropped.
+pub(crate) fn unsynced_queue_status(conn: &Connection) -> serde_json::Value {
+    let (evicted, last_at) = load_eviction_stats(conn);
+    serde_json::json!({
+        "pendingReports": count_unsynced(conn).unwrap_or(0),
+        "maxUnsyncedReports": load_max_unsynced(conn),
+        "evictionOccurred": evicted > 0,
+        "evictedReports": evicted,
+        "lastEvictionAt": last_at,
+    })
+}
+
+fn record_sweep(conn: &Connection, removed: usize) {
+    let meta = serde_json::json!({
+        "at": Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+        "removed": removed,
+    });
+    let _ = conn.execute(
+        "INSERT OR REPLACE INTO config (key, value) VALUES (?1, ?2)",
+        params![LAST_SWEEP_KEY, meta.to_string()],
+    );
+}
+
+fn run_sweep_if_due(db_path: &std::path::Path) {
+    if crate::paths::wipe_in_progress() {
+        return;
+    }
+    let Ok(conn) = Connection::open(db_path) else {
+        return;
+    };
+    let (Some(days), hour) = load_retention_settings(&conn) else {
+        return;
+    };
+    let now = Local::now().naive_local();
+    if !sweep_due(now, load_last_sweep(&conn).map(|(at, _)| at), hour) {
+        return;
+    }
+    match delete_reports_older_than(&conn, days, crate::http_client::local_only()) {
+        Ok(removed) => {
+            log::info!(
+                "[Retention] removed {} report(s) older than {} day(s)",
+                removed,
+                days
+            );
+            record_sweep(&conn, removed);
+        }
+        Err(e) => log::warn!("[Retention] sweep failed: {}", e),
+    }
+}
+
+pub fn start_retention_thread(db_path: std::path::PathBuf) {
+    thread::spawn(move || loop {
+        run_sweep_if_due(&db_path);
+        thread::sleep(Duration::from_secs(RETENTION_POLL_SECS));
+    });
+}
+
+/// Runs the retention delete now. `days` defaults to the configured `retention_days`.
+#[tauri::command]
+pub fn clear_old_reports(days: Option<u32>) -> Result<usize, String> {
+    let db_path = crate::paths::db_path()?;
+    let conn = Connection::open(&db_path).map_err(|e| e.to_string())?;
+    let days = match days {
+        Some(0) => return Err("days must be at least 1".to_string()),
+        Some(d) => d,
+        None => load_retention_settings(&conn)
+            .0
+            .ok_or("No retention period configured (set retentionDays first).")?,
+    };
+    let removed = delete_reports_older_than(&conn, days, crate::http_client::local_only())?;
+    record_sweep(&conn, removed);
+    log::info!("[Retention] manual sweep removed {} report(s) older than {} day(s)", removed, days);
+    Ok(removed)
+}
+
+#[tauri::command]
+pub fn get_retention_status() -> Result<serde_json::Value, String> {
+    let db_path = crate::paths::db_path()?;
+    let conn = Connection::open(&db_path).map_err(|e| e.to_string())?;
+    let (days, hour) = load_retention_settings(&conn);
+    let last = load_last_sweep(&conn);
+    let next = days.map(|_| {
+        next_sweep_at(Local::now().naive_local(), hour)
+            .format("%Y-%m-%d %H:%M:%S")
+            .to_string()
+    });
+    Ok(serde_json::json!({
+        "enabled": days.is_some(),
+        "retentionDays": days,
+        "sweepHour": hour,
+        "lastSweepAt": last.map(|(at, _)| at.format("%Y-%m-%d %H:%M:%S").to_string()),
+        "lastRemoved": last.map(|(_, n)| n),
+        "nextSweepAt": next,
+    }))
+}
+
+/// Tables reported by `get_storage_info`.
+const STORAGE_TABLES: &[&str] = &["reports", "config", "blockers"];
+
+/// Row counts per table plus the oldest / newest report timestamp (UTC, as stored).
+pub(crate) fn storage_stats(conn: &Connection) -> Result<serde_json::Value, String> {
+    let mut rows = serde_json::Map::new();
+    for table in STORAGE_TABLES {
+        // table names are constants; a missing table (old DB) counts as 0
+        let n: i64 = conn
+            .query_row(&format!("SELECT COUNT(*) FROM {}", table), [], |r| r.get(0))
+            .unwrap_or(0);
+        rows.insert(table.to_string(), n.into());
+    }
+    let (oldest, newest): (Option<String>, Option<String>) = conn
+        .query_row("SELECT MIN(created_at), MAX(created_at) FROM reports", [], |r| {
+            Ok((r.get(0)?, r.get(1)?))
+        })
+        .map_err(|e| e.to_string())?;
+    Ok(serde_json::json!({
+        "rowCounts": rows,
+        "oldestReportAt": oldest,
+        "newestReportAt": newest,
+    }))
+}
+
+fn file_len(path: &std::path::Path) -> u64 {
+    std::fs::metadata(path).map(|m| m.len()).unwrap_or(0)
+}
+
+/// Local disk usage for diagnostics ("the app is using 2 GB"): DB (+ WAL/journal) size,
+/// debug screenshots, row counts and the report date range.
+#[tauri::command]
+pub fn get_storage_info() -> Result<serde_json::Value, String> {
+    let db_path = crate::paths::db_path()?;
+    let conn = Connection::open(&db_path).map_err(|e| e.to_string())?;
+    let mut info = storage_stats(&conn)?;
+    let sidecar = |suffix: &str| {
+        let mut p = db_path.clone().into_os_string();
+        p.push(suffix);
+        file_len(std::path::Path::new(&p))
+    };
+    let db_bytes = file_len(&db_path);
+    let wal_bytes = sidecar("-wal") + sidecar("-journal");
+    let screenshots_bytes: u64 = crate::paths::screenshots_tmp_dir()
+        .ok()
+        .and_then(|d| std::fs::read_dir(d).ok())
+        .map(|entries| {
+            entries
+                .filter_map(Result::ok)
+                .filter_map(|e| e.metadata().ok())
+                .filter(|m| m.is_file())
+                .map(|m| m.len())
+                .sum()
+        })
+        .unwrap_or(0);
+    info["dbPath"] = db_path.to_string_lossy().into();
+    info["dbBytes"] = db_bytes.into();
+    info["walBytes"] = wal_bytes.into();
+    info["screenshotsBytes"] = screenshots_bytes.into();
+    info["totalBytes"] = (db_bytes + wal_bytes + screenshots_bytes).into();
+    Ok(info)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn at(y: i32, m: u32, d: u32, h: u32, min: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(y, m, d)
+            .unwrap()
+            .and_hms_opt(h, min, 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn retention_days_validation() {
+        assert_eq!(validate_retention_days(0), Ok(None));
+        assert_eq!(validate_retention_days(1), Ok(Some(1)));
+        assert_eq!(validate_retention_days(RETENTION_DAYS_MAX), Ok(Some(RETENTION_DAYS_MAX)));
+        assert!(validate_retention_days(RETENTION_DAYS_MAX + 1).is_err());
+    }
+
+    #[test]
+    fn next_sweep_is_later_today_or_tomorrow() {
+        assert_eq!(next_sweep_at(at(2026, 3, 10, 1, 30), 3), at(2026, 3, 10, 3, 0));
+        assert_eq!(next_sweep_at(at(2026, 3, 10, 3, 0), 3), at(2026, 3, 11, 3, 0));
+        assert_eq!(next_sweep_at(at(2026, 3, 10, 22, 0), 3), at(2026, 3, 11, 3, 0));
+    }
+
+    #[test]
+    fn sweep_due_once_per_day_after_hour() {
+        assert!(!sweep_due(at(2026, 3, 10, 2, 59), None, 3));
+        assert!(sweep_due(at(2026, 3, 10, 3, 5), None, 3));
+        assert!(!sweep_due(at(2026, 3, 10, 9, 0), Some(at(2026, 3, 10, 3, 5)), 3));
+        assert!(sweep_due(at(2026, 3, 11, 3, 0), Some(at(2026, 3, 10, 3, 5)), 3));
+    }
+
+    #[test]
+    fn storage_stats_counts_rows_and_range() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE reports (id INTEGER PRIMARY KEY, created_at TEXT);
+             CREATE TABLE config (key TEXT PRIMARY KEY, value TEXT);
+             INSERT INTO reports (created_at) VALUES ('2026-03-01 08:00:00'), ('2026-03-05 17:30:00');
+             INSERT INTO config VALUES ('k', 'v');",
+        )
+        .unwrap();
+        let s = storage_stats(&conn).unwrap();
+        assert_eq!(s["rowCounts"]["reports"], 2);
+        assert_eq!(s["rowCounts"]["config"], 1);
+        assert_eq!(s["rowCounts"]["blockers"], 0, "missing table counts as empty");
+        assert_eq!(s["oldestReportAt"], "2026-03-01 08:00:00");
+        assert_eq!(s["newestReportAt"], "2026-03-05 17:30:00");
+    }
+
+    #[test]
+    fn delete_keeps_recent_and_unsynced_reports() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE reports (id INTEGER PRIMARY KEY, description TEXT, synced INTEGER DEFAULT 0, created_at TEXT);
+             INSERT INTO reports (description, synced, created_at) VALUES ('old', 1, datetime('now', '-40 days'));
+             INSERT INTO reports (description, synced, created_at) VALUES ('old-unsynced', 0, datetime('now', '-40 days'));
+             INSERT INTO reports (description, synced, created_at) VALUES ('new', 1, datetime('now', '-1 days'));",
+        )
+        .unwrap();
+        let left = |conn: &Connection| -> Vec<String> {
+            conn.prepare("SELECT description FROM reports ORDER BY id")
+                .unwrap()
+                .query_map([], |r| r.get(0))
+                .unwrap()
+                .map(|r| r.unwrap())
+                .collect()
+        };
+        assert_eq!(delete_reports_older_than(&conn, 30, false).unwrap(), 1);
+        assert_eq!(left(&conn), vec!["old-unsynced", "new"]);
+        // local-only: nothing will ever upload, so unsynced rows age out too
+        assert_eq!(delete_reports_older_than(&conn, 30, true).unwrap(), 1);
+        assert_eq!(left(&conn), vec!["new"]);
+    }
+
+    #[test]
+    fn eviction_drops_oldest_unsynced_only() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE config (key TEXT PRIMARY KEY, value TEXT);
+             CREATE TABLE reports (id INTEGER PRIMARY KEY, description TEXT, synced INTEGER DEFAULT 0);
+             INSERT INTO reports (description, synced) VALUES ('a', 1), ('b', 0), ('c', 0), ('d', 0), ('e', 0);",
+        )
+        .unwrap();
+        assert_eq!(enforce_unsynced_cap(&conn).unwrap(), 0, "no cap configured");
+        conn.execute("INSERT INTO config VALUES ('max_unsynced_reports', '2')", [])
+            .unwrap();
+        assert_eq!(enforce_unsynced_cap(&conn).unwrap(), 2);
+        let left: Vec<String> = conn
+            .prepare("SELECT description FROM reports ORDER BY id")
+            .unwrap()
+            .query_map([], |r| r.get(0))
+            .unwrap()
+            .map(|r| r.unwrap())
+            .collect();
+        assert_eq!(left, vec!["a", "d", "e"]);
+        let status = unsynced_queue_status(&conn);
+        assert_eq!(status["pendingReports"], 2);
+        assert_eq!(status["evictedReports"], 2);
+        assert_eq!(status["evictionOccurred"], true);
+    }
+
+    #[test]
+    fn settings_default_hour_and_disabled_days() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch("CREATE TABLE config (key TEXT PRIMARY KEY, value TEXT);")
+            .unwrap();
+        assert_eq!(load_retention_settings(&conn), (None, RETENTION_SWEEP_HOUR_DEFAULT));
+        conn.execute_batch(
+            "INSERT INTO config VALUES ('retention_days', '14');
+             INSERT INTO config VALUES ('retention_sweep_hour', '23');",
+        )
+        .unwrap();
+        assert_eq!(load_retention_settings(&conn), (Some(14), 23));
+    }
+}
@@ -0,0 +1,103 @@
+//! `captureMode: "on_change"` — cheap perceptual hash (dHash) of the primary screen so the
+//! renderer only asks for an expensive vision analysis when the screen actually changed.
+
+use image::DynamicImage;
+use std::sync::Mutex;
+
+pub(crate) const CAPTURE_MODE_INTERVAL: &str = "interval";
+pub(crate) const CAPTURE_MODE_ON_CHANGE: &str = "on_change";
+
+/// Hamming distance (out of 64 bits) at or above which the screen counts as changed.
+/// Cursor blinks / clock ticks stay well below; switching file, tab or app goes well above.
+pub(crate) const CHANGE_THRESHOLD_BITS: u32 = 10;
+
+/// Last analyzed frame: `(hash, description, category)`.
+static LAST_ANALYZED: Mutex<Option<(u64, String, String)>> = Mutex::new(None);
+
+pub(crate) fn normalize_capture_mode(mode: &str) -> Result<String, String> {
+    match mode.trim().to_lowercase().replace('-', "_").as_str() {
+        "interval" => Ok(CAPTURE_MODE_INTERVAL.to_string()),
+        "on_change" | "onchange" => Ok(CAPTURE_MODE_ON_CHANGE.to_string()),
+        other => Err(format!(
+            "Invalid captureMode '{}' (expected \"interval\" or \"on_change\")",
+            other
+        )),
+    }
+}
+
+/// 64-bit difference hash: 9x8 grayscale, one bit per horizontal gradient.
+pub(crate) fn dhash(img: &DynamicImage) -> u64 {
+    let small = img
+        .resize_exact(9, 8, image::imageops::FilterType::Triangle)
+        .to_luma8();
+    let mut hash = 0u64;
+    for y in 0..8 {
+        for x in 0..8 {
+            hash <<= 1;
+            if small.get_pixel(x, y)[0] < small.get_pixel(x + 1, y)[0] {
+                hash |= 1;
+            }
+        }
+    }
+    hash
+}
+
+pub(crate) fn hamming(a: u64, b: u64) -> u32 {
+    (a ^ b).count_ones()
+}
+
+pub(crate) fn remember_analyzed(hash: u64, description: &str, category: &str) {
+    if let Ok(mut g) = LAST_ANALYZED.lock() {
+        *g = Some((hash, description.to_string(), category.to_string()));
+    }
+}
+
+/// Previous analysis if `hash` is close enough to the last analyzed frame to reuse it.
+pub(crate) fn reusable_analysis(hash: u64) -> Option<(String, String)> {
+    let g = LAST_ANALYZED.lock().ok()?;
+    let (last, desc, cat) = g.as_ref()?;
+    (hamming(*last, hash) < CHANGE_THRESHOLD_BITS).then(|| (desc.clone(), cat.clone()))
+}
+
+/// Polled by the renderer every few seconds in on_change mode (no disk write, no model call).
+#[tauri::command]
+pub async fn check_screen_changed() -> Result<serde_json::Value, String> {
+    tauri::async_runtime::spawn_blocking(|| {
+        let img = crate::capture_target::grab_frame()?;
+        let hash = dhash(&img);
+        let last = LAST_ANALYZED.lock().ok().and_then(|g| g.as_ref().map(|l| l.0));
+        let distance = last.map(|l| hamming(l, hash));
+        Ok(serde_json::json!({
+            "changed": distance.map_or(true, |d| d >= CHANGE_THRESHOLD_BITS),
+            "distance": distance,
+        }))
+    })
+    .await
+    .map_err(|e| format!("Task join error: {}", e))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{GrayImage, Luma};
+
+    fn gradient(flip: bool) -> DynamicImage {
+        DynamicImage::ImageLuma8(GrayImage::from_fn(90, 80, |x, _| {
+            let v = (x * 255 / 89) as u8;
+            Luma([if flip { 255 - v } else { v }])
+        }))
+    }
+
+    #[test]
+    fn identical_frames_hash_equal_and_inverted_differs() {
+        assert_eq!(hamming(dhash(&gradient(false)), dhash(&gradient(false))), 0);
+        assert!(hamming(dhash(&gradient(false)), dhash(&gradient(true))) >= CHANGE_THRESHOLD_BITS);
+    }
+
+    #[test]
+    fn capture_mode_validation() {
+        assert_eq!(normalize_capture_mode("On-Change").unwrap(), CAPTURE_MODE_ON_CHANGE);
+        assert_eq!(normalize_capture_mode("interval").unwrap(), CAPTURE_MODE_INTERVAL);
+        assert!(normalize_capture_mode("sometimes").is_err());
+    }
+}
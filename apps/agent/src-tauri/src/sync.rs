@@ -1,9 +1,16 @@
 use crate::sync_env::{supabase_anon_key, supabase_url};
 use crate::vision_model::LLAMA_CHAT_MODEL_ID;
-use crate::sync_pure::{clamp_line_for_summary, jwt_exp, select_unsynced_pending_sql, truncate_tasks_for_summary};
-use reqwest::blocking::{Client, Response};
+use crate::error::{AgentError, AgentResult};
+use crate::sync_pure::{
+    activity_report_body, clamp_line_for_summary, classify_sync_error, jwt_exp, local_midnight_utc,
+    mark_unsynced_since, parse_captured_at, parse_resync_since, retry_after_until,
+    select_unsynced_pending_sql, LICENSE_INVALID_ERR, truncate_tasks_for_summary,
+};
+use reqwest::blocking::Response;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use rusqlite::Connection;
 use serde::{Deserialize, Serialize};
 
@@ -21,6 +28,64 @@ const SUMMARY_MAX_TASK_CHARS: usize = 5000;
 /// Avoid one verbose vision capture consuming the whole summary budget (`FLOWSIGHT_SUMMARY_MAX_LINE_CHARS` to override).
 const SUMMARY_MAX_LINE_CHARS_DEFAULT: usize = 450;
 
+/// Set while a cloud upload runs; quitting mid-upload would leave rows uploaded but not marked synced.
+static SYNC_IN_FLIGHT: AtomicBool = AtomicBool::new(false);
+
+/// Clears `SYNC_IN_FLIGHT` on every exit path of `perform_sync` (including `?` and panics).
+struct SyncInFlightGuard;
+
+impl Drop for SyncInFlightGuard {
+    fn drop(&mut self) {
+        SYNC_IN_FLIGHT.store(false, Ordering::SeqCst);
+    }
+}
+
+/// Set from a 429 `Retry-After`; uploads are skipped (reports stay queued) until then.
+static THROTTLED_UNTIL: Mutex<Option<chrono::DateTime<chrono::Utc>>> = Mutex::new(None);
+
+/// Active server throttle, if any (`None` once it has expired).
+pub(crate) fn throttled_until() -> Option<chrono::DateTime<chrono::Utc>> {
+    let until = (*THROTTLED_UNTIL.lock().ok()?)?;
+    (until > chrono::Utc::now()).then_some(until)
+}
+
+/// Drops any server throttle (factory reset).
+pub(crate) fn clear_throttle() {
+    if let Ok(mut g) = THROTTLED_UNTIL.lock() {
+        *g = None;
+    }
+}
+
+/// On HTTP 429 records the `Retry-After` deadline and returns the error for the caller.
+fn check_rate_limited(resp: &Response) -> Result<(), String> {
+    if resp.status().as_u16() != 429 {
+        return Ok(());
+    }
+    let header = resp
+        .headers()
+        .get(reqwest::header::RETRY_AFTER)
+        .and_then(|v| v.to_str().ok());
+    let until = retry_after_until(header, chrono::Utc::now());
+    if let Ok(mut g) = THROTTLED_UNTIL.lock() {
+        *g = Some(until);
+    }
+    let until = until.to_rfc3339_opts(chrono::SecondsFormat::Secs, true);
+    log::warn!("[CloudSync] HTTP 429 from server; pausing uploads until {}", until);
+    Err(format!("HTTP 429 Too Many Requests: throttled by server until {}", until))
+}
+
+/// Blocks until no sync is running or `timeout` elapses. Returns `true` if idle.
+pub(crate) fn wait_for_sync_idle(timeout: Duration) -> bool {
+    let deadline = Instant::now() + timeout;
+    while SYNC_IN_FLIGHT.load(Ordering::SeqCst) {
+        if Instant::now() >= deadline {
+            return false;
+        }
+        thread::sleep(Duration::from_millis(100));
+    }
+    true
+}
+
 // User session stored locally after login
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct UserSession {
@@ -31,13 +96,53 @@ pub struct UserSession {
     pub email: String,
 }
 
+/// Outcome of one local report in a sync run. Reports upload as one AI summary per batch, so
+/// every report in a failed batch carries the same error.
+#[derive(Serialize, Debug, Clone)]
+pub struct ReportSyncOutcome {
+    pub id: i64,
+    pub ok: bool,
+    pub error: Option<String>,
+    /// `network`, `auth`, `license`, `throttled`, `too_large` or `rejected` (see `classify_sync_error`).
+    pub error_kind: Option<&'static str>,
+}
+
+#[derive(Serialize, Debug, Default)]
+pub struct SyncResult {
+    /// Summary text on success, or why nothing was uploaded.
+    pub message: String,
+    pub synced: usize,
+    pub failed: usize,
+    /// Unsynced reports left locally after this run.
+    pub pending: i64,
+    pub reports: Vec<ReportSyncOutcome>,
+}
+
+impl SyncResult {
+    fn skipped(message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+            ..Default::default()
+        }
+    }
+}
+
 pub fn start_sync_thread(db_path: std::path::PathBuf) {
     let path_clone = db_path.clone();
     thread::spawn(move || {
         // Run once immediately so the first cloud batch is not delayed by SYNC_INTERVAL_MINS.
         let _ = perform_sync(&path_clone);
         loop {
-            thread::sleep(Duration::from_secs(SYNC_INTERVAL_MINS * 60));
+            // ±10% so agents started together do not hit the cloud at the same instant
+            let wait_ms = crate::agent_pure::jittered_interval_ms(
+                SYNC_INTERVAL_MINS * 60_000,
+                crate::agent_pure::CAPTURE_JITTER_PERCENT_DEFAULT,
+                crate::agent::jitter_seed(),
+            );
+            thread::sleep(Duration::from_millis(wait_ms));
+            if crate::paths::wipe_in_progress() {
+                continue;
+            }
             let _ = perform_sync(&path_clone);
         }
     });
@@ -46,6 +151,9 @@ pub fn start_sync_thread(db_path: std::path::PathBuf) {
 /// Proactively refreshes the Supabase session when the access token is missing, expired,
 /// or close to expiry. Safe to call from a background thread.
 pub(crate) fn refresh_session_if_expiring(db_path: &std::path::PathBuf) {
+    if crate::http_client::local_only() || crate::paths::wipe_in_progress() {
+        return;
+    }
     let Ok(conn) = Connection::open(db_path) else {
         return;
     };
@@ -64,7 +172,7 @@ pub(crate) fn refresh_session_if_expiring(db_path: &std::path::PathBuf) {
 
     match refresh_supabase_token(&session) {
         Ok(new_session) => {
-            println!(
+            log::info!(
                 "[Sync] Proactive JWT refresh OK (previous access exp: {})",
                 exp
             );
@@ -74,7 +182,7 @@ pub(crate) fn refresh_session_if_expiring(db_path: &std::path::PathBuf) {
                 let _ = crate::entitlements::save_entitlements(&conn, &entitlements);
             }
         }
-        Err(e) => println!("[Sync] Proactive JWT refresh failed: {}", e),
+        Err(e) => log::warn!("[Sync] Proactive JWT refresh failed: {}", e),
     }
 }
 
@@ -89,13 +197,93 @@ pub fn start_token_refresh_thread(db_path: std::path::PathBuf) {
 }
 
 #[tauri::command]
-pub fn force_sync_now() -> Result<String, String> {
+pub fn force_sync_now() -> AgentResult<SyncResult> {
+    let db_path = crate::paths::db_path()?;
+    crate::entitlements::require_feature(&db_path, "sync")?;
+    // the background sync just skips without a session; a manual one should ask to sign in
+    get_user_session(&Connection::open(&db_path)?).ok_or(AgentError::NotRegistered)?;
+    perform_sync(&db_path)
+}
+
+/// Newest cloud `activity_reports.captured_at` for this user on or after `since` (`None` =
+/// any time), or `None` if the cloud has nothing in that range.
+fn latest_remote_report_since(
+    session: &UserSession,
+    since: Option<chrono::DateTime<chrono::Utc>>,
+) -> AgentResult<Option<String>> {
+    let mut url = format!(
+        "{}/rest/v1/activity_reports?select=captured_at&user_id=eq.{}&order=captured_at.desc&limit=1",
+        supabase_url(),
+        urlencoding::encode(&session.user_id)
+    );
+    if let Some(since) = since {
+        url.push_str(&format!(
+            "&captured_at=gte.{}",
+            urlencoding::encode(&since.to_rfc3339_opts(chrono::SecondsFormat::Secs, true))
+        ));
+    }
+    let get = |s: &UserSession| {
+        crate::http_client::cloud_client()
+            .get(&url)
+            .header("apikey", supabase_anon_key())
+            .header("Authorization", format!("Bearer {}", s.access_token))
+            .send()
+    };
+    let mut resp = get(session)?;
+    if matches!(resp.status().as_u16(), 401 | 403) {
+        if let Ok(refreshed) = refresh_supabase_token(session) {
+            resp = get(&refreshed)?;
+        }
+    }
+    check_rate_limited(&resp).map_err(AgentError::Network)?;
+    if matches!(resp.status().as_u16(), 401 | 403) {
+        return Err(AgentError::NotRegistered);
+    }
+    let rows = crate::http_client::read_json(resp, "Cloud history check").map_err(AgentError::Network)?;
+    Ok(rows[0]["captured_at"].as_str().map(str::to_string))
+}
+
+/// Recovery after a cloud-side data loss: marks local reports since `since` (`YYYY-MM-DD`,
+/// `None` = everything still stored) as unsynced and runs a sync.
+///
+/// Uploads have no idempotency key, so re-sending a window the cloud still has would duplicate
+/// it. The range is therefore only requeued when the cloud has no `activity_reports` in it;
+/// otherwise the error names the newest cloud report so a later `since` can be picked.
+#[tauri::command]
+pub fn resync_all(since: Option<String>) -> AgentResult<serde_json::Value> {
     let db_path = crate::paths::db_path()?;
     crate::entitlements::require_feature(&db_path, "sync")?;
-    match perform_sync(&db_path) {
-        Ok(summary) => Ok(format!("Sync Report:\n\n{}", summary)),
-        Err(e) => Err(format!("Sync failed: {}", e))
+    crate::http_client::ensure_network_allowed()?;
+    let since = parse_resync_since(since.as_deref()).map_err(AgentError::Config)?;
+    refresh_session_if_expiring(&db_path);
+    let conn = Connection::open(&db_path)?;
+    let session = get_user_session(&conn).ok_or(AgentError::NotRegistered)?;
+    if let Some(latest) = latest_remote_report_since(&session, since.map(local_midnight_utc))? {
+        return Err(AgentError::Config(format!(
+            "The cloud already has activity reports in this range (newest: {}); resyncing would \
+             duplicate them. Pass a `since` date after the newest cloud report.",
+            latest
+        )));
     }
+    let tx = conn.unchecked_transaction()?;
+    let reset = mark_unsynced_since(&tx, since).map_err(AgentError::Config)?;
+    // The offline-queue cap would otherwise delete the re-flagged history on the next capture.
+    if let Some(cap) = crate::retention::load_max_unsynced(&tx) {
+        let pending = crate::retention::count_unsynced(&tx)?;
+        if pending > cap as i64 {
+            return Err(AgentError::Config(format!(
+                "Resync would queue {} reports, over maxUnsyncedReports ({}); raise the cap or pass a later date",
+                pending, cap
+            )));
+        }
+    }
+    tx.commit()?;
+    log::info!("[CloudSync] resync_all: {} report(s) marked unsynced (since {:?})", reset, since);
+    let sync = perform_sync(&db_path)?;
+    Ok(serde_json::json!({
+        "reset": reset,
+        "sync": sync,
+    }))
 }
 
 // Get user session from local config
@@ -125,7 +313,7 @@ pub(crate) fn get_user_session_from_conn(conn: &Connection) -> Option<UserSessio
                         let auth_exp = jwt_exp(auth_token);
                         let us_exp = jwt_exp(&us.access_token);
                         if auth_exp > us_exp {
-                            println!("[Sync] Merging fresher Supabase tokens into user_session (auth_exp={} > us_exp={})", auth_exp, us_exp);
+                            log::info!("[Sync] Merging fresher Supabase tokens into user_session (auth_exp={} > us_exp={})", auth_exp, us_exp);
                             us.access_token = auth_token.to_string();
                             if let Some(rt) = auth["refresh_token"].as_str() {
                                 us.refresh_token = Some(rt.to_string());
@@ -157,7 +345,7 @@ pub(crate) fn get_user_session_from_conn(conn: &Connection) -> Option<UserSessio
                     email: v["user"]["email"].as_str()?.to_string(),
                 })
             } else {
-                println!("[Sync] auth_session is {} (not Supabase), no user_session available", provider);
+                log::info!("[Sync] auth_session is {} (not Supabase), no user_session available", provider);
                 None
             }
         }
@@ -184,15 +372,16 @@ pub fn save_user_session(user_id: String, team_id: Option<String>, access_token:
         [&json]
     ).map_err(|e| e.to_string())?;
     
-    println!("[Sync] User session saved for: {}", session.email);
+    log::info!("[Sync] User session saved for: {}", session.email);
     Ok(())
 }
 
 fn refresh_supabase_token(session: &UserSession) -> Result<UserSession, String> {
     let refresh_token = session.refresh_token.as_ref().ok_or("No refresh token available in session")?;
     
-    println!("[Sync] Attempting token refresh using token starting with: {}...", &refresh_token[..10]);
-    let client = Client::new();
+    // logs land in the app log file: never include token material
+    log::info!("[Sync] Attempting Supabase token refresh");
+    let client = crate::http_client::cloud_client();
     let url = format!("{}/auth/v1/token?grant_type=refresh_token", supabase_url());
     
     let resp = client.post(&url)
@@ -204,7 +393,7 @@ fn refresh_supabase_token(session: &UserSession) -> Result<UserSession, String>
     let status = resp.status();
     if !status.is_success() {
         let err_body = resp.text().unwrap_or_default();
-        println!("[Sync] Refresh failed: {}", err_body);
+        log::warn!("[Sync] Refresh failed: {}", err_body);
         return Err(format!("Refresh failed (HTTP {}): {}", status, err_body));
     }
     
@@ -240,7 +429,7 @@ pub fn clear_user_session() -> Result<(), String> {
         .map_err(|e| e.to_string())?;
     crate::entitlements::clear_entitlements(&conn)?;
     
-    println!("[Sync] User session cleared");
+    log::info!("[Sync] User session cleared");
     Ok(())
 }
 
@@ -252,23 +441,49 @@ pub fn get_current_user() -> Result<Option<UserSession>, String> {
     Ok(get_user_session(&conn))
 }
 
-fn perform_sync(db_path: &std::path::PathBuf) -> Result<String, String> {
+/// Errors: `Busy` while another sync runs, `Database` for local DB failures, `Other` when the
+/// server reports an expired license. Other upload failures are reported per report in the
+/// `SyncResult` instead.
+fn perform_sync(db_path: &std::path::PathBuf) -> AgentResult<SyncResult> {
+    // Background timer and "Sync now" must never upload the same batch twice in parallel.
+    if SYNC_IN_FLIGHT.swap(true, Ordering::SeqCst) {
+        return Err(AgentError::Busy("A sync is already in progress".to_string()));
+    }
+    let _guard = SyncInFlightGuard;
+
+    let mut result = perform_sync_batch(db_path)?;
+    if let Ok(conn) = Connection::open(db_path) {
+        result.pending = crate::retention::count_unsynced(&conn).unwrap_or(0);
+    }
+    Ok(result)
+}
+
+fn perform_sync_batch(db_path: &std::path::PathBuf) -> AgentResult<SyncResult> {
+    if crate::http_client::local_only() {
+        return Ok(SyncResult::skipped(crate::http_client::LOCAL_ONLY_ERR));
+    }
+    if let Some(until) = throttled_until() {
+        return Ok(SyncResult::skipped(format!(
+            "Throttled by server until {} — reports stay queued locally",
+            until.with_timezone(&chrono::Local).format("%H:%M:%S")
+        )));
+    }
     refresh_session_if_expiring(db_path);
 
-    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    let conn = Connection::open(db_path)?;
     
     // Check if user is logged in
     let session = match get_user_session(&conn) {
         Some(s) => s,
         None => {
-            println!("[CloudSync] No user session found. Sync disabled.");
-            return Ok("Not logged in - sync disabled".to_string());
+            log::info!("[CloudSync] No user session found. Sync disabled.");
+            return Ok(SyncResult::skipped("Not logged in - sync disabled"));
         }
     };
 
     if let Err(reason) = crate::entitlements::require_feature(db_path, "sync") {
-        println!("[CloudSync] {}", reason);
-        return Ok(reason);
+        log::info!("[CloudSync] {}", reason);
+        return Ok(SyncResult::skipped(reason));
     }
 
     if let Ok(entitlements) =
@@ -276,12 +491,12 @@ fn perform_sync(db_path: &std::path::PathBuf) -> Result<String, String> {
     {
         let _ = crate::entitlements::save_entitlements(&conn, &entitlements);
         if !entitlements.can_sync {
-            println!("[CloudSync] License inactive — sync disabled.");
-            return Ok("License inactive — sync disabled".to_string());
+            log::info!("[CloudSync] License inactive — sync disabled.");
+            return Ok(SyncResult::skipped("License inactive — sync disabled"));
         }
     }
 
-    println!("[CloudSync] REST base: {}", supabase_url());
+    log::debug!("[CloudSync] REST base: {}", supabase_url());
     
     let batch_limit = std::env::var("FLOWSIGHT_SYNC_BATCH_LIMIT")
         .ok()
@@ -297,13 +512,13 @@ fn perform_sync(db_path: &std::path::PathBuf) -> Result<String, String> {
             |r| r.get::<_, i64>(0),
         )
         .unwrap_or(0);
-    println!(
+    log::info!(
         "[CloudSync] Pending unsynced reports: {} (uploading oldest up to {} rows)",
         total_unsynced, batch_limit
     );
 
     let sql = select_unsynced_pending_sql(batch_limit);
-    let mut stmt = conn.prepare(&sql).map_err(|e| e.to_string())?;
+    let mut stmt = conn.prepare(&sql)?;
 
     let rows = stmt.query_map([], |row| {
         Ok((
@@ -311,13 +526,17 @@ fn perform_sync(db_path: &std::path::PathBuf) -> Result<String, String> {
             row.get::<_, String>(1)?,
             row.get::<_, String>(2)?,
             row.get::<_, i32>(3)?,
-            row.get::<_, Option<String>>(4)?
+            row.get::<_, Option<String>>(4)?,
+            row.get::<_, Option<String>>(5)?,
         ))
-    }).map_err(|e| e.to_string())?;
+    })?;
     
     let mut ids = Vec::new();
     let mut full_text = String::new();
     let mut total_duration = 0;
+    // When the oldest capture in the batch happened — not when the (possibly much later) sync runs.
+    let now = chrono::Utc::now();
+    let mut batch_started_at: Option<chrono::DateTime<chrono::Utc>> = None;
     
     // Aggregations
     let mut categories = std::collections::HashMap::new();
@@ -330,8 +549,11 @@ fn perform_sync(db_path: &std::path::PathBuf) -> Result<String, String> {
         .unwrap_or(SUMMARY_MAX_LINE_CHARS_DEFAULT);
 
     for r in rows {
-        if let Ok((id, desc, cat, dur, ticket)) = r {
+        if let Ok((id, desc, cat, dur, ticket, created_at)) = r {
             ids.push(id);
+            if let Some(at) = created_at.as_deref().and_then(|c| parse_captured_at(c, now)) {
+                batch_started_at = Some(batch_started_at.map_or(at, |b| b.min(at)));
+            }
             let desc = clamp_line_for_summary(&desc, line_cap);
             full_text.push_str(&format!("- [{}] {}\n", cat, desc));
             total_duration += dur;
@@ -347,37 +569,38 @@ fn perform_sync(db_path: &std::path::PathBuf) -> Result<String, String> {
     }
 
     if (total_unsynced as usize) > ids.len() {
-        println!(
+        log::info!(
             "[CloudSync] {} more unsynced report(s) queued after this batch; will upload on the next run.",
             total_unsynced as usize - ids.len()
         );
     }
     
     if ids.is_empty() {
-        println!("[CloudSync] No new reports to sync.");
-        return Ok("No new activity to report.".to_string());
+        log::info!("[CloudSync] No new reports to sync.");
+        return Ok(SyncResult::skipped("No new activity to report."));
     }
 
     // 2. Generate summary with local vision model
-    println!("[CloudSync] Summarizing {} reports...", ids.len());
+    log::info!("[CloudSync] Summarizing {} reports...", ids.len());
     let summary = summarize_with_vision_model(&full_text).unwrap_or_else(|e| {
-        println!("[CloudSync] Summary generation failed: {}", e);
+        log::warn!("[CloudSync] Summary generation failed: {}", e);
         "Summary generation failed".to_string()
     });
-    println!("[CloudSync] Summary generated ({} chars): {:.120}", summary.len(), summary);
+    log::debug!("[CloudSync] Summary generated ({} chars): {:.120}", summary.len(), summary);
     
     // 3. Upload to Supabase with user authentication (retry on JWT expired)
-    let upload_result = upload_session(&session, total_duration, &summary, &categories, &tickets);
+    let started_at = batch_started_at.unwrap_or(now);
+    let upload_result = upload_session(&session, started_at, total_duration, &summary, &categories, &tickets);
     let upload_result = match &upload_result {
-        Err(e) if e.contains("401") || e.contains("PGRST3") => {
-            println!("[CloudSync] Auth error detected ({}), attempting JWT refresh...", e);
-            let conn_refresh = Connection::open(db_path).map_err(|e| e.to_string())?;
+        Err(e) if classify_sync_error(e) == "auth" => {
+            log::warn!("[CloudSync] Auth error detected ({}), attempting JWT refresh...", e);
+            let conn_refresh = Connection::open(db_path)?;
             let session_for_refresh =
                 get_user_session(&conn_refresh).unwrap_or_else(|| session.clone());
             match refresh_supabase_token(&session_for_refresh) {
-                Ok(refreshed) => upload_session(&refreshed, total_duration, &summary, &categories, &tickets),
+                Ok(refreshed) => upload_session(&refreshed, started_at, total_duration, &summary, &categories, &tickets),
                 Err(ref_err) => {
-                    println!("[CloudSync] Token refresh failed: {}", ref_err);
+                    log::warn!("[CloudSync] Token refresh failed: {}", ref_err);
                     upload_result
                 }
             }
@@ -387,7 +610,7 @@ fn perform_sync(db_path: &std::path::PathBuf) -> Result<String, String> {
 
     match upload_result {
         Ok(_) => {
-            println!(
+            log::info!(
                 "[CloudSync] Upload success for {} — in Supabase open public.work_sessions and public.activity_reports (local dev-agent.db table \"reports\" is not uploaded as raw rows).",
                 session.email
             );
@@ -405,19 +628,19 @@ fn perform_sync(db_path: &std::path::PathBuf) -> Result<String, String> {
                 .map(|(t, _)| t.clone());
 
             // Same AI summary as work_sessions — not the raw SQLite log (that stays local only).
-            let activity_body = serde_json::json!({
-                "user_id": session.user_id,
-                "team_id": session.team_id,
-                "description": summary.clone(),
-                "category": primary_category,
-                "jira_ticket_id": primary_jira,
-                "duration_seconds": total_duration,
-                "captured_at": chrono::Utc::now().to_rfc3339()
-            });
+            let activity_body = activity_report_body(
+                &session.user_id,
+                session.team_id.as_deref(),
+                &summary,
+                &primary_category,
+                primary_jira.as_deref(),
+                total_duration,
+                started_at,
+            );
 
             match post_activity_report_with_refresh(db_path, &session, &activity_body) {
-                Ok(()) => println!("[CloudSync] activity_reports: AI window summary saved"),
-                Err(e) => println!(
+                Ok(()) => log::info!("[CloudSync] activity_reports: AI window summary saved"),
+                Err(e) => log::error!(
                     "[CloudSync] activity_reports insert failed (work_sessions row already saved): {}",
                     e
                 ),
@@ -445,7 +668,7 @@ fn perform_sync(db_path: &std::path::PathBuf) -> Result<String, String> {
             let host = base
                 .trim_end_matches('/')
                 .trim_start_matches("https://");
-            println!(
+            log::debug!(
                 "[CloudSync] (testing) {} UTC | {} local capture(s) → {} | work_sessions + activity_reports",
                 chrono::Utc::now().format("%Y-%m-%d %H:%M:%S"),
                 ids.len(),
@@ -453,25 +676,51 @@ fn perform_sync(db_path: &std::path::PathBuf) -> Result<String, String> {
             );
         },
         Err(e) => {
-            if e.contains("License expired") || e.contains("403") {
-                println!("[CloudSync] LICENSE EXPIRED - Sync blocked");
-                return Err("License expired. Contact your PM to renew.".to_string());
+            if classify_sync_error(&e) == "license" {
+                log::warn!("[CloudSync] LICENSE EXPIRED - Sync blocked");
+                return Err(AgentError::Other("License expired. Contact your PM to renew.".to_string()));
             }
             
-            println!("[CloudSync] Upload failed: {}", e);
-            return Ok(format!("(Cloud Upload Failed: {})\n\nLOCAL SUMMARY:\n{}", e, summary));
+            log::warn!("[CloudSync] Upload failed: {}", e);
+            let kind = classify_sync_error(&e);
+            return Ok(SyncResult {
+                message: format!("(Cloud Upload Failed: {})\n\nLOCAL SUMMARY:\n{}", e, summary),
+                synced: 0,
+                failed: ids.len(),
+                pending: 0,
+                reports: ids
+                    .iter()
+                    .map(|&id| ReportSyncOutcome {
+                        id,
+                        ok: false,
+                        error: Some(e.clone()),
+                        error_kind: Some(kind),
+                    })
+                    .collect(),
+            });
         }
     }
     
-    println!("[CloudSync] Processed {} reports.", ids.len());
-    Ok(summary)
+    log::info!("[CloudSync] Processed {} reports.", ids.len());
+    Ok(SyncResult {
+        message: summary,
+        synced: ids.len(),
+        failed: 0,
+        pending: 0,
+        reports: ids
+            .iter()
+            .map(|&id| ReportSyncOutcome {
+                id,
+                ok: true,
+                error: None,
+                error_kind: None,
+            })
+            .collect(),
+    })
 }
 
 fn summarize_with_vision_model(text: &str) -> Result<String, String> {
-    let client = Client::builder()
-        .timeout(Duration::from_secs(60))
-        .build()
-        .map_err(|e| e.to_string())?;
+    let client = crate::http_client::local_client();
 
     let max_chars = std::env::var("FLOWSIGHT_SUMMARY_MAX_CHARS")
         .ok()
@@ -480,7 +729,7 @@ fn summarize_with_vision_model(text: &str) -> Result<String, String> {
 
     let n_chars = text.chars().count();
     if n_chars > max_chars {
-        println!(
+        log::info!(
             "[CloudSync] Truncating summary TASKS from {} to {} Unicode chars (local n_ctx limit)",
             n_chars, max_chars
         );
@@ -506,16 +755,11 @@ fn summarize_with_vision_model(text: &str) -> Result<String, String> {
                 "Local AI server offline — cannot summarize (start Local AI monitoring first)".to_string()
             })?,
         )
+        .timeout(Duration::from_secs(60))
         .json(&body)
         .send()
         .map_err(|e| e.to_string())?;
-    if !resp.status().is_success() {
-        let status = resp.status();
-        let err_body = resp.text().unwrap_or_default();
-        return Err(format!("Summary request failed ({}): {}", status, err_body));
-    }
-
-    let json: serde_json::Value = resp.json().map_err(|e| e.to_string())?;
+    let json = crate::http_client::read_json(resp, "Summary request")?;
     let content = json["choices"][0]["message"]["content"].as_str().unwrap_or("");
     if content.is_empty() {
         return Err("Model returned empty summary.".to_string());
@@ -526,12 +770,13 @@ fn summarize_with_vision_model(text: &str) -> Result<String, String> {
 
 fn upload_session(
     session: &UserSession,
+    started_at: chrono::DateTime<chrono::Utc>,
     duration: i32, 
     summary: &str, 
     categories: &std::collections::HashMap<String, i32>,
     tickets: &std::collections::HashMap<String, i32>
 ) -> Result<(), String> {
-    let client = Client::new();
+    let client = crate::http_client::cloud_client();
     let url = format!("{}/rest/v1/work_sessions", supabase_url()); 
     
     let body = serde_json::json!({
@@ -541,8 +786,8 @@ fn upload_session(
         "summary": summary,
         "category_breakdown": categories,
         "jira_breakdown": tickets,
-        "session_date": chrono::Local::now().format("%Y-%m-%d").to_string(),
-        "created_at": chrono::Utc::now().to_rfc3339()
+        "session_date": started_at.with_timezone(&chrono::Local).format("%Y-%m-%d").to_string(),
+        "created_at": started_at.to_rfc3339()
     });
 
     let resp = client.post(&url)
@@ -554,10 +799,11 @@ fn upload_session(
         .send()
         .map_err(|e| e.to_string())?;
     
+    check_rate_limited(&resp)?;
     let status = resp.status();
     
     if status.as_u16() == 403 {
-        return Err("License expired or invalid".to_string());
+        return Err(LICENSE_INVALID_ERR.to_string());
     }
     
     if !status.is_success() {
@@ -569,7 +815,7 @@ fn upload_session(
 }
 
 fn post_activity_report_row(session: &UserSession, body: &serde_json::Value) -> Result<Response, String> {
-    let client = Client::new();
+    let client = crate::http_client::cloud_client();
     let url = format!("{}/rest/v1/activity_reports", supabase_url());
     client
         .post(&url)
@@ -582,7 +828,9 @@ fn post_activity_report_row(session: &UserSession, body: &serde_json::Value) ->
         .map_err(|e| e.to_string())
 }
 
-/// Retries once with a refreshed JWT if the first POST returns 401/403.
+/// The one path that posts an `activity_reports` row (batch sync and `upload_activity_report`):
+/// retries once with a refreshed JWT if the first POST returns 401/403, records a 429 pause,
+/// and maps 403 to `LICENSE_INVALID_ERR`.
 fn post_activity_report_with_refresh(
     db_path: &std::path::PathBuf,
     session: &UserSession,
@@ -597,9 +845,10 @@ fn post_activity_report_with_refresh(
             }
         }
     }
+    check_rate_limited(&resp)?;
     let status = resp.status();
     if status.as_u16() == 403 {
-        return Err("License expired or invalid".to_string());
+        return Err(LICENSE_INVALID_ERR.to_string());
     }
     if !status.is_success() {
         let t = resp.text().unwrap_or_default();
@@ -614,47 +863,175 @@ pub fn upload_activity_report(
     description: String,
     category: String,
     jira_ticket_id: Option<String>,
-    duration_seconds: i32
-) -> Result<(), String> {
+    duration_seconds: i32,
+    captured_at: Option<String>,
+) -> AgentResult<()> {
     let db_path = crate::paths::db_path()?;
     crate::entitlements::require_feature(&db_path, "sync")?;
-    let conn = Connection::open(&db_path).map_err(|e| e.to_string())?;
+    let conn = Connection::open(&db_path)?;
     
     let session = get_user_session(&conn)
-        .ok_or("Not logged in")?;
-    
-    let body = serde_json::json!({
-        "user_id": session.user_id,
-        "team_id": session.team_id,
-        "description": description,
-        "category": category,
-        "jira_ticket_id": jira_ticket_id,
-        "duration_seconds": duration_seconds,
-        "captured_at": chrono::Utc::now().to_rfc3339()
-    });
-    
-    let resp = post_activity_report_row(&session, &body)?;
-    
-    if resp.status().as_u16() == 403 {
-        return Err("License expired or invalid".to_string());
+        .ok_or(AgentError::NotRegistered)?;
+    if let Some(until) = throttled_until() {
+        return Err(AgentError::Network(format!(
+            "Throttled by server until {}",
+            until.to_rfc3339()
+        )));
     }
     
-    resp.error_for_status()
-        .map_err(|e| e.to_string())?;
-    
+    let captured_at = captured_at
+        .as_deref()
+        .and_then(|c| parse_captured_at(c, chrono::Utc::now()))
+        .unwrap_or_else(chrono::Utc::now);
+    let body = activity_report_body(
+        &session.user_id,
+        session.team_id.as_deref(),
+        &description,
+        &category,
+        jira_ticket_id.as_deref(),
+        duration_seconds,
+        captured_at,
+    );
+
+    post_activity_report_with_refresh(&db_path, &session, &body).map_err(|e| match e.as_str() {
+        LICENSE_INVALID_ERR => AgentError::Config(e),
+        _ => AgentError::Network(e),
+    })
+}
+
+/// `(authenticated, registered)` checks for `test_cloud_connection`, each `{ ok, message }`:
+/// the stored session's JWT is accepted by `/auth/v1/user`, and the user has a `profiles` row
+/// (created by `join_team`) so uploads are attributed to a known developer.
+pub(crate) fn cloud_session_checks(client: &reqwest::blocking::Client) -> (serde_json::Value, serde_json::Value) {
+    let check = |ok: bool, message: String| serde_json::json!({ "ok": ok, "message": message });
+    let not_checked = || check(false, "Not checked (not signed in).".to_string());
+
+    let session = crate::paths::db_path()
+        .ok()
+        .and_then(|p| {
+            refresh_session_if_expiring(&p);
+            Connection::open(&p).ok()
+        })
+        .and_then(|conn| get_user_session(&conn));
+    let Some(session) = session else {
+        return (check(false, "Not signed in.".to_string()), not_checked());
+    };
+
+    let auth = client
+        .get(format!("{}/auth/v1/user", supabase_url()))
+        .header("apikey", supabase_anon_key())
+        .header("Authorization", format!("Bearer {}", session.access_token))
+        .send();
+    let authenticated = match auth {
+        Ok(r) if r.status().is_success() => check(true, format!("Signed in as {}.", session.email)),
+        Ok(r) if matches!(r.status().as_u16(), 401 | 403) => {
+            check(false, "Session rejected by the server — sign in again.".to_string())
+        }
+        Ok(r) => check(false, format!("Auth check failed (HTTP {}).", r.status())),
+        Err(e) => check(false, format!("Auth check failed: {}", e)),
+    };
+    if authenticated["ok"] != true {
+        return (authenticated, not_checked());
+    }
+
+    let profile = client
+        .get(format!(
+            "{}/rest/v1/profiles?id=eq.{}&select=id",
+            supabase_url(),
+            urlencoding::encode(&session.user_id)
+        ))
+        .header("apikey", supabase_anon_key())
+        .header("Authorization", format!("Bearer {}", session.access_token))
+        .send()
+        .map_err(|e| e.to_string())
+        .and_then(|r| crate::http_client::read_json(r, "Profile lookup"));
+    let registered = match profile {
+        Ok(rows) if rows.as_array().is_some_and(|a| !a.is_empty()) => match session.team_id {
+            Some(_) => check(true, "Developer profile found; reports go to your team.".to_string()),
+            None => check(true, "Developer profile found, but no team selected yet.".to_string()),
+        },
+        Ok(_) => check(false, "No developer profile yet — join a team with an invite.".to_string()),
+        Err(e) => check(false, e),
+    };
+    (authenticated, registered)
+}
+
+fn patch_profile_name(session: &UserSession, name: &str) -> Result<Response, String> {
+    crate::http_client::cloud_client()
+        .patch(format!(
+            "{}/rest/v1/profiles?id=eq.{}",
+            supabase_url(),
+            urlencoding::encode(&session.user_id)
+        ))
+        .header("apikey", supabase_anon_key())
+        .header("Authorization", format!("Bearer {}", session.access_token))
+        .header("Content-Type", "application/json")
+        .header("Prefer", "return=representation")
+        .json(&serde_json::json!({ "display_name": name }))
+        .send()
+        .map_err(|e| e.to_string())
+}
+
+/// Writes `name` to the user's `profiles.display_name` right away. The dashboard reads names
+/// from `profiles`, so past and future reports show the new name without waiting for a sync.
+/// Retries once with a refreshed JWT on 401/403.
+pub(crate) fn push_display_name(db_path: &std::path::PathBuf, name: &str) -> AgentResult<()> {
+    crate::http_client::ensure_network_allowed()?;
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(AgentError::Config("Display name cannot be empty".to_string()));
+    }
+    refresh_session_if_expiring(db_path);
+    let conn = Connection::open(db_path)?;
+    let session = get_user_session(&conn).ok_or(AgentError::NotRegistered)?;
+
+    let mut resp = patch_profile_name(&session, name).map_err(AgentError::Network)?;
+    if matches!(resp.status().as_u16(), 401 | 403) {
+        if let Ok(refreshed) = refresh_supabase_token(&session) {
+            resp = patch_profile_name(&refreshed, name).map_err(AgentError::Network)?;
+        }
+    }
+    check_rate_limited(&resp).map_err(AgentError::Network)?;
+    if matches!(resp.status().as_u16(), 401 | 403) {
+        return Err(AgentError::NotRegistered);
+    }
+    let rows = crate::http_client::read_json(resp, "Profile update").map_err(AgentError::Network)?;
+    if rows.as_array().is_some_and(|a| a.is_empty()) {
+        return Err(AgentError::Config(
+            "No developer profile yet — join a team with an invite first.".to_string(),
+        ));
+    }
+    log::info!("[Team] Display name updated in profile");
     Ok(())
 }
 
+/// Saves `name` as `devName` and renames the developer on the dashboard immediately (see
+/// `push_display_name`). The local name is kept even if the push fails; `join_team` sends it.
+#[tauri::command]
+pub fn update_display_name(
+    state: tauri::State<'_, crate::agent::AgentState>,
+    name: String,
+) -> AgentResult<()> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(AgentError::Config("Display name cannot be empty".to_string()));
+    }
+    crate::agent::store_dev_name(&state, name)?;
+    let db_path = crate::paths::db_path()?;
+    push_display_name(&db_path, name)
+}
+
 // Get all teams the current user belongs to
 #[tauri::command]
-pub fn get_user_teams() -> Result<serde_json::Value, String> {
+pub fn get_user_teams() -> AgentResult<serde_json::Value> {
+    crate::http_client::ensure_network_allowed()?;
     let db_path = crate::paths::db_path()?;
-    let conn = Connection::open(&db_path).map_err(|e| e.to_string())?;
+    let conn = Connection::open(&db_path)?;
     
     let session = get_user_session(&conn)
-        .ok_or("Not logged in")?;
+        .ok_or(AgentError::NotRegistered)?;
     
-    let client = Client::new();
+    let client = crate::http_client::cloud_client();
     let mut current_token = session.access_token.clone();
     
     // Fetch team memberships from Supabase
@@ -666,8 +1043,7 @@ pub fn get_user_teams() -> Result<serde_json::Value, String> {
     let mut resp = client.get(&url)
         .header("apikey", supabase_anon_key())
         .header("Authorization", format!("Bearer {}", current_token))
-        .send()
-        .map_err(|e| e.to_string())?;
+        .send()?;
     
     // Retry on 401/403
     if resp.status().as_u16() == 401 || resp.status().as_u16() == 403 {
@@ -676,18 +1052,17 @@ pub fn get_user_teams() -> Result<serde_json::Value, String> {
             resp = client.get(&url)
                 .header("apikey", supabase_anon_key())
                 .header("Authorization", format!("Bearer {}", current_token))
-                .send()
-                .map_err(|e| e.to_string())?;
+                .send()?;
         }
     }
     
     let status = resp.status();
     if !status.is_success() {
         let body = resp.text().unwrap_or_default();
-        return Err(format!("Failed to fetch teams (HTTP {}): {}", status, body));
+        return Err(AgentError::Network(format!("Failed to fetch teams (HTTP {}): {}", status, body)));
     }
     
-    let teams: Vec<serde_json::Value> = resp.json().map_err(|e| e.to_string())?;
+    let teams: Vec<serde_json::Value> = resp.json()?;
 
     // Auto-elegir primer team si no hay uno activo persistido todavía.
     // Evita que activity_reports/work_sessions suban con team_id=NULL cuando el
@@ -701,7 +1076,7 @@ pub fn get_user_teams() -> Result<serde_json::Value, String> {
                 .first()
                 .and_then(|t| t["team_id"].as_str().map(|s| s.to_string()));
             if let Some(id) = first.clone() {
-                println!("[Team] No active team in session; auto-selecting first membership: {}", id);
+                log::info!("[Team] No active team in session; auto-selecting first membership: {}", id);
                 save_user_session(
                     session.user_id.clone(),
                     Some(id.clone()),
@@ -714,7 +1089,7 @@ pub fn get_user_teams() -> Result<serde_json::Value, String> {
         }
     };
 
-    println!("[Team] Found {} team memberships, active: {:?}", teams.len(), active_team_id);
+    log::info!("[Team] Found {} team memberships, active: {:?}", teams.len(), active_team_id);
 
     Ok(serde_json::json!({
         "teams": teams,
@@ -724,14 +1099,14 @@ pub fn get_user_teams() -> Result<serde_json::Value, String> {
 
 // Set the active team for the current user (persists to SQLite)
 #[tauri::command]
-pub fn set_active_team(team_id: String) -> Result<(), String> {
+pub fn set_active_team(team_id: String) -> AgentResult<()> {
     let db_path = crate::paths::db_path()?;
-    let conn = Connection::open(&db_path).map_err(|e| e.to_string())?;
+    let conn = Connection::open(&db_path)?;
     
     let session = get_user_session(&conn)
-        .ok_or("Not logged in")?;
+        .ok_or(AgentError::NotRegistered)?;
     
-    println!("[Team] Setting active team to: {}", team_id);
+    log::info!("[Team] Setting active team to: {}", team_id);
     
     save_user_session(
         session.user_id,
@@ -740,60 +1115,67 @@ pub fn set_active_team(team_id: String) -> Result<(), String> {
         session.refresh_token,
         session.email
     )
+    .map_err(AgentError::from)
 }
 
 // Join a team using an invitation token
 #[tauri::command]
-pub fn join_team(token: String) -> Result<serde_json::Value, String> {
+pub fn join_team(token: String) -> AgentResult<serde_json::Value> {
     let db_path = crate::paths::db_path()?;
     crate::entitlements::require_feature(&db_path, "sync")?;
     refresh_session_if_expiring(&db_path);
 
-    let conn = Connection::open(&db_path).map_err(|e| e.to_string())?;
+    let conn = Connection::open(&db_path)?;
     
     let mut session = get_user_session(&conn)
-        .ok_or("Not logged in. Please sign in first.")?;
+        .ok_or(AgentError::NotRegistered)?;
     
-    let client = Client::new();
+    let client = crate::http_client::cloud_client();
     let mut current_token = session.access_token.clone();
     
     // 1. Fetch current user info (Retry on 401)
-    println!("[Team] Fetching user info for profile sync...");
+    log::info!("[Team] Fetching user info for profile sync...");
     let mut user_resp = client.get(format!("{}/auth/v1/user", supabase_url()))
         .header("apikey", supabase_anon_key())
         .header("Authorization", format!("Bearer {}", current_token))
-        .send()
-        .map_err(|e| e.to_string())?;
+        .send()?;
         
     if user_resp.status().as_u16() == 401 || user_resp.status().as_u16() == 403 {
-        println!("[Team] JWT might be expired (HTTP {}), attempting refresh...", user_resp.status());
+        log::warn!("[Team] JWT might be expired (HTTP {}), attempting refresh...", user_resp.status());
         if let Ok(new_s) = refresh_supabase_token(&session) {
             session = new_s;
             current_token = session.access_token.clone();
             user_resp = client.get(format!("{}/auth/v1/user", supabase_url()))
                 .header("apikey", supabase_anon_key())
                 .header("Authorization", format!("Bearer {}", current_token))
-                .send()
-                .map_err(|e| e.to_string())?;
+                .send()?;
         }
     }
     
     if !user_resp.status().is_success() {
         let err_body = user_resp.text().unwrap_or_else(|_| "Empty body".to_string());
-        return Err(format!(
-            "Your session has expired. Please sign out and sign in again. ({})",
-            err_body
-        ));
+        log::warn!("[Team] Session rejected by auth server: {}", err_body);
+        return Err(AgentError::NotRegistered);
     }
     
-    let user_json: serde_json::Value = user_resp.json().map_err(|e| e.to_string())?;
+    let user_json: serde_json::Value = user_resp.json()?;
     let meta = &user_json["user_metadata"];
-    let display_name = meta["full_name"].as_str().or(meta["name"].as_str()).unwrap_or("User");
+    // a devName set in the app wins over the OAuth name, or this upsert would undo a rename
+    let configured_name: Option<String> = conn
+        .query_row("SELECT value FROM config WHERE key = 'dev_name'", [], |r| r.get(0))
+        .ok()
+        .filter(|n: &String| !n.trim().is_empty());
+    let display_name = configured_name
+        .as_deref()
+        .map(str::trim)
+        .or(meta["full_name"].as_str())
+        .or(meta["name"].as_str())
+        .unwrap_or("User");
     let avatar_url = meta["avatar_url"].as_str();
     
     let user_id_from_jwt_owned = user_json["id"].as_str().unwrap_or(&session.user_id).to_string();
     let user_id_from_jwt = &user_id_from_jwt_owned;
-    println!("[Team] Syncing profile for user {} (JWT id: {})", session.user_id, user_id_from_jwt);
+    log::info!("[Team] Syncing profile for user {} (JWT id: {})", session.user_id, user_id_from_jwt);
     
     // 2. Ensure profile exists (Upsert)
     let profile_url = format!("{}/rest/v1/profiles", supabase_url());
@@ -812,32 +1194,30 @@ pub fn join_team(token: String) -> Result<serde_json::Value, String> {
         
     match prof_resp {
         Ok(r) if !r.status().is_success() => {
-            println!("[Team] Profile upsert failed (HTTP {}): {}", r.status(), r.text().unwrap_or_default());
+            log::warn!("[Team] Profile upsert failed (HTTP {}): {}", r.status(), r.text().unwrap_or_default());
         }
-        Err(e) => println!("[Team] Profile upsert request error: {}", e),
-        _ => println!("[Team] Profile synced successfully"),
+        Err(e) => log::warn!("[Team] Profile upsert request error: {}", e),
+        _ => log::info!("[Team] Profile synced successfully"),
     }
 
     // 3. Verify invitation (Retry on 401)
-    println!("[Team] Validating invitation token: {}", token);
+    log::info!("[Team] Validating invitation token");
     let inv_url = format!("{}/rest/v1/invitations?token=eq.{}&select=team_id,expires_at,used_at,created_by,email", supabase_url(), token);
     
     let mut inv_resp = client.get(&inv_url)
         .header("apikey", supabase_anon_key())
         .header("Authorization", format!("Bearer {}", current_token))
-        .send()
-        .map_err(|e| e.to_string())?;
+        .send()?;
         
     if inv_resp.status().as_u16() == 401 || inv_resp.status().as_u16() == 403 {
-        println!("[Team] Invitation request unauthorized, attempting refresh with latest session...");
+        log::warn!("[Team] Invitation request unauthorized, attempting refresh with latest session...");
         if let Ok(new_s) = refresh_supabase_token(&session) {
             session = new_s;
             current_token = session.access_token.clone();
             inv_resp = client.get(&inv_url)
                 .header("apikey", supabase_anon_key())
                 .header("Authorization", format!("Bearer {}", current_token))
-                .send()
-                .map_err(|e| e.to_string())?;
+                .send()?;
         }
     }
     
@@ -845,30 +1225,30 @@ pub fn join_team(token: String) -> Result<serde_json::Value, String> {
     if !inv_status.is_success() {
         let err_body = inv_resp.text().unwrap_or_else(|_| "Empty body".to_string());
         if err_body.contains("JWT expired") || err_body.contains("PGRST303") {
-            return Err(
-                "Your session has expired. Please sign out, sign in again, then join the team."
-                    .to_string(),
-            );
+            return Err(AgentError::NotRegistered);
         }
-        return Err(format!("Error validating invitation (HTTP {}): {}", inv_status, err_body));
+        return Err(AgentError::Network(format!(
+            "Error validating invitation (HTTP {}): {}",
+            inv_status, err_body
+        )));
     }
     
-    let invitations: Vec<serde_json::Value> = inv_resp.json().map_err(|e| e.to_string())?;
+    let invitations: Vec<serde_json::Value> = inv_resp.json()?;
     let invitation = invitations.get(0).ok_or("Invalid invitation token")?;
     
-    println!("[Team] Invitation details: {:?}", invitation);
+    log::debug!("[Team] Invitation details: {:?}", invitation);
     let inv_email = invitation["email"].as_str();
-    println!("[Team] Analyzing match: Session Email '{}' vs Invitation Email '{:?}'", session.email, inv_email);
+    log::debug!("[Team] Analyzing match: Session Email '{}' vs Invitation Email '{:?}'", session.email, inv_email);
     
     if !invitation["used_at"].is_null() {
-        return Err("This invitation has already been used".to_string());
+        return Err(AgentError::Config("This invitation has already been used".to_string()));
     }
     
     let team_id = invitation["team_id"].as_str().ok_or("Malformed invitation (missing team_id)")?;
     let _inviter_id = invitation["created_by"].as_str();
     
     // 4. Add to team_members (Retry on 401)
-    println!("[Team] Adding user {} to team {} (role: member, omitting invited_by)", user_id_from_jwt, team_id);
+    log::info!("[Team] Adding user {} to team {} (role: member, omitting invited_by)", user_id_from_jwt, team_id);
     let member_url = format!("{}/rest/v1/team_members", supabase_url());
     let member_body = serde_json::json!({
         "team_id": team_id,
@@ -883,8 +1263,7 @@ pub fn join_team(token: String) -> Result<serde_json::Value, String> {
         .header("Content-Type", "application/json")
         .header("Prefer", "return=minimal")
         .json(&member_body)
-        .send()
-        .map_err(|e| e.to_string())?;
+        .send()?;
     
     if member_resp.status().as_u16() == 401 || member_resp.status().as_u16() == 403 {
         if let Ok(new_s) = refresh_supabase_token(&session) {
@@ -896,8 +1275,7 @@ pub fn join_team(token: String) -> Result<serde_json::Value, String> {
                 .header("Content-Type", "application/json")
                 .header("Prefer", "return=minimal")
                 .json(&member_body)
-                .send()
-                .map_err(|e| e.to_string())?;
+                .send()?;
         }
     }
         
@@ -907,7 +1285,7 @@ pub fn join_team(token: String) -> Result<serde_json::Value, String> {
         if err_text.contains("unique_team_user") || err_text.contains("duplicate") {
             // Already a member
         } else {
-            return Err(format!("Failed to join team: {}", err_text));
+            return Err(AgentError::Network(format!("Failed to join team: {}", err_text)));
         }
     }
     
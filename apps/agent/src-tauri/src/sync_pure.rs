@@ -6,7 +6,7 @@ use base64::Engine;
 pub(crate) fn select_unsynced_pending_sql(limit: usize) -> String {
     let lim = limit.max(1).min(5000);
     format!(
-        "SELECT id, description, activity_type, duration_seconds, jira_ticket_id \
+        "SELECT id, description, activity_type, duration_seconds, jira_ticket_id, created_at \
          FROM reports \
          WHERE synced = 0 \
          ORDER BY id ASC \
@@ -15,6 +15,85 @@ pub(crate) fn select_unsynced_pending_sql(limit: usize) -> String {
     )
 }
 
+/// `resync_all`'s `since` (`YYYY-MM-DD`, local date; `None` = everything).
+pub(crate) fn parse_resync_since(since: Option<&str>) -> Result<Option<chrono::NaiveDate>, String> {
+    since
+        .map(|s| {
+            chrono::NaiveDate::parse_from_str(s.trim(), "%Y-%m-%d")
+                .map_err(|_| format!("Invalid date '{}' (expected YYYY-MM-DD)", s))
+        })
+        .transpose()
+}
+
+/// Local midnight of `since` in UTC, for filtering cloud `captured_at` values.
+pub(crate) fn local_midnight_utc(since: chrono::NaiveDate) -> chrono::DateTime<chrono::Utc> {
+    use chrono::TimeZone;
+    let midnight = since.and_hms_opt(0, 0, 0).unwrap_or_default();
+    chrono::Local
+        .from_local_datetime(&midnight)
+        .earliest()
+        .map(|t| t.with_timezone(&chrono::Utc))
+        .unwrap_or_else(|| midnight.and_utc())
+}
+
+/// Flags reports captured on or after local date `since` (`None` = all) as unsynced again.
+/// Returns how many rows were flipped.
+pub(crate) fn mark_unsynced_since(
+    conn: &rusqlite::Connection,
+    since: Option<chrono::NaiveDate>,
+) -> Result<usize, String> {
+    let since = since.map(|d| d.format("%Y-%m-%d").to_string());
+    conn.execute(
+        "UPDATE reports SET synced = 0
+         WHERE synced != 0 AND (?1 IS NULL OR date(created_at, 'localtime') >= ?1)",
+        [since],
+    )
+    .map_err(|e| e.to_string())
+}
+
+/// Clock skew tolerated before a capture time counts as "in the future" (bad clock / bad row).
+const CAPTURED_AT_MAX_FUTURE_SECS: i64 = 300;
+
+/// `reports.created_at` (UTC `YYYY-MM-DD HH:MM:SS`, or RFC 3339) as the cloud capture time.
+/// `None` for unparseable or future values; callers then fall back to `now`.
+pub(crate) fn parse_captured_at(
+    raw: &str,
+    now: chrono::DateTime<chrono::Utc>,
+) -> Option<chrono::DateTime<chrono::Utc>> {
+    use chrono::{DateTime, NaiveDateTime, Utc};
+    let raw = raw.trim();
+    let at = NaiveDateTime::parse_from_str(raw, "%Y-%m-%d %H:%M:%S")
+        .map(|t| t.and_utc())
+        .or_else(|_| DateTime::parse_from_rfc3339(raw).map(|t| t.with_timezone(&Utc)))
+        .ok()?;
+    (at <= now + chrono::Duration::seconds(CAPTURED_AT_MAX_FUTURE_SECS)).then_some(at)
+}
+
+/// Pause used when a 429 has no (or an unparseable) `Retry-After`.
+const RETRY_AFTER_DEFAULT_SECS: i64 = 60;
+/// Upper bound so a bogus header cannot stop uploads for days.
+const RETRY_AFTER_MAX_SECS: i64 = 3600;
+
+/// When uploads may resume after a 429: `Retry-After` as delta-seconds or an HTTP-date,
+/// clamped to `RETRY_AFTER_MAX_SECS`.
+pub(crate) fn retry_after_until(
+    header: Option<&str>,
+    now: chrono::DateTime<chrono::Utc>,
+) -> chrono::DateTime<chrono::Utc> {
+    let secs = header
+        .map(str::trim)
+        .and_then(|h| {
+            h.parse::<i64>().ok().or_else(|| {
+                chrono::DateTime::parse_from_rfc2822(h)
+                    .ok()
+                    .map(|t| (t.with_timezone(&chrono::Utc) - now).num_seconds())
+            })
+        })
+        .unwrap_or(RETRY_AFTER_DEFAULT_SECS)
+        .clamp(0, RETRY_AFTER_MAX_SECS);
+    now + chrono::Duration::seconds(secs)
+}
+
 pub(crate) fn jwt_exp(token: &str) -> i64 {
     let parts: Vec<&str> = token.split('.').collect();
     if parts.len() < 2 {
@@ -56,11 +135,74 @@ pub(crate) fn truncate_tasks_for_summary(text: &str, max_chars: usize) -> String
     format!("{}{}", OMIT, suffix)
 }
 
+/// Error for a 403 from the cloud tables; `classify_sync_error` maps it to `"license"`.
+pub(crate) const LICENSE_INVALID_ERR: &str = "License expired or invalid";
+
+/// One `activity_reports` row, built the same way for the batch sync and `upload_activity_report`.
+pub(crate) fn activity_report_body(
+    user_id: &str,
+    team_id: Option<&str>,
+    description: &str,
+    category: &str,
+    jira_ticket_id: Option<&str>,
+    duration_seconds: i32,
+    captured_at: chrono::DateTime<chrono::Utc>,
+) -> serde_json::Value {
+    serde_json::json!({
+        "user_id": user_id,
+        "team_id": team_id,
+        "description": description,
+        "category": category,
+        "jira_ticket_id": jira_ticket_id,
+        "duration_seconds": duration_seconds,
+        "captured_at": captured_at.to_rfc3339()
+    })
+}
+
+/// Coarse reason for a failed upload, so the UI can tell "retry later" from "fix something".
+/// Matches the error strings produced by `upload_session` / reqwest.
+/// Status code of an `"HTTP <code> ..."` error from the upload helpers, if it has one. Digits
+/// elsewhere in the message (timestamps, ids) are not status codes.
+pub(crate) fn http_error_status(err: &str) -> Option<u16> {
+    let code = err.strip_prefix("HTTP ")?;
+    code.get(..3)?.parse().ok()
+}
+
+pub(crate) fn classify_sync_error(err: &str) -> &'static str {
+    let status = http_error_status(err);
+    if err.starts_with(LICENSE_INVALID_ERR) || err.contains("License expired") || status == Some(403) {
+        "license"
+    } else if status == Some(429) {
+        "throttled"
+    } else if status == Some(401) || err.contains("PGRST3") || err.contains("JWT") {
+        "auth"
+    } else if status == Some(413) || err.to_lowercase().contains("too large") {
+        "too_large"
+    } else if err.starts_with("HTTP ") {
+        "rejected"
+    } else {
+        "network"
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use rusqlite::Connection;
 
+    #[test]
+    fn activity_report_body_shape() {
+        let at = chrono::DateTime::parse_from_rfc3339("2026-03-02T10:00:00Z")
+            .unwrap()
+            .with_timezone(&chrono::Utc);
+        let body = activity_report_body("u1", None, "Summary", "Coding", Some("FS-1"), 90, at);
+        assert_eq!(body["team_id"], serde_json::Value::Null);
+        assert_eq!(body["jira_ticket_id"], "FS-1");
+        assert_eq!(body["duration_seconds"], 90);
+        assert_eq!(body["captured_at"], "2026-03-02T10:00:00+00:00");
+        assert_eq!(classify_sync_error(LICENSE_INVALID_ERR), "license");
+    }
+
     fn jwt_with_exp(exp: i64) -> String {
         let header = base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(r#"{"alg":"none"}"#);
         let payload = serde_json::json!({ "exp": exp });
@@ -104,6 +246,78 @@ mod tests {
         assert!(t.ends_with('…'));
     }
 
+    #[test]
+    fn classify_sync_error_kinds() {
+        assert_eq!(classify_sync_error("error sending request for url (https://x): timed out"), "network");
+        assert_eq!(classify_sync_error("HTTP 401 Unauthorized: JWT expired"), "auth");
+        assert_eq!(classify_sync_error("HTTP 413 Payload Too Large: "), "too_large");
+        assert_eq!(classify_sync_error("HTTP 400 Bad Request: invalid input"), "rejected");
+        assert_eq!(classify_sync_error("License expired or invalid"), "license");
+        assert_eq!(classify_sync_error("HTTP 429 Too Many Requests: slow down"), "throttled");
+        // a throttle deadline whose digits contain "401" is still a throttle, not an auth error
+        assert_eq!(
+            classify_sync_error("HTTP 429 Too Many Requests: throttled by server until 2026-04-01T10:40:01Z"),
+            "throttled"
+        );
+        assert_eq!(classify_sync_error("error sending request: id 4013 reset"), "network");
+        assert_eq!(http_error_status("HTTP 503 Service Unavailable: "), Some(503));
+        assert_eq!(http_error_status("Refresh failed (HTTP 401)"), None);
+    }
+
+    #[test]
+    fn retry_after_seconds_date_and_default() {
+        let now = chrono::DateTime::parse_from_rfc3339("2026-03-02T12:00:00Z")
+            .unwrap()
+            .with_timezone(&chrono::Utc);
+        assert_eq!(retry_after_until(Some("120"), now).to_rfc3339(), "2026-03-02T12:02:00+00:00");
+        assert_eq!(
+            retry_after_until(Some("Mon, 02 Mar 2026 12:05:00 GMT"), now).to_rfc3339(),
+            "2026-03-02T12:05:00+00:00"
+        );
+        assert_eq!(retry_after_until(None, now).to_rfc3339(), "2026-03-02T12:01:00+00:00");
+        assert_eq!(retry_after_until(Some("999999"), now).to_rfc3339(), "2026-03-02T13:00:00+00:00");
+    }
+
+    #[test]
+    fn captured_at_accepts_sqlite_and_rfc3339_rejects_future() {
+        let now = chrono::DateTime::parse_from_rfc3339("2026-03-02T12:00:00Z")
+            .unwrap()
+            .with_timezone(&chrono::Utc);
+        assert_eq!(
+            parse_captured_at("2026-03-02 08:15:00", now).unwrap().to_rfc3339(),
+            "2026-03-02T08:15:00+00:00"
+        );
+        assert_eq!(
+            parse_captured_at("2026-03-02T09:15:00+01:00", now).unwrap().to_rfc3339(),
+            "2026-03-02T08:15:00+00:00"
+        );
+        assert!(parse_captured_at("2026-03-03 08:00:00", now).is_none());
+        assert!(parse_captured_at("yesterday", now).is_none());
+    }
+
+    #[test]
+    fn mark_unsynced_since_resets_only_newer_rows() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE reports (id INTEGER PRIMARY KEY, synced INTEGER DEFAULT 0, created_at TEXT);
+             INSERT INTO reports (synced, created_at) VALUES (1, datetime('now', '-10 days'));
+             INSERT INTO reports (synced, created_at) VALUES (1, datetime('now', '-1 days'));
+             INSERT INTO reports (synced, created_at) VALUES (0, datetime('now'));",
+        )
+        .unwrap();
+        let since = (chrono::Local::now().date_naive() - chrono::Duration::days(3))
+            .format("%Y-%m-%d")
+            .to_string();
+        let since = parse_resync_since(Some(&since)).unwrap();
+        assert_eq!(mark_unsynced_since(&conn, since).unwrap(), 1);
+        assert_eq!(mark_unsynced_since(&conn, None).unwrap(), 1);
+        assert!(parse_resync_since(Some("last week")).is_err());
+        assert_eq!(parse_resync_since(None), Ok(None));
+        let day = since.unwrap();
+        let midnight = local_midnight_utc(day).with_timezone(&chrono::Local);
+        assert_eq!((midnight.date_naive(), midnight.time()), (day, chrono::NaiveTime::MIN));
+    }
+
     #[test]
     fn pending_query_selects_all_unsynced_oldest_first() {
         let conn = Connection::open_in_memory().unwrap();
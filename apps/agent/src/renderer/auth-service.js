@@ -197,6 +197,12 @@ export async function signInForCloudFeatures(email, password) {
 /** @deprecated Use signInForCloudFeatures */
 export const signInWorker = signInForCloudFeatures;
 
+// localOnly: supabase-js must not refresh tokens against the cloud in the background.
+export function setCloudAutoRefresh(enabled) {
+  const { auth } = getSupabaseClient();
+  return enabled ? auth.startAutoRefresh() : auth.stopAutoRefresh();
+}
+
 export async function signOutWorker() {
   const supabase = getSupabaseClient();
   await supabase.auth.signOut();